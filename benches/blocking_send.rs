@@ -0,0 +1,63 @@
+//! Measures the per-send overhead of sending from a synchronous producer
+//! thread, comparing [`BlockingSender`], which caches its parking machinery,
+//! against [`block_on_send`], which sets it up fresh for every send.
+//!
+//! Run with `cargo bench --features blocking --bench blocking_send`.
+
+use std::{
+    hint::black_box,
+    thread,
+    time::{Duration, Instant},
+};
+
+use handoff::{block_on_recv, block_on_send, channel, BlockingSender, Sender};
+
+const ITEMS: u32 = 200_000;
+
+/// Send `ITEMS` items with `send` to a receiver on another thread, and return
+/// the average time per item.
+fn measure(send: impl FnOnce(Sender<u32>)) -> Duration {
+    let (sender, mut receiver) = channel();
+
+    let receiver_thread = thread::spawn(move || {
+        while let Some(item) = block_on_recv(&mut receiver) {
+            black_box(item);
+        }
+    });
+
+    let start = Instant::now();
+    send(sender);
+    let elapsed = start.elapsed();
+
+    receiver_thread.join().expect("receiver panicked");
+    elapsed / ITEMS
+}
+
+fn fresh_parking(mut sender: Sender<u32>) {
+    for i in 0..ITEMS {
+        block_on_send(&mut sender, i).expect("receiver disconnected");
+    }
+}
+
+fn cached_parking(sender: Sender<u32>) {
+    let mut sender: BlockingSender<u32> = sender.into_blocking();
+
+    for i in 0..ITEMS {
+        sender.send_blocking(i).expect("receiver disconnected");
+    }
+}
+
+fn main() {
+    // Warm up both paths before measuring
+    measure(fresh_parking);
+    measure(cached_parking);
+
+    println!(
+        "block_on_send:                 {:?} / send",
+        measure(fresh_parking)
+    );
+    println!(
+        "BlockingSender::send_blocking: {:?} / send",
+        measure(cached_parking)
+    );
+}
@@ -0,0 +1,78 @@
+//! Compares the sender's reclaim strategies, configured with
+//! [`Builder::reclaim_spins`], when both halves busy-poll on their own
+//! threads.
+//!
+//! Busy-polling maximizes how often the sender is polled while the receiver
+//! is in the middle of taking its item, which is the only time the reclaim
+//! strategy matters. For the low-latency case the strategies are designed
+//! for, pin the process to two sibling hyperthreads, for instance with
+//! `taskset -c 0,1` on Linux. With only one core, both threads spend most
+//! of their time waiting to be scheduled, and the results are meaningless.
+//!
+//! Run with `cargo bench --bench reclaim`.
+
+use std::{
+    future::Future,
+    hint::black_box,
+    pin::pin,
+    task::{Context, Poll, Waker},
+    thread,
+    time::{Duration, Instant},
+};
+
+use futures::StreamExt;
+use handoff::Builder;
+
+const ITEMS: u32 = 100_000;
+
+/// Hand off `ITEMS` items between two busy-polling threads, and return the
+/// average time per item.
+fn busy_polling(spins: u32) -> Duration {
+    let (mut sender, mut receiver) = Builder::new().reclaim_spins(spins).channel();
+
+    let receiver_thread = thread::spawn(move || {
+        let mut cx = Context::from_waker(Waker::noop());
+
+        loop {
+            match receiver.poll_next_unpin(&mut cx) {
+                Poll::Ready(Some(item)) => drop(black_box(item)),
+                Poll::Ready(None) => break,
+                Poll::Pending => std::hint::spin_loop(),
+            }
+        }
+    });
+
+    let mut cx = Context::from_waker(Waker::noop());
+    let start = Instant::now();
+
+    for i in 0..ITEMS {
+        let mut send = pin!(sender.send(i));
+
+        while send.as_mut().poll(&mut cx).is_pending() {
+            std::hint::spin_loop();
+        }
+    }
+
+    let elapsed = start.elapsed();
+
+    drop(sender);
+    receiver_thread.join().expect("receiver panicked");
+    elapsed / ITEMS
+}
+
+fn main() {
+    let strategies = [
+        ("yield (0 spins)", 0),
+        ("backoff (128 spins)", 128),
+        ("pure spin", u32::MAX),
+    ];
+
+    // Warm up before measuring
+    for (_, spins) in strategies {
+        busy_polling(spins);
+    }
+
+    for (name, spins) in strategies {
+        println!("{name:20} {:?} / item", busy_polling(spins));
+    }
+}
@@ -0,0 +1,161 @@
+/*!
+Sends that report the receiver's sequence number for the delivered item.
+*/
+
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::atomic::Ordering::Relaxed,
+    task::{Context, Poll},
+};
+
+use futures_util::ready;
+use pin_project::pin_project;
+
+use crate::{SendError, SendFut, Sender};
+
+/// Proof that the receiver took a specific item, returned by
+/// [`Sender::send_acked`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Ack {
+    seq: u64,
+}
+
+impl Ack {
+    /// The sequence number of the item among all the items the receiver has
+    /// taken from this channel, starting at 0. Peeking an item counts as
+    /// taking it.
+    #[inline]
+    #[must_use]
+    pub fn seq(&self) -> u64 {
+        self.seq
+    }
+}
+
+impl<T> Sender<T> {
+    /// Asynchronously send an item to the receiver, and get an [`Ack`] with
+    /// the sequence number the receiver assigned to the item when it took it.
+    ///
+    /// This behaves exactly like [`send`][Sender::send]. Because the receiver
+    /// counts every item it takes, including items from sends that were
+    /// dropped after the receiver took them, comparing acks with the
+    /// receiver's own count is useful for end-to-end flow accounting.
+    #[inline]
+    pub fn send_acked(&mut self, item: T) -> SendAckedFut<'_, T> {
+        SendAckedFut {
+            send: self.send(item),
+            seq: None,
+        }
+    }
+}
+
+/// Future for the [`send_acked`][Sender::send_acked] method. See its
+/// documentation for details.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+#[pin_project]
+#[derive(Debug)]
+pub struct SendAckedFut<'a, T> {
+    #[pin]
+    send: SendFut<'a, T>,
+
+    // The receiver's count of taken items, as of when we first staged our
+    // item. Since only one item is staged at a time, no other item can be
+    // taken between then and when our item is taken, so this is our item's
+    // sequence number.
+    seq: Option<u64>,
+}
+
+impl<T> Future for SendAckedFut<'_, T> {
+    type Output = Result<Ack, SendError<T>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+
+        // If the lock fails, the item was never staged, so the send below
+        // will fail without needing a sequence number.
+        if this.seq.is_none() {
+            if let Some(lock) = this.send.inner.lock() {
+                *this.seq = Some(lock.taken_count.load(Relaxed));
+            }
+        }
+
+        ready!(this.send.poll(cx))?;
+
+        let seq = this
+            .seq
+            .expect("a send can't succeed without being staged first");
+
+        Poll::Ready(Ok(Ack { seq }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        future::Future,
+        pin::pin,
+        task::{Context, Poll, Waker},
+    };
+
+    use cool_asserts::assert_matches;
+    use futures::{FutureExt, StreamExt};
+
+    use crate::{channel, SendError};
+
+    #[tokio::test]
+    async fn acks_are_sequential() {
+        let (mut sender, receiver) = channel();
+
+        let sender_task = tokio::task::spawn(async move {
+            let mut seqs = Vec::new();
+            for i in 0..5 {
+                seqs.push(sender.send_acked(i).await.unwrap().seq());
+            }
+            seqs
+        });
+
+        let items: Vec<i32> = receiver.collect().await;
+        assert_eq!(items, [0, 1, 2, 3, 4]);
+        assert_eq!(sender_task.await.unwrap(), [0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn dropped_sends_are_counted() {
+        let (mut sender, mut receiver) = channel();
+        let mut cx = Context::from_waker(Waker::noop());
+
+        // Taken by the receiver, but the sender never learns about it
+        {
+            let mut send = pin!(sender.send(1));
+            assert!(send.as_mut().poll(&mut cx).is_pending());
+            assert_eq!(receiver.try_recv(), Ok(1));
+        }
+
+        // Peeked, which counts as taken, and then received
+        {
+            let mut send = pin!(sender.send_acked(2));
+            assert!(send.as_mut().poll(&mut cx).is_pending());
+            assert_eq!(receiver.poll_peek(&mut cx), Poll::Ready(Some(&2)));
+            assert!(send.as_mut().poll(&mut cx).is_pending());
+            assert_eq!(receiver.try_recv(), Ok(2));
+            assert_matches!(send.poll(&mut cx), Poll::Ready(Ok(ack)) => {
+                assert_eq!(ack.seq(), 1);
+            });
+        }
+
+        let mut send = pin!(sender.send_acked(3));
+        assert!(send.as_mut().poll(&mut cx).is_pending());
+        assert_eq!(receiver.try_recv(), Ok(3));
+        assert_matches!(send.poll(&mut cx), Poll::Ready(Ok(ack)) => {
+            assert_eq!(ack.seq(), 2);
+        });
+    }
+
+    #[test]
+    fn disconnected() {
+        let (mut sender, receiver) = channel();
+        drop(receiver);
+
+        assert_matches!(sender.send_acked(1).now_or_never(), Some(Err(SendError(1))));
+    }
+}
@@ -0,0 +1,370 @@
+/*!
+Sending an item through whichever of several channels is ready first.
+*/
+
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::atomic::Ordering::Relaxed,
+    task::{Context, Poll},
+};
+
+use futures_util::ready;
+use pin_project::{pin_project, pinned_drop};
+
+use crate::{SendError, SendFut, Sender};
+
+/// Send `item` through whichever of `senders` has a receiver ready for it
+/// first, resolving to that sender's index once the item is taken.
+///
+/// A receiver is ready once it's waiting for an item: for instance, when a
+/// [`recv`][crate::Receiver::recv] is pending. A receiver that was already
+/// waiting when this started is woken once to confirm that it's still being
+/// polled. Until one is ready, nothing is staged, and the future waits for
+/// any of the receivers to start waiting. The item is then staged with that
+/// receiver alone (if several are ready at once, the first in `senders`
+/// wins), so it can only ever be delivered once. If that receiver
+/// disconnects without taking the item, it's offered to the others again.
+///
+/// Once the item is staged, it stays committed to its receiver until it's
+/// taken or the receiver disconnects, even if the receiver stops waiting in
+/// the meantime. If every receiver is disconnected, the item is returned in
+/// a [`SendError`].
+///
+/// ```
+/// # futures::executor::block_on(async move {
+/// use futures::future::join;
+/// use handoff::{channel, send_to_any};
+///
+/// let (sender_a, _receiver_a) = channel();
+/// let (sender_b, mut receiver_b) = channel();
+/// let mut senders = [sender_a, sender_b];
+///
+/// // Only the second receiver is waiting for an item
+/// let (index, received) = join(send_to_any(&mut senders, "work"), receiver_b.recv()).await;
+///
+/// assert_eq!(index.unwrap(), 1);
+/// assert_eq!(received, Some("work"));
+/// # });
+/// ```
+#[inline]
+pub fn send_to_any<T>(senders: &mut [Sender<T>], item: T) -> SendToAnyFut<'_, T> {
+    SendToAnyFut {
+        senders,
+        item: Some(item),
+        send: None,
+        index: 0,
+    }
+}
+
+/// Future for the [`send_to_any`] function. See its documentation for
+/// details.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+#[pin_project(PinnedDrop)]
+#[derive(Debug)]
+pub struct SendToAnyFut<'a, T> {
+    senders: &'a [Sender<T>],
+
+    // The item, while it isn't committed to any receiver. This is `None` once
+    // it's staged, or once the future completes.
+    item: Option<T>,
+
+    // The send the item is committed to, if any
+    #[pin]
+    send: Option<SendFut<'a, T>>,
+
+    // The index of the sender that `send` belongs to
+    index: usize,
+}
+
+impl<T> Future for SendToAnyFut<'_, T> {
+    type Output = Result<usize, SendError<T>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut this = self.project();
+
+        loop {
+            if let Some(send) = this.send.as_mut().as_pin_mut() {
+                let result = ready!(send.poll(cx));
+                this.send.set(None);
+
+                match result {
+                    Ok(()) => return Poll::Ready(Ok(*this.index)),
+
+                    // The receiver disconnected without taking the item, so
+                    // offer it to the others
+                    Err(SendError(item)) => *this.item = Some(item),
+                }
+            }
+
+            let item = this
+                .item
+                .take()
+                .expect("Don't poll futures after they completed");
+
+            let mut connected = false;
+            let mut waiting = None;
+
+            for (index, sender) in this.senders.iter().enumerate() {
+                let Some(lock) = sender.inner.lock() else { continue };
+
+                if lock.receiver_closing.load(Relaxed) {
+                    continue;
+                }
+
+                connected = true;
+
+                // Ask to be woken once this receiver starts waiting, before
+                // checking if it already is, so that we can't miss it. See
+                // `Inner::signal_demand` for the other side of this.
+                lock.sender_waker.register(cx.waker());
+                lock.wants_demand.store(true, Relaxed);
+
+                if lock.receiver_waiting.load(Relaxed) {
+                    waiting = Some(index);
+                    break;
+                }
+
+                // A receiver that parked before we asked doesn't know to
+                // tell us, so wake it to confirm that it's still waiting.
+                // One that stopped polling without withdrawing its waker
+                // never confirms, and isn't woken again.
+                if let Some(waker) = lock.receiver_waker.take() {
+                    waker.wake();
+                }
+            }
+
+            let Some(index) = waiting else {
+                return match connected {
+                    true => {
+                        *this.item = Some(item);
+                        Poll::Pending
+                    }
+                    false => Poll::Ready(Err(SendError(item))),
+                };
+            };
+
+            // The other receivers no longer need to tell us when they're
+            // waiting; the committed send registers its own waker.
+            withdraw_demand(this.senders);
+
+            *this.index = index;
+            this.send.set(Some(SendFut::new(&this.senders[index].inner, item)));
+        }
+    }
+}
+
+#[pinned_drop]
+impl<T> PinnedDrop for SendToAnyFut<'_, T> {
+    fn drop(self: Pin<&mut Self>) {
+        withdraw_demand(self.senders);
+    }
+}
+
+/// Stop the receivers from waking us when they start waiting, and discard
+/// the wakers we registered to find out.
+fn withdraw_demand<T>(senders: &[Sender<T>]) {
+    for sender in senders {
+        let Some(lock) = sender.inner.lock() else { continue };
+
+        if lock.wants_demand.swap(false, Relaxed) {
+            lock.receiver_waiting.store(false, Relaxed);
+            drop(lock.sender_waker.take());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        future::Future,
+        pin::pin,
+        sync::Arc,
+        task::{Context, Poll, Waker},
+    };
+
+    use cool_asserts::assert_matches;
+    use futures::{FutureExt, StreamExt};
+
+    use super::send_to_any;
+    use crate::{channel, testing::CountingWaker, SendError, TryRecvError};
+
+    #[test]
+    fn delivers_to_waiting_receiver() {
+        let (sender_a, mut receiver_a) = channel();
+        let (sender_b, mut receiver_b) = channel();
+        let (sender_c, mut receiver_c) = channel();
+        let mut senders = [sender_a, sender_b, sender_c];
+        let mut cx = Context::from_waker(Waker::noop());
+
+        let receiver_waker = Arc::new(CountingWaker::default());
+        let waker = Waker::from(receiver_waker.clone());
+        let mut recv_cx = Context::from_waker(&waker);
+        assert!(receiver_b.poll_next_unpin(&mut recv_cx).is_pending());
+
+        // The receiver was already waiting, so it's asked to confirm
+        let mut send = pin!(send_to_any(&mut senders, 1));
+        assert!(send.as_mut().poll(&mut cx).is_pending());
+        assert_eq!(receiver_waker.count(), 1);
+        assert_eq!(receiver_b.try_recv(), Err(TryRecvError::Empty));
+
+        assert!(receiver_b.poll_next_unpin(&mut recv_cx).is_pending());
+        assert!(send.as_mut().poll(&mut cx).is_pending());
+
+        // Only the waiting receiver has the item staged
+        assert_eq!(receiver_a.try_recv(), Err(TryRecvError::Empty));
+        assert_eq!(receiver_c.try_recv(), Err(TryRecvError::Empty));
+        assert_eq!(receiver_b.try_recv(), Ok(1));
+
+        assert_matches!(send.poll(&mut cx), Poll::Ready(Ok(1)));
+    }
+
+    #[test]
+    fn waits_for_a_receiver() {
+        let (sender_a, _receiver_a) = channel();
+        let (sender_b, mut receiver_b) = channel();
+        let mut senders = [sender_a, sender_b];
+
+        let sender_waker = Arc::new(CountingWaker::default());
+        let waker = Waker::from(sender_waker.clone());
+        let mut cx = Context::from_waker(&waker);
+
+        let mut send = pin!(send_to_any(&mut senders, 1));
+        assert!(send.as_mut().poll(&mut cx).is_pending());
+        assert_eq!(sender_waker.count(), 0);
+
+        // Once a receiver starts waiting, the send is woken to stage its item
+        let mut recv_cx = Context::from_waker(Waker::noop());
+        assert!(receiver_b.poll_next_unpin(&mut recv_cx).is_pending());
+        assert_eq!(sender_waker.count(), 1);
+
+        assert!(send.as_mut().poll(&mut cx).is_pending());
+        assert_eq!(receiver_b.try_recv(), Ok(1));
+        assert_matches!(send.poll(&mut cx), Poll::Ready(Ok(1)));
+    }
+
+    #[test]
+    fn skips_receiver_that_stopped_waiting() {
+        let (sender_a, mut receiver_a) = channel();
+        let (sender_b, mut receiver_b) = channel();
+        let mut senders = [sender_a, sender_b];
+        let mut cx = Context::from_waker(Waker::noop());
+
+        // The first receiver waits, but gives up before the send starts
+        assert!(receiver_a.recv().poll_unpin(&mut cx).is_pending());
+
+        let mut send = pin!(send_to_any(&mut senders, 1));
+        assert!(send.as_mut().poll(&mut cx).is_pending());
+        assert!(receiver_b.poll_next_unpin(&mut cx).is_pending());
+        assert!(send.as_mut().poll(&mut cx).is_pending());
+
+        assert_eq!(receiver_a.try_recv(), Err(TryRecvError::Empty));
+        assert_eq!(receiver_b.try_recv(), Ok(1));
+        assert_matches!(send.poll(&mut cx), Poll::Ready(Ok(1)));
+    }
+
+    #[test]
+    fn skips_stream_receiver_that_stopped_polling() {
+        let (sender_a, mut receiver_a) = channel();
+        let (sender_b, mut receiver_b) = channel();
+        let mut senders = [sender_a, sender_b];
+        let mut cx = Context::from_waker(Waker::noop());
+
+        {
+            let mut send = pin!(send_to_any(&mut senders, 0));
+            assert!(send.as_mut().poll(&mut cx).is_pending());
+
+            assert!(receiver_a.poll_next_unpin(&mut cx).is_pending());
+            assert!(send.as_mut().poll(&mut cx).is_pending());
+            assert_eq!(receiver_a.try_recv(), Ok(0));
+            assert_matches!(send.poll(&mut cx), Poll::Ready(Ok(0)));
+        }
+
+        // The first receiver was polled as a stream, and then only with
+        // `try_recv`, so it's no longer waiting, even though its waker was
+        // never withdrawn
+        assert!(receiver_a.poll_next_unpin(&mut cx).is_pending());
+        assert_eq!(receiver_a.try_recv(), Err(TryRecvError::Empty));
+
+        let mut send = pin!(send_to_any(&mut senders, 1));
+        assert!(send.as_mut().poll(&mut cx).is_pending());
+        assert!(receiver_b.poll_next_unpin(&mut cx).is_pending());
+        assert!(send.as_mut().poll(&mut cx).is_pending());
+
+        assert_eq!(receiver_a.try_recv(), Err(TryRecvError::Empty));
+        assert_eq!(receiver_b.try_recv(), Ok(1));
+        assert_matches!(send.poll(&mut cx), Poll::Ready(Ok(1)));
+    }
+
+    #[test]
+    fn offered_again_after_disconnect() {
+        let (sender_a, mut receiver_a) = channel();
+        let (sender_b, mut receiver_b) = channel();
+        let mut senders = [sender_a, sender_b];
+        let mut cx = Context::from_waker(Waker::noop());
+
+        let mut send = pin!(send_to_any(&mut senders, 1));
+        assert!(send.as_mut().poll(&mut cx).is_pending());
+        assert!(receiver_a.poll_next_unpin(&mut cx).is_pending());
+        assert!(send.as_mut().poll(&mut cx).is_pending());
+        assert!(receiver_b.is_empty());
+
+        // The first receiver leaves without taking the item, so it goes to
+        // the next receiver that waits for it
+        drop(receiver_a);
+        assert!(send.as_mut().poll(&mut cx).is_pending());
+        assert!(receiver_b.poll_next_unpin(&mut cx).is_pending());
+        assert!(send.as_mut().poll(&mut cx).is_pending());
+
+        assert_eq!(receiver_b.try_recv(), Ok(1));
+        assert_matches!(send.poll(&mut cx), Poll::Ready(Ok(1)));
+    }
+
+    #[test]
+    fn all_disconnected() {
+        let (sender_a, receiver_a) = channel();
+        let (sender_b, receiver_b) = channel();
+        let mut senders = [sender_a, sender_b];
+        drop((receiver_a, receiver_b));
+
+        assert_matches!(
+            send_to_any(&mut senders, 1).now_or_never(),
+            Some(Err(SendError(1)))
+        );
+        assert_matches!(
+            send_to_any(&mut [], 2).now_or_never(),
+            Some(Err(SendError(2)))
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn each_item_delivered_exactly_once() {
+        let (senders, consumers): (Vec<_>, Vec<_>) = (0..4)
+            .map(|_| {
+                let (sender, receiver) = channel::<u32>();
+                (sender, tokio::task::spawn(receiver.collect::<Vec<_>>()))
+            })
+            .unzip();
+
+        let mut senders = senders;
+        let mut counts = [0; 4];
+
+        for i in 0..1000 {
+            let index = send_to_any(&mut senders, i).await.unwrap();
+            counts[index] += 1;
+        }
+
+        drop(senders);
+
+        let mut received = Vec::new();
+
+        for (consumer, count) in consumers.into_iter().zip(counts) {
+            let items = consumer.await.unwrap();
+            assert_eq!(items.len(), count);
+            received.extend(items);
+        }
+
+        received.sort_unstable();
+        assert_eq!(received, (0..1000).collect::<Vec<_>>());
+    }
+}
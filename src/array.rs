@@ -0,0 +1,563 @@
+/*!
+A bounded, single-producer/single-consumer channel whose buffer lives inline.
+
+Unlike the handoff channel, this channel buffers up to `N` items, and a send
+only blocks while the buffer is full. The buffer is a fixed-size array stored
+directly in the [`Channel`] object, so the channel never allocates: it can
+live on the stack, or in a `static` cell, or wherever else its owner puts it.
+The module only uses `core`, so it doesn't need `alloc` or `std` either, and
+[`Channel::new`] is a `const fn`, so a channel can be built at compile time.
+
+Because nothing is allocated to share between the halves, the halves can't
+own the channel. Instead, the [`Channel`] owns the buffer and lends out a
+[`Sender`] and [`Receiver`] that borrow it, with [`split`][Channel::split].
+Once both halves are dropped, the channel can be split again; any items still
+in the buffer are kept for the next receiver.
+
+```
+# futures::executor::block_on(async move {
+use handoff::array;
+use futures::{future::join, StreamExt};
+
+let mut channel = array::Channel::<i32, 4>::new();
+let (mut sender, receiver) = channel.split();
+
+let send_task = async move {
+    for i in 0..10 {
+        sender.send(i).await.unwrap();
+    }
+};
+
+let ((), received) = join(send_task, receiver.collect::<Vec<_>>()).await;
+assert_eq!(received, (0..10).collect::<Vec<_>>());
+# });
+```
+*/
+
+use core::{
+    cell::UnsafeCell,
+    fmt::{self, Debug, Formatter},
+    future::Future,
+    mem::MaybeUninit,
+    pin::Pin,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+    task::{Context, Poll},
+};
+
+use futures_util::{task::AtomicWaker, Stream};
+
+use crate::{SendError, TryRecvError};
+
+/// The storage for a bounded channel with `N` inline slots. See the [module
+/// documentation][self] for details.
+///
+/// `N` must be at least 1; `Channel::<T, 0>::new()` fails to compile.
+pub struct Channel<T, const N: usize> {
+    slots: [UnsafeCell<MaybeUninit<T>>; N],
+
+    // The total number of items ever received. This is only written by the
+    // receiver; `head % N` is the slot holding the oldest buffered item.
+    head: AtomicUsize,
+
+    // The total number of items ever sent. This is only written by the
+    // sender; `tail % N` is the slot the next item will be written to. The
+    // number of buffered items is `tail - head`, with wrapping.
+    tail: AtomicUsize,
+
+    // The waker owned by the sender. Should be signalled when the receiver
+    // frees a slot (or disconnects)
+    sender_waker: AtomicWaker,
+
+    // The waker owned by the receiver. Should be signalled when the sender
+    // fills a slot (or disconnects)
+    receiver_waker: AtomicWaker,
+
+    sender_connected: AtomicBool,
+    receiver_connected: AtomicBool,
+}
+
+// Items are only ever moved from the sender to the receiver, and the slots
+// are only accessed according to the head/tail protocol.
+unsafe impl<T: Send, const N: usize> Send for Channel<T, N> {}
+unsafe impl<T: Send, const N: usize> Sync for Channel<T, N> {}
+
+impl<T, const N: usize> Channel<T, N> {
+    const NONZERO: () = assert!(N > 0, "an array channel needs at least one slot");
+
+    /// Create a new, empty channel.
+    #[must_use]
+    pub const fn new() -> Self {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::NONZERO;
+
+        Self {
+            slots: [const { UnsafeCell::new(MaybeUninit::uninit()) }; N],
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+            sender_waker: AtomicWaker::new(),
+            receiver_waker: AtomicWaker::new(),
+            sender_connected: AtomicBool::new(false),
+            receiver_connected: AtomicBool::new(false),
+        }
+    }
+
+    /// Lend out the sending and receiving halves of this channel. Both
+    /// halves borrow the channel, so it can't be split again until both of
+    /// them have been dropped.
+    pub fn split(&mut self) -> (Sender<'_, T, N>, Receiver<'_, T, N>) {
+        *self.sender_connected.get_mut() = true;
+        *self.receiver_connected.get_mut() = true;
+
+        let channel = &*self;
+        (Sender { channel }, Receiver { channel })
+    }
+
+    /// The number of items currently buffered in the channel.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        let head = self.head.load(Ordering::Acquire);
+        self.tail.load(Ordering::Acquire).wrapping_sub(head)
+    }
+
+    /// Returns true if no items are currently buffered in the channel.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The number of items the channel can buffer, which is always `N`.
+    #[must_use]
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Write an item into the next free slot, or return it if the buffer is
+    /// full. Only the sender may call this.
+    fn try_push(&self, item: T) -> Result<(), T> {
+        let tail = self.tail.load(Ordering::Relaxed);
+
+        if tail.wrapping_sub(self.head.load(Ordering::Acquire)) == N {
+            return Err(item);
+        }
+
+        // Safety: the slot is free, because the receiver has read past it,
+        // and only the sender writes to free slots.
+        unsafe { (*self.slots[tail % N].get()).write(item) };
+        self.tail.store(tail.wrapping_add(1), Ordering::Release);
+        self.receiver_waker.wake();
+
+        Ok(())
+    }
+
+    /// Read the oldest buffered item, if any. Only the receiver may call
+    /// this.
+    fn try_pop(&self) -> Option<T> {
+        let head = self.head.load(Ordering::Relaxed);
+
+        if head == self.tail.load(Ordering::Acquire) {
+            return None;
+        }
+
+        // Safety: the slot is filled, because the sender has written past it,
+        // and only the receiver reads from filled slots. Advancing the head
+        // marks it as free again, so it won't be read twice.
+        let item = unsafe { (*self.slots[head % N].get()).assume_init_read() };
+        self.head.store(head.wrapping_add(1), Ordering::Release);
+        self.sender_waker.wake();
+
+        Some(item)
+    }
+}
+
+impl<T, const N: usize> Default for Channel<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> Drop for Channel<T, N> {
+    fn drop(&mut self) {
+        let head = *self.head.get_mut();
+        let tail = *self.tail.get_mut();
+
+        let mut index = head;
+        while index != tail {
+            // Safety: everything between the head and the tail is filled, and
+            // we have exclusive access to the channel.
+            unsafe { self.slots[index % N].get_mut().assume_init_drop() };
+            index = index.wrapping_add(1);
+        }
+    }
+}
+
+impl<T, const N: usize> Debug for Channel<T, N> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Channel")
+            .field("len", &self.len())
+            .field("capacity", &N)
+            .field("sender_connected", &self.sender_connected)
+            .field("receiver_connected", &self.receiver_connected)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The sending half of an array channel.
+///
+/// This object is created by [`Channel::split`]. See the [module
+/// documentation][self] for details.
+pub struct Sender<'a, T, const N: usize> {
+    channel: &'a Channel<T, N>,
+}
+
+impl<'a, T, const N: usize> Sender<'a, T, N> {
+    /// Asynchronously send an item to the receiver.
+    ///
+    /// This method will asynchronously block until there's a free slot in
+    /// the channel's buffer, then write the item into it. If the receiver
+    /// disconnects, this will instead return a [`SendError`] containing the
+    /// item.
+    #[inline]
+    pub fn send(&mut self, item: T) -> SendFut<'_, 'a, T, N> {
+        SendFut {
+            sender: self,
+            item: Some(item),
+        }
+    }
+
+    /// Check if the receiver is still connected.
+    #[inline]
+    #[must_use = "checking the connection has no effect on its own"]
+    pub fn is_connected(&self) -> bool {
+        self.channel.receiver_connected.load(Ordering::Acquire)
+    }
+}
+
+impl<T, const N: usize> Drop for Sender<'_, T, N> {
+    fn drop(&mut self) {
+        self.channel.sender_connected.store(false, Ordering::Release);
+        self.channel.receiver_waker.wake();
+    }
+}
+
+impl<T, const N: usize> Debug for Sender<'_, T, N> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sender")
+            .field("channel", self.channel)
+            .finish()
+    }
+}
+
+/// Future for the [`send`][Sender::send] method of an array [`Sender`]. See
+/// its documentation for details.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct SendFut<'s, 'a, T, const N: usize> {
+    sender: &'s mut Sender<'a, T, N>,
+    item: Option<T>,
+}
+
+// The item is never pinned; it's moved into the buffer when there's room.
+impl<T, const N: usize> Unpin for SendFut<'_, '_, T, N> {}
+
+impl<T, const N: usize> Future for SendFut<'_, '_, T, N> {
+    type Output = Result<(), SendError<T>>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let channel = self.sender.channel;
+        let mut item = self
+            .item
+            .take()
+            .expect("can't poll a SendFut after it completes");
+
+        // Register the waker after the first failed attempt, then try again,
+        // so that a slot freed in between isn't missed.
+        for attempt in 0..2 {
+            if !channel.receiver_connected.load(Ordering::Acquire) {
+                return Poll::Ready(Err(SendError(item)));
+            }
+
+            item = match channel.try_push(item) {
+                Ok(()) => return Poll::Ready(Ok(())),
+                Err(item) => item,
+            };
+
+            if attempt == 0 {
+                channel.sender_waker.register(cx.waker());
+            }
+        }
+
+        self.item = Some(item);
+        Poll::Pending
+    }
+}
+
+impl<T, const N: usize> Debug for SendFut<'_, '_, T, N> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SendFut")
+            .field("sender", &self.sender)
+            .field("sent", &self.item.is_none())
+            .finish()
+    }
+}
+
+/// The receiving half of an array channel.
+///
+/// This object is created by [`Channel::split`]. It implements [`Stream`],
+/// yielding buffered items in order until the sender disconnects and the
+/// buffer is empty. See the [module documentation][self] for details.
+pub struct Receiver<'a, T, const N: usize> {
+    channel: &'a Channel<T, N>,
+}
+
+impl<'a, T, const N: usize> Receiver<'a, T, N> {
+    /// Attempt to receive the next item.
+    ///
+    /// This method will asynchronously block until an item is available in
+    /// the buffer, then return it. If the sender disconnects, any items it
+    /// already sent are still received, after which this returns `None`.
+    #[inline]
+    pub fn recv(&mut self) -> RecvFut<'_, 'a, T, N> {
+        RecvFut { receiver: self }
+    }
+
+    /// Attempt to receive an item without blocking.
+    #[must_use = "if the result is unused, a received item is dropped"]
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        // Check the connection first, so that an item sent just before the
+        // sender disconnected isn't missed.
+        let connected = self.channel.sender_connected.load(Ordering::Acquire);
+
+        match self.channel.try_pop() {
+            Some(item) => Ok(item),
+            None if connected => Err(TryRecvError::Empty),
+            None => Err(TryRecvError::Disconnected),
+        }
+    }
+
+    /// The number of items currently waiting to be received.
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.channel.len()
+    }
+
+    /// Returns true if no items are currently waiting to be received.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.channel.is_empty()
+    }
+
+    /// Check if the sender is still connected. Even if it isn't, there may
+    /// still be buffered items to receive.
+    #[inline]
+    #[must_use = "checking the connection has no effect on its own"]
+    pub fn is_connected(&self) -> bool {
+        self.channel.sender_connected.load(Ordering::Acquire)
+    }
+
+    fn poll_recv(&mut self, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        for attempt in 0..2 {
+            match self.try_recv() {
+                Ok(item) => return Poll::Ready(Some(item)),
+                Err(TryRecvError::Disconnected) => return Poll::Ready(None),
+                Err(TryRecvError::Empty) => {}
+            }
+
+            if attempt == 0 {
+                self.channel.receiver_waker.register(cx.waker());
+            }
+        }
+
+        Poll::Pending
+    }
+}
+
+impl<T, const N: usize> Drop for Receiver<'_, T, N> {
+    fn drop(&mut self) {
+        self.channel
+            .receiver_connected
+            .store(false, Ordering::Release);
+        self.channel.sender_waker.wake();
+    }
+}
+
+impl<T, const N: usize> Debug for Receiver<'_, T, N> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Receiver")
+            .field("channel", self.channel)
+            .finish()
+    }
+}
+
+impl<T, const N: usize> Stream for Receiver<'_, T, N> {
+    type Item = T;
+
+    #[inline]
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        self.poll_recv(cx)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.is_connected() {
+            true => (self.len(), None),
+            false => (self.len(), Some(self.len())),
+        }
+    }
+}
+
+/// Future for the [`recv`][Receiver::recv] method of an array [`Receiver`].
+/// See its documentation for details.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+#[derive(Debug)]
+pub struct RecvFut<'r, 'a, T, const N: usize> {
+    receiver: &'r mut Receiver<'a, T, N>,
+}
+
+impl<T, const N: usize> Future for RecvFut<'_, '_, T, N> {
+    type Output = Option<T>;
+
+    #[inline]
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        self.receiver.poll_recv(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        future::Future,
+        pin::pin,
+        sync::Arc,
+        task::{Context, Waker},
+    };
+
+    use cool_asserts::assert_matches;
+    use futures::{future::join, FutureExt};
+
+    use super::Channel;
+    use crate::{SendError, TryRecvError};
+
+    #[test]
+    fn buffers_up_to_capacity() {
+        let mut channel = Channel::<i32, 4>::new();
+        let (mut sender, mut receiver) = channel.split();
+        let mut cx = Context::from_waker(Waker::noop());
+
+        for i in 0..4 {
+            assert!(pin!(sender.send(i)).poll(&mut cx).is_ready());
+        }
+
+        assert_eq!(receiver.len(), 4);
+
+        // The buffer is full, so the fifth send waits for a free slot
+        let mut send = pin!(sender.send(4));
+        assert!(send.as_mut().poll(&mut cx).is_pending());
+        assert_eq!(receiver.try_recv(), Ok(0));
+        assert_matches!(send.poll(&mut cx), std::task::Poll::Ready(Ok(())));
+
+        for i in 1..5 {
+            assert_eq!(receiver.try_recv(), Ok(i));
+        }
+
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Empty));
+    }
+
+    #[tokio::test]
+    async fn items_wrap_around_the_buffer() {
+        let mut channel = Channel::<usize, 4>::new();
+        let (mut sender, mut receiver) = channel.split();
+
+        let send_task = async move {
+            for i in 0..100 {
+                sender.send(i).await.unwrap();
+            }
+        };
+
+        let recv_task = async {
+            let mut received = Vec::new();
+            while let Some(item) = receiver.recv().await {
+                received.push(item);
+            }
+            received
+        };
+
+        let ((), received) = join(send_task, recv_task).await;
+        assert_eq!(received, (0..100).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn buffered_items_survive_sender_disconnect() {
+        let mut channel = Channel::<i32, 4>::new();
+        let (mut sender, mut receiver) = channel.split();
+
+        sender.send(1).await.unwrap();
+        sender.send(2).await.unwrap();
+        drop(sender);
+
+        assert!(!receiver.is_connected());
+        assert_eq!(receiver.recv().await, Some(1));
+        assert_eq!(receiver.recv().await, Some(2));
+        assert_eq!(receiver.recv().await, None);
+    }
+
+    #[tokio::test]
+    async fn send_to_disconnected_receiver() {
+        let mut channel = Channel::<i32, 4>::new();
+        let (mut sender, receiver) = channel.split();
+        drop(receiver);
+
+        assert!(!sender.is_connected());
+        assert_matches!(sender.send(1).await, Err(SendError(1)));
+    }
+
+    #[test]
+    fn split_again_keeps_buffered_items() {
+        let mut channel = Channel::<i32, 4>::new();
+
+        {
+            let (mut sender, _receiver) = channel.split();
+            sender.send(1).now_or_never().unwrap().unwrap();
+        }
+
+        assert_eq!(channel.len(), 1);
+
+        let (_sender, mut receiver) = channel.split();
+        assert!(receiver.is_connected());
+        assert_eq!(receiver.try_recv(), Ok(1));
+    }
+
+    #[test]
+    fn dropping_channel_drops_buffered_items() {
+        let item = Arc::new(());
+
+        {
+            let mut channel = Channel::<Arc<()>, 4>::new();
+            let (mut sender, mut receiver) = channel.split();
+
+            for _ in 0..4 {
+                sender.send(item.clone()).now_or_never().unwrap().unwrap();
+            }
+
+            // Consume one, so the remaining items straddle the slots
+            drop(receiver.try_recv().unwrap());
+            sender.send(item.clone()).now_or_never().unwrap().unwrap();
+            assert_eq!(Arc::strong_count(&item), 5);
+        }
+
+        assert_eq!(Arc::strong_count(&item), 1);
+    }
+
+    #[test]
+    fn built_at_compile_time() {
+        static CHANNEL: Channel<u32, 4> = Channel::new();
+
+        assert!(CHANNEL.is_empty());
+        assert_eq!(CHANNEL.capacity(), 4);
+
+        let mut channel = const { Channel::<u32, 4>::new() };
+        let (mut sender, mut receiver) = channel.split();
+        sender.send(7).now_or_never().unwrap().unwrap();
+        assert_eq!(receiver.try_recv(), Ok(7));
+    }
+}
@@ -0,0 +1,605 @@
+/*!
+A tiny, dependency-free executor for driving channel operations from
+synchronous code.
+
+This is deliberately minimal (in the spirit of `pollster`): it parks the
+current thread until the future's waker is signalled, then polls again. It
+doesn't know anything about timers or IO, so it's only suitable for futures
+that are woken by some other thread, like the futures in this crate.
+*/
+
+#[cfg(feature = "time")]
+use std::time::{Duration, Instant};
+use std::{
+    future::Future,
+    pin::pin,
+    sync::{
+        atomic::{
+            AtomicBool,
+            Ordering::{Acquire, Release},
+        },
+        Arc,
+    },
+    task::{Context, Poll, Wake, Waker},
+    thread::{self, Thread},
+};
+
+use crate::{Receiver, SendError, Sender};
+#[cfg(feature = "time")]
+use crate::{RecvTimeoutError, SendTimeoutError, TryRecvError};
+
+/// A waker that unparks a specific thread.
+///
+/// The `notified` flag exists because `thread::park` is allowed to return
+/// spuriously; it lets us distinguish real wakeups from spurious ones, so that
+/// we don't needlessly re-poll the future.
+#[derive(Debug)]
+pub(crate) struct ThreadWaker {
+    thread: Thread,
+    notified: AtomicBool,
+}
+
+impl ThreadWaker {
+    /// Create a waker for the current thread.
+    #[must_use]
+    pub(crate) fn current() -> Arc<Self> {
+        Arc::new(Self {
+            thread: thread::current(),
+            notified: AtomicBool::new(false),
+        })
+    }
+
+    /// Check if this waker belongs to the current thread.
+    #[inline]
+    #[must_use]
+    fn is_current(&self) -> bool {
+        self.thread.id() == thread::current().id()
+    }
+
+    /// Park the current thread until this waker is signalled. Must be called
+    /// from the thread that created the waker.
+    pub(crate) fn park(&self) {
+        while !self.notified.swap(false, Acquire) {
+            thread::park();
+        }
+    }
+
+    /// Park the current thread until this waker is signalled, or until
+    /// `deadline`. Returns `false` if the deadline passed first. Must be
+    /// called from the thread that created the waker.
+    #[cfg(feature = "time")]
+    fn park_until(&self, deadline: Instant) -> bool {
+        loop {
+            if self.notified.swap(false, Acquire) {
+                return true;
+            }
+
+            let now = Instant::now();
+
+            if now >= deadline {
+                return false;
+            }
+
+            thread::park_timeout(deadline - now);
+        }
+    }
+}
+
+impl Wake for ThreadWaker {
+    #[inline]
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref()
+    }
+
+    #[inline]
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.notified.store(true, Release);
+        self.thread.unpark();
+    }
+}
+
+/// Drive a future to completion on the current thread, using an existing
+/// thread waker. The waker must have been created by the current thread.
+pub(crate) fn block_on_with<F: Future>(thread_waker: &Arc<ThreadWaker>, fut: F) -> F::Output {
+    let waker = Waker::from(thread_waker.clone());
+    let mut cx = Context::from_waker(&waker);
+    let mut fut = pin!(fut);
+
+    loop {
+        match fut.as_mut().poll(&mut cx) {
+            Poll::Ready(out) => return out,
+            Poll::Pending => thread_waker.park(),
+        }
+    }
+}
+
+/// Send an item on the current thread, using an existing thread waker, giving
+/// up at `deadline`. The waker must have been created by the current thread.
+#[cfg(feature = "time")]
+fn send_until_with<T>(
+    thread_waker: &Arc<ThreadWaker>,
+    sender: &mut Sender<T>,
+    item: T,
+    deadline: Instant,
+) -> Result<(), SendTimeoutError<T>> {
+    let waker = Waker::from(thread_waker.clone());
+    let mut cx = Context::from_waker(&waker);
+    let mut send = pin!(sender.send(item));
+
+    loop {
+        if let Poll::Ready(result) = send.as_mut().poll(&mut cx) {
+            return result.map_err(SendTimeoutError::from);
+        }
+
+        if !thread_waker.park_until(deadline) {
+            // If the receiver took the item just as the deadline passed, the
+            // send is a success.
+            return match send.reclaim() {
+                Some(item) => Err(SendTimeoutError::Timeout(item)),
+                None => Ok(()),
+            };
+        }
+    }
+}
+
+/// Receive an item on the current thread, using an existing thread waker,
+/// giving up at `deadline`. The waker must have been created by the current
+/// thread.
+#[cfg(feature = "time")]
+fn recv_until_with<T>(
+    thread_waker: &Arc<ThreadWaker>,
+    receiver: &mut Receiver<T>,
+    deadline: Instant,
+) -> Result<Option<T>, RecvTimeoutError> {
+    let waker = Waker::from(thread_waker.clone());
+    let mut cx = Context::from_waker(&waker);
+
+    {
+        let mut recv = pin!(receiver.recv());
+
+        loop {
+            if let Poll::Ready(item) = recv.as_mut().poll(&mut cx) {
+                return Ok(item);
+            }
+
+            if !thread_waker.park_until(deadline) {
+                break;
+            }
+        }
+    }
+
+    // The deadline passed, but an item might have arrived since the last
+    // poll; if so, it should be delivered rather than reported as a timeout.
+    match receiver.try_recv() {
+        Ok(item) => Ok(Some(item)),
+        Err(TryRecvError::Disconnected) => Ok(None),
+        Err(TryRecvError::Empty) => Err(RecvTimeoutError),
+    }
+}
+
+/// Drive a future to completion on the current thread.
+#[inline]
+pub(crate) fn block_on<F: Future>(fut: F) -> F::Output {
+    block_on_with(&ThreadWaker::current(), fut)
+}
+
+/// Synchronously receive the next item from a [`Receiver`], blocking the
+/// current thread until an item is sent.
+///
+/// This is equivalent to calling `block_on(receiver.recv())` with any
+/// executor, but doesn't require pulling in a runtime. If the sender
+/// disconnects (either before or during the call), this returns `None` rather
+/// than blocking forever.
+///
+/// This must not be called from inside an asynchronous context, since it will
+/// block the executor thread.
+///
+/// # Example
+///
+/// ```
+/// use std::thread;
+/// use handoff::{channel, block_on_recv};
+/// use futures::executor::block_on;
+///
+/// let (mut sender, mut receiver) = channel();
+///
+/// let sender_thread = thread::spawn(move || {
+///     for i in 0..10 {
+///         block_on(sender.send(i)).expect("receiver disconnected");
+///     }
+/// });
+///
+/// for i in 0..10 {
+///     assert_eq!(block_on_recv(&mut receiver), Some(i));
+/// }
+///
+/// sender_thread.join().expect("sender panicked");
+/// assert_eq!(block_on_recv(&mut receiver), None);
+/// ```
+pub fn block_on_recv<T>(receiver: &mut Receiver<T>) -> Option<T> {
+    block_on(receiver.recv())
+}
+
+/// Synchronously send an item to a [`Sender`], blocking the current thread
+/// until the receiver takes it.
+///
+/// This is the sending counterpart to [`block_on_recv`]. If the receiver
+/// disconnects, this returns a [`SendError`] containing the item. For sending
+/// many items from the same thread, prefer [`BlockingSender`], which avoids
+/// setting up the parking machinery on each send.
+///
+/// This must not be called from inside an asynchronous context, since it will
+/// block the executor thread.
+pub fn block_on_send<T>(sender: &mut Sender<T>, item: T) -> Result<(), SendError<T>> {
+    block_on(sender.send(item))
+}
+
+/// A [`Sender`] for use from synchronous code, like a dedicated producer
+/// thread feeding an async consumer.
+///
+/// Created with [`Sender::into_blocking`]. Unlike [`block_on_send`], this
+/// caches the waker used to park the sending thread, so repeated sends from
+/// the same thread don't allocate. The handle can be moved freely between
+/// threads; the cache is transparently replaced when it's used from a
+/// different thread.
+#[derive(Debug)]
+pub struct BlockingSender<T> {
+    sender: Sender<T>,
+    waker: Option<Arc<ThreadWaker>>,
+}
+
+/// Get the cached waker for the current thread, replacing it if it belongs to
+/// a different thread.
+fn cached_thread_waker(cache: &mut Option<Arc<ThreadWaker>>) -> &Arc<ThreadWaker> {
+    if cache.as_ref().is_some_and(|waker| !waker.is_current()) {
+        *cache = None;
+    }
+
+    cache.get_or_insert_with(ThreadWaker::current)
+}
+
+impl<T> Receiver<T> {
+    /// Synchronously receive the next item from the sender, blocking the
+    /// current thread until an item is sent or `timeout` elapses.
+    ///
+    /// This returns `Ok(None)` if the sender disconnects, or a
+    /// [`RecvTimeoutError`] if the timeout elapses first. An item that's sent
+    /// just as the timeout elapses is still received, rather than reported
+    /// as a timeout.
+    ///
+    /// This must not be called from inside an asynchronous context, since it
+    /// will block the executor thread.
+    #[cfg(feature = "time")]
+    pub fn recv_blocking_timeout(
+        &mut self,
+        timeout: Duration,
+    ) -> Result<Option<T>, RecvTimeoutError> {
+        let deadline = Instant::now() + timeout;
+        recv_until_with(&ThreadWaker::current(), self, deadline)
+    }
+}
+
+impl<T> Sender<T> {
+    /// Convert this sender into a [`BlockingSender`], for sending from
+    /// synchronous code.
+    #[inline]
+    #[must_use]
+    pub fn into_blocking(self) -> BlockingSender<T> {
+        BlockingSender {
+            sender: self,
+            waker: None,
+        }
+    }
+
+    /// Synchronously send an item to the receiver, blocking the current
+    /// thread until the receiver takes it or `timeout` elapses.
+    ///
+    /// This is the synchronous equivalent of
+    /// [`send_timeout`][Sender::send_timeout]: if the timeout elapses first,
+    /// the item is reclaimed and returned in a
+    /// [`SendTimeoutError::Timeout`]. For sending many items from the same
+    /// thread, prefer [`BlockingSender::send_blocking_timeout`].
+    ///
+    /// This must not be called from inside an asynchronous context, since it
+    /// will block the executor thread.
+    #[cfg(feature = "time")]
+    pub fn send_blocking_timeout(
+        &mut self,
+        item: T,
+        timeout: Duration,
+    ) -> Result<(), SendTimeoutError<T>> {
+        let deadline = Instant::now() + timeout;
+        send_until_with(&ThreadWaker::current(), self, item, deadline)
+    }
+}
+
+impl<T> Sender<T> {
+    /// Synchronously send every item from `items` to the receiver, blocking
+    /// the current thread until the receiver takes each one.
+    ///
+    /// This is the synchronous equivalent of [`extend`][Sender::extend]: if
+    /// the receiver disconnects, the item that couldn't be sent is returned
+    /// in a [`SendError`], and the rest of the iterator is dropped. The
+    /// thread's parking waker is set up once for the whole batch.
+    ///
+    /// This must not be called from inside an asynchronous context, since it
+    /// will block the executor thread.
+    ///
+    /// ```
+    /// use std::thread;
+    /// use handoff::{block_on_recv, channel};
+    ///
+    /// let (mut sender, mut receiver) = channel();
+    /// let sender_thread = thread::spawn(move || sender.send_iter_blocking(0..3));
+    ///
+    /// let mut received = Vec::new();
+    /// while let Some(item) = block_on_recv(&mut receiver) {
+    ///     received.push(item);
+    /// }
+    ///
+    /// sender_thread.join().unwrap().unwrap();
+    /// assert_eq!(received, [0, 1, 2]);
+    /// ```
+    pub fn send_iter_blocking<I>(&mut self, items: I) -> Result<(), SendError<T>>
+    where
+        I: IntoIterator<Item = T>,
+    {
+        block_on(self.extend(items))
+    }
+}
+
+#[cfg(test)]
+impl<T> Receiver<T> {
+    /// Synchronously receive every item until the sender disconnects.
+    pub(crate) fn collect_blocking<C: Default + Extend<T>>(&mut self) -> C {
+        let thread_waker = ThreadWaker::current();
+        let mut items = C::default();
+
+        while let Some(item) = block_on_with(&thread_waker, self.recv()) {
+            items.extend([item]);
+        }
+
+        items
+    }
+}
+
+impl<T> BlockingSender<T> {
+    /// Send an item to the receiver, blocking the current thread until the
+    /// receiver takes it.
+    ///
+    /// If the receiver disconnects, this returns a [`SendError`] containing
+    /// the item. This must not be called from inside an asynchronous context,
+    /// since it will block the executor thread.
+    pub fn send_blocking(&mut self, item: T) -> Result<(), SendError<T>> {
+        let waker = cached_thread_waker(&mut self.waker);
+        block_on_with(waker, self.sender.send(item))
+    }
+
+    /// Send an item to the receiver, blocking the current thread until the
+    /// receiver takes it or `timeout` elapses. See
+    /// [`Sender::send_blocking_timeout`] for details.
+    #[cfg(feature = "time")]
+    pub fn send_blocking_timeout(
+        &mut self,
+        item: T,
+        timeout: Duration,
+    ) -> Result<(), SendTimeoutError<T>> {
+        let deadline = Instant::now() + timeout;
+        let waker = cached_thread_waker(&mut self.waker);
+        send_until_with(waker, &mut self.sender, item, deadline)
+    }
+
+    /// Check if the receiver is still connected. See
+    /// [`Sender::is_connected`] for details.
+    #[inline]
+    #[must_use]
+    pub fn is_connected(&self) -> bool {
+        self.sender.is_connected()
+    }
+
+    /// Get a reference to the underlying sender.
+    #[inline]
+    #[must_use]
+    pub fn get_ref(&self) -> &Sender<T> {
+        &self.sender
+    }
+
+    /// Convert this back into an async [`Sender`].
+    #[inline]
+    #[must_use]
+    pub fn into_inner(self) -> Sender<T> {
+        self.sender
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{thread, time::Duration};
+
+    use cool_asserts::assert_matches;
+
+    use crate::{channel, SendError};
+    #[cfg(feature = "time")]
+    use crate::{RecvTimeoutError, SendTimeoutError};
+
+    use super::{block_on, block_on_recv, block_on_send};
+
+    #[test]
+    fn recv_from_thread() {
+        let (mut sender, mut receiver) = channel();
+
+        let sender_thread = thread::spawn(move || {
+            for i in 0..1_000 {
+                block_on(sender.send(i)).unwrap();
+            }
+        });
+
+        for i in 0..1_000 {
+            assert_eq!(block_on_recv(&mut receiver), Some(i));
+        }
+
+        sender_thread.join().unwrap();
+        assert_eq!(block_on_recv(&mut receiver), None);
+    }
+
+    #[test]
+    fn send_iter_blocking_into_collect_blocking() {
+        let (mut sender, mut receiver) = channel();
+
+        let sender_thread = thread::spawn(move || sender.send_iter_blocking(0..100));
+        let received: Vec<i32> = receiver.collect_blocking();
+
+        sender_thread.join().unwrap().unwrap();
+        assert_eq!(received, Vec::from_iter(0..100));
+    }
+
+    #[test]
+    fn send_iter_blocking_stops_at_disconnect() {
+        let (mut sender, mut receiver) = channel();
+
+        let receiver_thread = thread::spawn(move || block_on_recv(&mut receiver));
+
+        assert_matches!(sender.send_iter_blocking(0..10), Err(SendError(1)));
+        assert_eq!(receiver_thread.join().unwrap(), Some(0));
+    }
+
+    #[test]
+    fn sender_disconnects_while_blocked() {
+        let (sender, mut receiver) = channel::<i32>();
+
+        let sender_thread = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            drop(sender);
+        });
+
+        assert_eq!(block_on_recv(&mut receiver), None);
+        sender_thread.join().unwrap();
+    }
+
+    #[test]
+    fn blocking_sender_across_threads() {
+        let (sender, mut receiver) = channel();
+        let mut sender = sender.into_blocking();
+
+        let receiver_thread = thread::spawn(move || {
+            let mut items = Vec::new();
+            while let Some(item) = block_on_recv(&mut receiver) {
+                items.push(item);
+            }
+            items
+        });
+
+        for i in 0..100 {
+            sender.send_blocking(i).unwrap();
+        }
+
+        // Moving the sender to another thread must replace the cached waker
+        let sender_thread = thread::spawn(move || {
+            for i in 100..200 {
+                sender.send_blocking(i).unwrap();
+            }
+        });
+
+        sender_thread.join().unwrap();
+        assert_eq!(receiver_thread.join().unwrap(), Vec::from_iter(0..200));
+    }
+
+    #[test]
+    #[cfg(feature = "time")]
+    fn send_blocking_timeout_with_slow_receiver() {
+        let (mut sender, mut receiver) = channel();
+
+        let receiver_thread = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            block_on_recv(&mut receiver)
+        });
+
+        assert_matches!(
+            sender.send_blocking_timeout(1, Duration::from_millis(10)),
+            Err(SendTimeoutError::Timeout(1))
+        );
+
+        // The timed out item was reclaimed, so the receiver gets the next one
+        let mut sender = sender.into_blocking();
+        sender
+            .send_blocking_timeout(2, Duration::from_secs(5))
+            .unwrap();
+        assert_eq!(receiver_thread.join().unwrap(), Some(2));
+
+        assert_matches!(
+            sender.send_blocking_timeout(3, Duration::from_secs(5)),
+            Err(SendTimeoutError::Disconnected(3))
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "time")]
+    fn recv_blocking_timeout() {
+        let (mut sender, mut receiver) = channel();
+
+        assert_eq!(
+            receiver.recv_blocking_timeout(Duration::from_millis(10)),
+            Err(RecvTimeoutError)
+        );
+
+        let sender_thread = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(10));
+            block_on_send(&mut sender, 1).unwrap();
+        });
+
+        assert_eq!(
+            receiver.recv_blocking_timeout(Duration::from_secs(5)),
+            Ok(Some(1))
+        );
+
+        sender_thread.join().unwrap();
+        assert_eq!(
+            receiver.recv_blocking_timeout(Duration::from_secs(5)),
+            Ok(None)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "time")]
+    fn recv_blocking_timeout_at_deadline() {
+        // An item that's already waiting is received even with no time left
+        let (mut sender, mut receiver) = channel();
+        let sender_thread = thread::spawn(move || block_on_send(&mut sender, 1).unwrap());
+
+        while receiver.recv_blocking_timeout(Duration::ZERO) == Err(RecvTimeoutError) {
+            thread::yield_now();
+        }
+        sender_thread.join().unwrap();
+
+        // Items sent at about the same moment as the deadline are either
+        // received or left for the next receive, but never lost.
+        let (mut sender, mut receiver) = channel();
+        let sender_thread = thread::spawn(move || {
+            for i in 0..50 {
+                thread::sleep(Duration::from_millis(1));
+                block_on_send(&mut sender, i).unwrap();
+            }
+        });
+
+        let mut items = Vec::new();
+        loop {
+            match receiver.recv_blocking_timeout(Duration::from_millis(1)) {
+                Ok(Some(item)) => items.push(item),
+                Ok(None) => break,
+                Err(RecvTimeoutError) => {}
+            }
+        }
+
+        sender_thread.join().unwrap();
+        assert_eq!(items, Vec::from_iter(0..50));
+    }
+
+    #[test]
+    fn send_after_disconnect() {
+        let (mut sender, receiver) = channel();
+        drop(receiver);
+
+        assert_eq!(block_on_send(&mut sender, 1).unwrap_err().0, 1);
+        assert_eq!(sender.into_blocking().send_blocking(2).unwrap_err().0, 2);
+    }
+}
@@ -0,0 +1,246 @@
+/*!
+Sending a pair of items through two channels, all or nothing.
+*/
+
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use pin_project::pin_project;
+use thiserror::Error;
+
+use crate::{SendError, SendFut, Sender};
+
+/// Send `a` through `sender_a` and `b` through `sender_b`, succeeding only if
+/// both are delivered.
+///
+/// Both items are staged at once, and the future resolves once both
+/// receivers have taken them. If either receiver is already disconnected,
+/// neither item is staged. If one receiver disconnects while its item is
+/// staged, the other item is withdrawn, as long as its receiver hasn't taken
+/// it yet, and both are returned in a [`SendBothError::Neither`].
+///
+/// Because taking an item can't be undone, the guarantee only extends as far
+/// as the receivers allow: if one receiver takes its item and the other
+/// disconnects afterward without taking its own, the first item has already
+/// been delivered. In that case, the undelivered item alone is returned in a
+/// [`SendBothError::FirstUndelivered`] or
+/// [`SendBothError::SecondUndelivered`].
+///
+/// ```
+/// # futures::executor::block_on(async move {
+/// use futures::future::join3;
+/// use handoff::{channel, send_both, SendBothError};
+///
+/// let (mut sender_a, mut receiver_a) = channel();
+/// let (mut sender_b, mut receiver_b) = channel();
+///
+/// let (sent, a, b) = join3(
+///     send_both(&mut sender_a, 1, &mut sender_b, "one"),
+///     receiver_a.recv(),
+///     receiver_b.recv(),
+/// )
+/// .await;
+///
+/// sent.unwrap();
+/// assert_eq!((a, b), (Some(1), Some("one")));
+///
+/// // With a receiver gone, neither item is sent
+/// drop(receiver_b);
+/// let result = send_both(&mut sender_a, 2, &mut sender_b, "two").await;
+/// assert_eq!(result, Err(SendBothError::Neither(2, "two")));
+/// # });
+/// ```
+#[inline]
+pub fn send_both<'a, 'b, A, B>(
+    sender_a: &'a mut Sender<A>,
+    a: A,
+    sender_b: &'b mut Sender<B>,
+    b: B,
+) -> SendBothFut<'a, 'b, A, B> {
+    SendBothFut {
+        send_a: sender_a.send(a),
+        send_b: sender_b.send(b),
+        started: false,
+        delivered_a: false,
+        delivered_b: false,
+    }
+}
+
+/// Future for the [`send_both`] function. See its documentation for details.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+#[pin_project]
+#[derive(Debug)]
+pub struct SendBothFut<'a, 'b, A, B> {
+    #[pin]
+    send_a: SendFut<'a, A>,
+    #[pin]
+    send_b: SendFut<'b, B>,
+
+    // True once we've checked that both receivers are connected
+    started: bool,
+
+    delivered_a: bool,
+    delivered_b: bool,
+}
+
+impl<A, B> Future for SendBothFut<'_, '_, A, B> {
+    type Output = Result<(), SendBothError<A, B>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut this = self.project();
+
+        // Neither item has been staged yet, so both can be reclaimed outright
+        // if either receiver is already gone.
+        if !*this.started {
+            *this.started = true;
+
+            if !this.send_a.inner.alive() || !this.send_b.inner.alive() {
+                let a = this.send_a.reclaim().expect("an unstaged item can't be taken");
+                let b = this.send_b.reclaim().expect("an unstaged item can't be taken");
+                return Poll::Ready(Err(SendBothError::Neither(a, b)));
+            }
+        }
+
+        if !*this.delivered_a {
+            match this.send_a.as_mut().poll(cx) {
+                Poll::Ready(Ok(())) => *this.delivered_a = true,
+                Poll::Ready(Err(SendError(a))) => {
+                    return Poll::Ready(Err(match *this.delivered_b {
+                        true => SendBothError::FirstUndelivered(a),
+                        false => match this.send_b.reclaim() {
+                            Some(b) => SendBothError::Neither(a, b),
+                            None => SendBothError::FirstUndelivered(a),
+                        },
+                    }))
+                }
+                Poll::Pending => {}
+            }
+        }
+
+        if !*this.delivered_b {
+            match this.send_b.as_mut().poll(cx) {
+                Poll::Ready(Ok(())) => *this.delivered_b = true,
+                Poll::Ready(Err(SendError(b))) => {
+                    return Poll::Ready(Err(match *this.delivered_a {
+                        true => SendBothError::SecondUndelivered(b),
+                        false => match this.send_a.reclaim() {
+                            Some(a) => SendBothError::Neither(a, b),
+                            None => SendBothError::SecondUndelivered(b),
+                        },
+                    }))
+                }
+                Poll::Pending => {}
+            }
+        }
+
+        match *this.delivered_a && *this.delivered_b {
+            true => Poll::Ready(Ok(())),
+            false => Poll::Pending,
+        }
+    }
+}
+
+/// An error from a [`send_both`] operation.
+#[derive(Error, Clone, Debug, Copy, PartialEq, Eq)]
+pub enum SendBothError<A, B> {
+    /// Neither item was delivered, because a receiver disconnected. Both
+    /// items are returned.
+    #[error("a receiver disconnected, so neither item was sent")]
+    Neither(A, B),
+
+    /// The second item was delivered, but the first receiver disconnected
+    /// after that, without taking its item. The first item is returned.
+    #[error("only the second item was delivered")]
+    FirstUndelivered(A),
+
+    /// The first item was delivered, but the second receiver disconnected
+    /// after that, without taking its item. The second item is returned.
+    #[error("only the first item was delivered")]
+    SecondUndelivered(B),
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        future::Future,
+        pin::pin,
+        task::{Context, Poll, Waker},
+    };
+
+    use futures::future::join3;
+
+    use super::{send_both, SendBothError};
+    use crate::{channel, TryRecvError};
+
+    #[tokio::test]
+    async fn both_delivered() {
+        let (mut sender_a, mut receiver_a) = channel();
+        let (mut sender_b, mut receiver_b) = channel();
+
+        for i in 0..5 {
+            let (sent, a, b) = join3(
+                send_both(&mut sender_a, i, &mut sender_b, i * 10),
+                receiver_a.recv(),
+                receiver_b.recv(),
+            )
+            .await;
+
+            assert_eq!(sent, Ok(()));
+            assert_eq!((a, b), (Some(i), Some(i * 10)));
+        }
+    }
+
+    #[test]
+    fn receiver_already_gone() {
+        let (mut sender_a, receiver_a) = channel();
+        let (mut sender_b, mut receiver_b) = channel();
+        drop(receiver_a);
+
+        let mut cx = Context::from_waker(Waker::noop());
+        let send = pin!(send_both(&mut sender_a, 1, &mut sender_b, 2));
+        assert_eq!(send.poll(&mut cx), Poll::Ready(Err(SendBothError::Neither(1, 2))));
+
+        // The other item was never staged
+        assert_eq!(receiver_b.try_recv(), Err(TryRecvError::Empty));
+    }
+
+    #[test]
+    fn staged_item_withdrawn_on_disconnect() {
+        let (mut sender_a, mut receiver_a) = channel();
+        let (mut sender_b, receiver_b) = channel();
+        let mut cx = Context::from_waker(Waker::noop());
+
+        {
+            let mut send = pin!(send_both(&mut sender_a, 1, &mut sender_b, 2));
+            assert!(send.as_mut().poll(&mut cx).is_pending());
+
+            drop(receiver_b);
+            assert_eq!(send.poll(&mut cx), Poll::Ready(Err(SendBothError::Neither(1, 2))));
+        }
+
+        assert_eq!(receiver_a.try_recv(), Err(TryRecvError::Empty));
+    }
+
+    #[test]
+    fn partial_delivery_is_reported() {
+        let (mut sender_a, mut receiver_a) = channel();
+        let (mut sender_b, receiver_b) = channel();
+        let mut cx = Context::from_waker(Waker::noop());
+
+        let mut send = pin!(send_both(&mut sender_a, 1, &mut sender_b, 2));
+        assert!(send.as_mut().poll(&mut cx).is_pending());
+
+        // The first item is taken before the second receiver disconnects,
+        // so it can't be withdrawn
+        assert_eq!(receiver_a.try_recv(), Ok(1));
+        drop(receiver_b);
+
+        assert_eq!(
+            send.poll(&mut cx),
+            Poll::Ready(Err(SendBothError::SecondUndelivered(2)))
+        );
+    }
+}
@@ -0,0 +1,134 @@
+/*!
+Configurable construction of handoff channels.
+*/
+
+use std::sync::atomic::{AtomicBool, AtomicPtr, AtomicU64};
+
+use futures_util::task::AtomicWaker;
+use twinsies::Joint;
+
+use crate::{closed::Waiters, Inner, Receiver, Sender};
+
+/// A builder for configuring a channel before creating it.
+///
+/// [`channel`][crate::channel] is equivalent to `Builder::new().channel()`;
+/// a builder is only necessary to change the defaults.
+///
+/// ```
+/// use handoff::Builder;
+///
+/// let (sender, receiver) = Builder::new().coop_budget(32).channel::<i32>();
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Builder {
+    coop_budget: u32,
+    reclaim_spins: u32,
+}
+
+impl Builder {
+    /// The default for [`coop_budget`][Builder::coop_budget], which matches
+    /// the budget used by tokio's cooperative scheduling.
+    pub const DEFAULT_COOP_BUDGET: u32 = 128;
+
+    /// Create a builder with the default configuration.
+    #[inline]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            coop_budget: Self::DEFAULT_COOP_BUDGET,
+            reclaim_spins: 0,
+        }
+    }
+
+    /// Set the cooperative scheduling budget, which prevents either half of
+    /// the channel from starving other tasks when the other half is always
+    /// ready.
+    ///
+    /// For the sender, this is the number of consecutive non-blocking sends
+    /// that [`send_coop`][Sender::send_coop] allows before it forces the
+    /// sending task to yield; a budget of 0 makes every `send_coop` yield
+    /// once. For the receiver, this is the number of items that a draining
+    /// future, like [`fold_until`][Receiver::fold_until], takes in a single
+    /// poll before yielding; a budget of 0 is treated as 1.
+    #[inline]
+    #[must_use]
+    pub const fn coop_budget(self, budget: u32) -> Self {
+        Self {
+            coop_budget: budget,
+            ..self
+        }
+    }
+
+    /// Set how many times the sender spins before yielding its thread, while
+    /// it waits for the receiver to hand back its item pointer.
+    ///
+    /// The sender only waits like this in a narrow window: when it's polled
+    /// while the receiver, on another thread, is in the middle of taking its
+    /// item. By default (with 0 spins), it yields its thread on each retry,
+    /// which is the right choice when threads share cores. When the sender
+    /// and receiver are pinned to separate cores, like sibling hyperthreads,
+    /// the receiver usually finishes within a few hundred cycles, and a short
+    /// spin with [`spin_loop`][std::hint::spin_loop] avoids the cost of a
+    /// trip through the scheduler. After `spins` retries, the sender falls
+    /// back to yielding; `u32::MAX` spins effectively never yields.
+    ///
+    /// `cargo bench --bench reclaim` compares these
+    /// strategies on the current machine.
+    #[inline]
+    #[must_use]
+    pub const fn reclaim_spins(self, spins: u32) -> Self {
+        Self {
+            reclaim_spins: spins,
+            ..self
+        }
+    }
+
+    /// Create a channel with this configuration.
+    #[must_use]
+    pub fn channel<T>(self) -> (Sender<T>, Receiver<T>) {
+        let (send_joint, recv_joint) = Joint::new(Inner {
+            sent_item: AtomicPtr::default(),
+            item_held: AtomicBool::new(false),
+            taken_item: AtomicPtr::default(),
+            taken_count: AtomicU64::new(0),
+            reclaim_spins: self.reclaim_spins,
+            receiver_closing: AtomicBool::new(false),
+            wants_demand: AtomicBool::new(false),
+            receiver_waiting: AtomicBool::new(false),
+            #[cfg(feature = "time")]
+            staged_at: AtomicU64::new(0),
+            #[cfg(feature = "metrics")]
+            rate: crate::metrics::RateEstimate::new(),
+            #[cfg(feature = "diagnostics")]
+            registrations: Default::default(),
+            sender_waker: AtomicWaker::new(),
+            receiver_waker: AtomicWaker::new(),
+            close_waiters: Waiters::default(),
+        });
+
+        (
+            Sender {
+                inner: send_joint,
+                coop_streak: 0,
+                coop_budget: self.coop_budget,
+            },
+            Receiver {
+                inner: recv_joint,
+                peeked: None,
+                prefetched: false,
+                #[cfg(feature = "time")]
+                peeked_wait: std::time::Duration::ZERO,
+                #[cfg(all(feature = "reactor", target_os = "linux"))]
+                readiness: std::sync::OnceLock::new(),
+                coop_budget: self.coop_budget,
+            },
+        )
+    }
+}
+
+impl Default for Builder {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
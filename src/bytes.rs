@@ -0,0 +1,335 @@
+/*!
+Zero-copy byte frames for handing off framed byte streams.
+
+When `T = Vec<u8>`, every item handed off is a separate allocation, and
+splitting a received buffer into smaller frames means copying each of them
+out. This module instead hands off [`Frame`]s: reference-counted views into a
+shared, immutable byte buffer. Cloning a frame, or splitting it with
+[`split_to`][Frame::split_to] and [`split_off`][Frame::split_off], only
+adjusts a reference count and a pair of offsets, so the receiver can carve
+a buffer into its individual messages without copying any bytes.
+
+The channel itself is an ordinary handoff channel of frames, so all of the
+usual [`Sender`] and [`Receiver`] methods are available.
+
+```
+# futures::executor::block_on(async move {
+use handoff::bytes::{self, Frame};
+use futures::future::join;
+
+let (mut sender, mut receiver) = bytes::channel();
+
+let send_task = async move {
+    sender.send(Frame::from(b"GET /\nGET /about\n".to_vec())).await.unwrap();
+};
+
+let recv_task = async move {
+    let mut frame = receiver.recv().await.unwrap();
+    let mut lines = Vec::new();
+
+    while let Some(end) = frame.iter().position(|&b| b == b'\n') {
+        let mut line = frame.split_to(end + 1);
+        line.truncate(end);
+        lines.push(line);
+    }
+
+    lines
+};
+
+let ((), lines) = join(send_task, recv_task).await;
+assert_eq!(lines, [&b"GET /"[..], &b"GET /about"[..]]);
+# });
+```
+*/
+
+use std::{
+    fmt::{self, Debug, Formatter},
+    hash::{Hash, Hasher},
+    ops::Deref,
+    sync::Arc,
+};
+
+use crate::{Receiver, Sender};
+
+/// Create a handoff channel of [`Frame`]s. See the [module
+/// documentation][self] for details.
+#[inline]
+#[must_use]
+pub fn channel() -> (Sender<Frame>, Receiver<Frame>) {
+    crate::channel()
+}
+
+/// A cheaply cloneable and splittable view into a shared byte buffer.
+///
+/// A `Frame` dereferences to the bytes it covers. Clones and splits share
+/// the same underlying buffer, which is freed once the last frame viewing it
+/// is dropped. The bytes are immutable; a frame can only be narrowed.
+#[derive(Clone)]
+pub struct Frame {
+    buffer: Arc<[u8]>,
+
+    // The range of `buffer` covered by this frame
+    start: usize,
+    end: usize,
+}
+
+impl Frame {
+    /// Create an empty frame.
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::from(Arc::<[u8]>::from([]))
+    }
+
+    /// The number of bytes in this frame.
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    /// Returns true if this frame has no bytes.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// Split this frame in two at `at`, returning the bytes before `at`
+    /// and leaving the bytes from `at` onward in `self`. No bytes are
+    /// copied.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `at > self.len()`.
+    #[must_use = "consider Frame::advance if you don't need the other half"]
+    pub fn split_to(&mut self, at: usize) -> Frame {
+        assert!(
+            at <= self.len(),
+            "split_to out of bounds: {at} > {len}",
+            len = self.len()
+        );
+
+        let mid = self.start + at;
+        let head = Frame {
+            buffer: self.buffer.clone(),
+            start: self.start,
+            end: mid,
+        };
+
+        self.start = mid;
+        head
+    }
+
+    /// Split this frame in two at `at`, returning the bytes from `at`
+    /// onward and leaving the bytes before `at` in `self`. No bytes are
+    /// copied.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `at > self.len()`.
+    #[must_use = "consider Frame::truncate if you don't need the other half"]
+    pub fn split_off(&mut self, at: usize) -> Frame {
+        assert!(
+            at <= self.len(),
+            "split_off out of bounds: {at} > {len}",
+            len = self.len()
+        );
+
+        let mid = self.start + at;
+        let tail = Frame {
+            buffer: self.buffer.clone(),
+            start: mid,
+            end: self.end,
+        };
+
+        self.end = mid;
+        tail
+    }
+
+    /// Discard the first `count` bytes of this frame.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `count > self.len()`.
+    pub fn advance(&mut self, count: usize) {
+        assert!(
+            count <= self.len(),
+            "advance out of bounds: {count} > {len}",
+            len = self.len()
+        );
+
+        self.start += count;
+    }
+
+    /// Shorten this frame to its first `len` bytes. Has no effect if the
+    /// frame is already shorter than that.
+    #[inline]
+    pub fn truncate(&mut self, len: usize) {
+        if len < self.len() {
+            self.end = self.start + len;
+        }
+    }
+}
+
+impl Default for Frame {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deref for Frame {
+    type Target = [u8];
+
+    #[inline]
+    fn deref(&self) -> &[u8] {
+        &self.buffer[self.start..self.end]
+    }
+}
+
+impl AsRef<[u8]> for Frame {
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl From<Arc<[u8]>> for Frame {
+    #[inline]
+    fn from(buffer: Arc<[u8]>) -> Self {
+        Frame {
+            start: 0,
+            end: buffer.len(),
+            buffer,
+        }
+    }
+}
+
+impl From<Vec<u8>> for Frame {
+    #[inline]
+    fn from(bytes: Vec<u8>) -> Self {
+        Self::from(Arc::<[u8]>::from(bytes))
+    }
+}
+
+impl From<Box<[u8]>> for Frame {
+    #[inline]
+    fn from(bytes: Box<[u8]>) -> Self {
+        Self::from(Arc::<[u8]>::from(bytes))
+    }
+}
+
+impl From<&[u8]> for Frame {
+    #[inline]
+    fn from(bytes: &[u8]) -> Self {
+        Self::from(Arc::<[u8]>::from(bytes))
+    }
+}
+
+impl Debug for Frame {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Frame").field(&&**self).finish()
+    }
+}
+
+impl PartialEq for Frame {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl Eq for Frame {}
+
+impl PartialEq<[u8]> for Frame {
+    #[inline]
+    fn eq(&self, other: &[u8]) -> bool {
+        **self == *other
+    }
+}
+
+impl PartialEq<&[u8]> for Frame {
+    #[inline]
+    fn eq(&self, other: &&[u8]) -> bool {
+        **self == **other
+    }
+}
+
+impl Hash for Frame {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        future::Future,
+        pin::pin,
+        task::{Context, Waker},
+    };
+
+    use futures::FutureExt;
+
+    use super::{channel, Frame};
+
+    #[test]
+    fn split_to_and_split_off() {
+        let mut frame = Frame::from(b"headerbodytrailer".to_vec());
+        let base = frame.as_ptr();
+
+        let header = frame.split_to(6);
+        let trailer = frame.split_off(4);
+
+        assert_eq!(header, &b"header"[..]);
+        assert_eq!(frame, &b"body"[..]);
+        assert_eq!(trailer, &b"trailer"[..]);
+
+        // Every piece still points into the original buffer
+        assert_eq!(header.as_ptr(), base);
+        assert_eq!(frame.as_ptr(), base.wrapping_add(6));
+        assert_eq!(trailer.as_ptr(), base.wrapping_add(10));
+    }
+
+    #[test]
+    #[should_panic(expected = "split_to out of bounds")]
+    fn split_to_out_of_bounds() {
+        let mut frame = Frame::from(b"abc".to_vec());
+        let _ = frame.split_to(4);
+    }
+
+    #[test]
+    fn received_frames_split_without_copying() {
+        let (mut sender, mut receiver) = channel();
+        let mut cx = Context::from_waker(Waker::noop());
+
+        // Length-prefixed messages, all in one buffer
+        let buffer = Frame::from(b"\x03one\x05three\x00".to_vec());
+        let base = buffer.as_ptr();
+
+        // The sender keeps a clone, which shares the buffer
+        let mut send = pin!(sender.send(buffer.clone()));
+        assert!(send.as_mut().poll(&mut cx).is_pending());
+
+        let mut frame = receiver.recv().now_or_never().flatten().unwrap();
+        assert!(send.poll(&mut cx).is_ready());
+        assert_eq!(frame.as_ptr(), base);
+
+        let mut messages = Vec::new();
+
+        while !frame.is_empty() {
+            let len = usize::from(frame[0]);
+            frame.advance(1);
+            messages.push(frame.split_to(len));
+        }
+
+        assert_eq!(messages, [&b"one"[..], &b"three"[..], &b""[..]]);
+        assert_eq!(messages[0].as_ptr(), base.wrapping_add(1));
+        assert_eq!(messages[1].as_ptr(), base.wrapping_add(5));
+
+        // The sender's clone is unaffected by the receiver's splits
+        assert_eq!(buffer.len(), 11);
+    }
+}
@@ -0,0 +1,716 @@
+/*!
+Sends that can be cancelled, with a precise report of whether the item was
+delivered, and receives that race against another future.
+*/
+
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{
+            AtomicBool,
+            Ordering::{Acquire, Release},
+        },
+        Arc,
+    },
+    task::{Context, Poll},
+};
+
+use futures_util::{future::Either, task::AtomicWaker};
+use pin_project::pin_project;
+
+use crate::{AbortableSendError, Receiver, RecvFut, SendError, SendFut, Sender, WeakSendError};
+
+impl<T> Sender<T> {
+    /// Asynchronously send an item to the receiver, with the option of
+    /// cancelling the send and learning whether the item was delivered.
+    ///
+    /// The returned future behaves exactly like [`send`][Sender::send].
+    /// Dropping it cancels the send, but the caller can't tell whether the
+    /// receiver took the item first. Instead, call
+    /// [`cancel`][SendCancellableFut::cancel] on it, which stops the send and
+    /// either confirms that the item was delivered, or returns it. The item
+    /// is never both delivered and returned, which makes this suitable for
+    /// exactly-once delivery.
+    #[inline]
+    pub fn send_cancellable(&mut self, item: T) -> SendCancellableFut<'_, T> {
+        SendCancellableFut {
+            send: self.send(item),
+            completed: false,
+        }
+    }
+
+    /// Asynchronously send an item to the receiver, giving up if `cancel`
+    /// completes first.
+    ///
+    /// This is the same as racing [`send`][Sender::send] against `cancel`,
+    /// except that the item is reclaimed and returned in
+    /// [`SendOrCancel::Cancelled`] if `cancel` wins, rather than being
+    /// dropped along with the send. The send is polled first, so if the
+    /// receiver takes the item at the same moment that `cancel` completes,
+    /// the send is reported as a success; the item is never both delivered
+    /// and returned.
+    #[inline]
+    pub fn send_or<F>(&mut self, item: T, cancel: F) -> SendOrFut<'_, T, F>
+    where
+        F: Future,
+    {
+        SendOrFut {
+            send: self.send(item),
+            cancel,
+        }
+    }
+
+    /// Asynchronously send an item to the receiver, giving it one chance to
+    /// take the item.
+    ///
+    /// The item is staged, and then the sending task yields for a single
+    /// scheduling round. If the receiver hasn't taken the item by the time
+    /// the task is polled again, the item is reclaimed and returned in
+    /// [`WeakSendError::WouldBlock`]. This is a best-effort send for
+    /// latency-sensitive producers, which can't wait indefinitely for a slow
+    /// receiver but can afford to give a waiting receiver a chance to run. As
+    /// with [`send_or`][Sender::send_or], the item is never both delivered
+    /// and returned.
+    #[inline]
+    pub fn weak_send(&mut self, item: T) -> WeakSendFut<'_, T> {
+        WeakSendFut {
+            send: self.send(item),
+            staged: false,
+        }
+    }
+
+    /// Asynchronously send an item to the receiver, with an [`AbortHandle`]
+    /// that can cancel the send from elsewhere.
+    ///
+    /// The returned future behaves exactly like [`send`][Sender::send], until
+    /// [`abort`][AbortHandle::abort] is called on the handle (or any of its
+    /// clones). Then, the send is cancelled and the item is reclaimed and
+    /// returned in [`AbortableSendError::Aborted`], just as if the future
+    /// had been dropped. This is useful when the code that decides to give
+    /// up on the send doesn't own its future. As with
+    /// [`send_or`][Sender::send_or], the item is never both delivered and
+    /// returned: if the receiver took the item before the abort, the send
+    /// succeeds.
+    ///
+    /// ```
+    /// # futures::executor::block_on(async move {
+    /// use handoff::AbortableSendError;
+    ///
+    /// let (mut sender, _receiver) = handoff::channel();
+    /// let (handle, send) = sender.send_abortable("item");
+    ///
+    /// handle.abort();
+    /// assert_eq!(send.await, Err(AbortableSendError::Aborted("item")));
+    /// # });
+    /// ```
+    #[inline]
+    pub fn send_abortable(&mut self, item: T) -> (AbortHandle, AbortableSendFut<'_, T>) {
+        let state = Arc::new(AbortState::default());
+
+        let send = AbortableSendFut {
+            send: self.send(item),
+            state: state.clone(),
+        };
+
+        (AbortHandle { state }, send)
+    }
+}
+
+/// A handle that aborts a send from [`Sender::send_abortable`].
+///
+/// The handle can be cloned, and it's fine to abort after the send has
+/// completed, or more than once; it has no effect after the first time.
+#[derive(Debug, Clone)]
+pub struct AbortHandle {
+    state: Arc<AbortState>,
+}
+
+impl AbortHandle {
+    /// Abort the send, waking its task so that it can reclaim its item.
+    #[inline]
+    pub fn abort(&self) {
+        self.state.aborted.store(true, Release);
+        self.state.waker.wake();
+    }
+
+    /// Check if [`abort`][AbortHandle::abort] has been called.
+    #[inline]
+    #[must_use]
+    pub fn is_aborted(&self) -> bool {
+        self.state.aborted.load(Acquire)
+    }
+}
+
+#[derive(Debug, Default)]
+struct AbortState {
+    aborted: AtomicBool,
+
+    // The waker for the task driving the send
+    waker: AtomicWaker,
+}
+
+/// The outcome of a [`send_or`][Sender::send_or].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SendOrCancel<T> {
+    /// The receiver took the item.
+    Sent,
+
+    /// The receiver disconnected before taking the item, which is returned.
+    Disconnected(T),
+
+    /// The cancellation future completed before the receiver took the item,
+    /// which is returned.
+    Cancelled(T),
+}
+
+/// The outcome of cancelling a send with [`SendCancellableFut::cancel`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[must_use]
+pub enum CancelOutcome<T> {
+    /// The receiver took the item before the send was cancelled. This
+    /// includes an item that the receiver is [peeking][crate::Receiver::peek]
+    /// at, since it can no longer be reclaimed.
+    Delivered,
+
+    /// The send was cancelled before the receiver took the item, which is
+    /// returned. This includes the case where the receiver disconnected.
+    Reclaimed(T),
+}
+
+/// Future for the [`send_cancellable`][Sender::send_cancellable] method. See
+/// its documentation for details.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+#[pin_project]
+#[derive(Debug)]
+pub struct SendCancellableFut<'a, T> {
+    #[pin]
+    send: SendFut<'a, T>,
+    completed: bool,
+}
+
+impl<T> SendCancellableFut<'_, T> {
+    /// Cancel the send, reporting whether the receiver had already taken the
+    /// item. After this is called, the future must not be polled again.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the future already completed, since its result already
+    /// reported the outcome.
+    pub fn cancel(self: Pin<&mut Self>) -> CancelOutcome<T> {
+        let this = self.project();
+
+        assert!(
+            !*this.completed,
+            "Don't cancel futures after they completed"
+        );
+        *this.completed = true;
+
+        match this.send.reclaim() {
+            Some(item) => CancelOutcome::Reclaimed(item),
+            None => CancelOutcome::Delivered,
+        }
+    }
+}
+
+impl<T> Future for SendCancellableFut<'_, T> {
+    type Output = Result<(), SendError<T>>;
+
+    #[inline]
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let result = futures_util::ready!(this.send.poll(cx));
+        *this.completed = true;
+        Poll::Ready(result)
+    }
+}
+
+/// Future for the [`send_or`][Sender::send_or] method. See its documentation
+/// for details.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+#[pin_project]
+#[derive(Debug)]
+pub struct SendOrFut<'a, T, F> {
+    #[pin]
+    send: SendFut<'a, T>,
+    #[pin]
+    cancel: F,
+}
+
+impl<T, F: Future> Future for SendOrFut<'_, T, F> {
+    type Output = SendOrCancel<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut this = self.project();
+
+        if let Poll::Ready(result) = this.send.as_mut().poll(cx) {
+            return Poll::Ready(match result {
+                Ok(()) => SendOrCancel::Sent,
+                Err(SendError(item)) => SendOrCancel::Disconnected(item),
+            });
+        }
+
+        futures_util::ready!(this.cancel.poll(cx));
+
+        Poll::Ready(match this.send.reclaim() {
+            Some(item) => SendOrCancel::Cancelled(item),
+            None => SendOrCancel::Sent,
+        })
+    }
+}
+
+/// Future for the [`weak_send`][Sender::weak_send] method. See its
+/// documentation for details.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+#[pin_project]
+#[derive(Debug)]
+pub struct WeakSendFut<'a, T> {
+    #[pin]
+    send: SendFut<'a, T>,
+
+    // True once the item has been staged and we've yielded once
+    staged: bool,
+}
+
+impl<T> Future for WeakSendFut<'_, T> {
+    type Output = Result<(), WeakSendError<T>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut this = self.project();
+
+        if let Poll::Ready(result) = this.send.as_mut().poll(cx) {
+            return Poll::Ready(result.map_err(WeakSendError::from));
+        }
+
+        // Give the receiver one scheduling round to take the item
+        if !*this.staged {
+            *this.staged = true;
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+
+        Poll::Ready(match this.send.reclaim() {
+            Some(item) => Err(WeakSendError::WouldBlock(item)),
+            None => Ok(()),
+        })
+    }
+}
+
+/// Future for the [`send_abortable`][Sender::send_abortable] method. See its
+/// documentation for details.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+#[pin_project]
+#[derive(Debug)]
+pub struct AbortableSendFut<'a, T> {
+    #[pin]
+    send: SendFut<'a, T>,
+    state: Arc<AbortState>,
+}
+
+impl<T> Future for AbortableSendFut<'_, T> {
+    type Output = Result<(), AbortableSendError<T>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut this = self.project();
+
+        // Don't stage the item at all if we were aborted before being polled
+        if !this.state.aborted.load(Acquire) {
+            if let Poll::Ready(result) = this.send.as_mut().poll(cx) {
+                return Poll::Ready(result.map_err(AbortableSendError::from));
+            }
+
+            // Register before checking again, so that we don't miss a
+            // concurrent abort.
+            this.state.waker.register(cx.waker());
+
+            if !this.state.aborted.load(Acquire) {
+                return Poll::Pending;
+            }
+        }
+
+        Poll::Ready(match this.send.reclaim() {
+            Some(item) => Err(AbortableSendError::Aborted(item)),
+            None => Ok(()),
+        })
+    }
+}
+
+impl<T> Receiver<T> {
+    /// Receive the next item from the sender, or give up if `other`
+    /// completes first.
+    ///
+    /// This resolves to [`Either::Left`] with the result of
+    /// [`recv`][Receiver::recv], or [`Either::Right`] with the output of
+    /// `other`, whichever is ready first. The receive is polled first, so if
+    /// both are ready at once, the item is received. If `other` wins, the
+    /// receive is cancelled right away, which is cancel safe: the sender's
+    /// item (if any) stays where it is, and will be delivered by a subsequent
+    /// receive.
+    #[inline]
+    pub fn recv_or_future<F>(&mut self, other: F) -> RecvOrFutureFut<'_, T, F>
+    where
+        F: Future,
+    {
+        RecvOrFutureFut {
+            recv: Some(self.recv()),
+            other,
+        }
+    }
+}
+
+/// Future for the [`recv_or_future`][Receiver::recv_or_future] method. See
+/// its documentation for details.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+#[pin_project]
+#[derive(Debug)]
+pub struct RecvOrFutureFut<'a, T, F> {
+    // This is `None` after the future completes.
+    recv: Option<RecvFut<'a, T>>,
+    #[pin]
+    other: F,
+}
+
+impl<T, F: Future> Future for RecvOrFutureFut<'_, T, F> {
+    type Output = Either<Option<T>, F::Output>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+
+        let recv = this
+            .recv
+            .as_mut()
+            .expect("Don't poll futures after they completed");
+
+        if let Poll::Ready(item) = Pin::new(recv).poll(cx) {
+            *this.recv = None;
+            return Poll::Ready(Either::Left(item));
+        }
+
+        let output = futures_util::ready!(this.other.poll(cx));
+
+        // Drop the receive now, rather than when this future is dropped, so
+        // that its waker is cleared.
+        *this.recv = None;
+        Poll::Ready(Either::Right(output))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        future::Future,
+        pin::pin,
+        sync::Arc,
+        task::{Context, Poll, Waker},
+    };
+
+    use cool_asserts::assert_matches;
+    use futures::{future::Either, FutureExt, StreamExt};
+
+    use crate::{channel, testing::CountingWaker, AbortableSendError, WeakSendError};
+
+    use super::{CancelOutcome, SendOrCancel};
+
+    #[test]
+    fn abort_before_staging() {
+        let (mut sender, mut receiver) = channel();
+
+        {
+            let (handle, send) = sender.send_abortable(1);
+            handle.abort();
+            assert!(handle.is_aborted());
+            assert_eq!(
+                send.now_or_never(),
+                Some(Err(AbortableSendError::Aborted(1)))
+            );
+        }
+
+        assert!(receiver.next().now_or_never().is_none());
+    }
+
+    #[test]
+    fn abort_after_staging() {
+        let (mut sender, mut receiver) = channel();
+        let waker = Arc::new(CountingWaker::default());
+        let task_waker = Waker::from(waker.clone());
+        let mut cx = Context::from_waker(&task_waker);
+
+        {
+            let (handle, send) = sender.send_abortable(1);
+            let mut send = pin!(send);
+            assert!(send.as_mut().poll(&mut cx).is_pending());
+
+            // The abort comes from elsewhere, and wakes the sending task
+            handle.abort();
+            assert_eq!(waker.count(), 1);
+            assert_eq!(
+                send.poll(&mut cx),
+                Poll::Ready(Err(AbortableSendError::Aborted(1)))
+            );
+        }
+
+        // The reclaimed item is no longer available to the receiver
+        assert!(receiver.next().now_or_never().is_none());
+    }
+
+    #[test]
+    fn abort_after_receive() {
+        let (mut sender, mut receiver) = channel();
+        let (handle, send) = sender.send_abortable(1);
+        let mut send = pin!(send);
+
+        assert!(send.as_mut().now_or_never().is_none());
+        assert_eq!(receiver.next().now_or_never(), Some(Some(1)));
+
+        // The item was already delivered, so the abort has no effect
+        handle.abort();
+        assert_eq!(send.now_or_never(), Some(Ok(())));
+    }
+
+    #[tokio::test]
+    async fn abort_from_another_task() {
+        let (mut sender, _receiver) = channel();
+        let (handle, send) = sender.send_abortable(String::from("item"));
+
+        let abort_task = tokio::task::spawn(async move {
+            tokio::task::yield_now().await;
+            handle.abort();
+        });
+
+        assert_eq!(
+            send.await,
+            Err(AbortableSendError::Aborted(String::from("item")))
+        );
+        abort_task.await.unwrap();
+    }
+
+    #[test]
+    fn abort_after_disconnect() {
+        let (mut sender, receiver) = channel();
+        drop(receiver);
+
+        let (handle, send) = sender.send_abortable(1);
+        assert_eq!(
+            send.now_or_never(),
+            Some(Err(AbortableSendError::Disconnected(1)))
+        );
+        handle.abort();
+    }
+
+    #[test]
+    fn cancel_before_receive() {
+        let (mut sender, mut receiver) = channel();
+
+        {
+            let mut send = pin!(sender.send_cancellable(1));
+            assert!(send.as_mut().now_or_never().is_none());
+            assert_eq!(send.cancel(), CancelOutcome::Reclaimed(1));
+        }
+
+        // The reclaimed item is no longer available to the receiver
+        assert!(receiver.next().now_or_never().is_none());
+    }
+
+    #[test]
+    fn cancel_after_receive() {
+        let (mut sender, mut receiver) = channel();
+        let mut send = pin!(sender.send_cancellable(1));
+
+        assert!(send.as_mut().now_or_never().is_none());
+        assert_eq!(receiver.next().now_or_never(), Some(Some(1)));
+        assert_eq!(send.cancel(), CancelOutcome::Delivered);
+    }
+
+    #[test]
+    fn cancel_while_peeked() {
+        let (mut sender, mut receiver) = channel();
+
+        {
+            let mut send = pin!(sender.send_cancellable(1));
+            assert!(send.as_mut().now_or_never().is_none());
+            assert_eq!(receiver.peek().now_or_never(), Some(Some(&1)));
+            assert_eq!(send.cancel(), CancelOutcome::Delivered);
+        }
+
+        assert_eq!(receiver.next().now_or_never(), Some(Some(1)));
+    }
+
+    #[test]
+    fn cancel_after_disconnect() {
+        let (mut sender, receiver) = channel();
+        let mut send = pin!(sender.send_cancellable(1));
+
+        assert!(send.as_mut().now_or_never().is_none());
+        drop(receiver);
+        assert_eq!(send.cancel(), CancelOutcome::Reclaimed(1));
+    }
+
+    #[test]
+    fn cancel_before_first_poll() {
+        let (mut sender, _receiver) = channel();
+        let send = pin!(sender.send_cancellable(1));
+        assert_eq!(send.cancel(), CancelOutcome::Reclaimed(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "Don't cancel futures after they completed")]
+    fn cancel_after_completion() {
+        let (mut sender, receiver) = channel();
+        drop(receiver);
+
+        let mut send = pin!(sender.send_cancellable(1));
+        assert_matches!(send.as_mut().now_or_never(), Some(Err(_)));
+        let _ = send.cancel();
+    }
+
+    #[tokio::test]
+    async fn send_or_sent() {
+        let (mut sender, mut receiver) = channel();
+
+        let receiver_task = tokio::task::spawn(async move { receiver.recv().await });
+        let outcome = sender.send_or(1, std::future::pending::<()>()).await;
+        assert_eq!(outcome, SendOrCancel::Sent);
+        assert_eq!(receiver_task.await.unwrap(), Some(1));
+    }
+
+    #[tokio::test]
+    async fn send_or_disconnected() {
+        let (mut sender, receiver) = channel();
+        drop(receiver);
+
+        let outcome = sender.send_or(1, std::future::pending::<()>()).await;
+        assert_eq!(outcome, SendOrCancel::Disconnected(1));
+    }
+
+    #[tokio::test]
+    async fn send_or_cancelled() {
+        let (mut sender, mut receiver) = channel();
+        let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel::<()>();
+
+        let send_task = tokio::task::spawn(async move {
+            let outcome = sender.send_or(1, shutdown_rx).await;
+            (outcome, sender)
+        });
+
+        tokio::task::yield_now().await;
+        shutdown_tx.send(()).unwrap();
+        let (outcome, mut sender) = send_task.await.unwrap();
+        assert_eq!(outcome, SendOrCancel::Cancelled(1));
+
+        // The reclaimed item is no longer available, but the channel is
+        // still usable
+        assert!(receiver.next().now_or_never().is_none());
+        let receiver_task = tokio::task::spawn(async move { receiver.recv().await });
+        sender.send(2).await.unwrap();
+        assert_eq!(receiver_task.await.unwrap(), Some(2));
+    }
+
+    #[test]
+    fn send_or_cancelled_after_peek() {
+        let (mut sender, mut receiver) = channel();
+        let (shutdown_tx, shutdown_rx) = futures::channel::oneshot::channel::<()>();
+
+        {
+            let mut send = pin!(sender.send_or(1, shutdown_rx));
+            assert!(send.as_mut().now_or_never().is_none());
+            assert_eq!(receiver.peek().now_or_never(), Some(Some(&1)));
+
+            // The receiver is holding the item, so it can't be reclaimed; the
+            // cancelled send is reported as a success.
+            shutdown_tx.send(()).unwrap();
+            assert_eq!(send.now_or_never(), Some(SendOrCancel::Sent));
+        }
+
+        assert_eq!(receiver.next().now_or_never(), Some(Some(1)));
+    }
+
+    #[test]
+    fn recv_or_future_other_wins() {
+        let (mut sender, mut receiver) = channel();
+        let (ready_tx, ready_rx) = futures::channel::oneshot::channel::<i32>();
+
+        let counter = Arc::new(CountingWaker::default());
+        let waker = Waker::from(counter.clone());
+        let mut cx = Context::from_waker(&waker);
+
+        {
+            let mut recv = pin!(receiver.recv_or_future(ready_rx));
+            assert!(recv.as_mut().poll(&mut cx).is_pending());
+
+            ready_tx.send(5).unwrap();
+            assert_matches!(recv.poll(&mut cx), Poll::Ready(Either::Right(Ok(5))));
+        }
+        let woken = counter.count();
+
+        // The cancelled receive's waker was cleared, so staging an item
+        // doesn't wake it, but a subsequent receive still gets the item.
+        let mut send = pin!(sender.send(1));
+        assert!(send.as_mut().poll(&mut cx).is_pending());
+        assert_eq!(counter.count(), woken);
+
+        assert_matches!(
+            receiver
+                .recv_or_future(std::future::pending::<()>())
+                .now_or_never(),
+            Some(Either::Left(Some(1)))
+        );
+        assert_matches!(send.poll(&mut cx), Poll::Ready(Ok(())));
+    }
+
+    #[test]
+    fn recv_or_future_prefers_item() {
+        let (mut sender, mut receiver) = channel();
+        let mut send = pin!(sender.send(1));
+        assert!(send.as_mut().now_or_never().is_none());
+
+        assert_matches!(
+            receiver
+                .recv_or_future(std::future::ready(()))
+                .now_or_never(),
+            Some(Either::Left(Some(1)))
+        );
+    }
+
+    #[test]
+    fn weak_send_reclaims_if_not_taken() {
+        let (mut sender, mut receiver) = channel();
+        let mut cx = Context::from_waker(Waker::noop());
+
+        {
+            let mut send = pin!(sender.weak_send(1));
+            assert!(send.as_mut().poll(&mut cx).is_pending());
+            assert_matches!(
+                send.poll(&mut cx),
+                Poll::Ready(Err(WeakSendError::WouldBlock(1)))
+            );
+        }
+
+        // The reclaimed item is no longer available to the receiver
+        assert!(receiver.next().now_or_never().is_none());
+
+        {
+            let mut send = pin!(sender.weak_send(2));
+            assert!(send.as_mut().poll(&mut cx).is_pending());
+            assert_eq!(receiver.next().now_or_never(), Some(Some(2)));
+            assert_matches!(send.poll(&mut cx), Poll::Ready(Ok(())));
+        }
+
+        drop(receiver);
+        assert_matches!(
+            sender.weak_send(3).now_or_never(),
+            Some(Err(WeakSendError::Disconnected(3)))
+        );
+    }
+
+    #[tokio::test]
+    async fn weak_send_to_waiting_receiver() {
+        let (mut sender, mut receiver) = channel();
+
+        let receiver_task = tokio::task::spawn(async move { receiver.recv().await });
+        tokio::task::yield_now().await;
+
+        let sender_task = tokio::task::spawn(async move { sender.weak_send(1).await });
+        assert_matches!(sender_task.await.unwrap(), Ok(()));
+        assert_eq!(receiver_task.await.unwrap(), Some(1));
+    }
+}
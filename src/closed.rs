@@ -0,0 +1,181 @@
+/*!
+Reusable disconnect notifications, which any number of tasks can await.
+*/
+
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Mutex,
+    task::{Context, Poll, Waker},
+};
+
+use twinsies::Joint;
+
+use crate::{Inner, Receiver, Sender};
+
+/// A set of wakers, all of which are woken when the channel disconnects.
+/// Unlike `AtomicWaker`, this supports any number of waiting tasks.
+#[derive(Debug, Default)]
+pub(crate) struct Waiters {
+    // Each waiting future owns one slot, by index. Vacated slots are reused.
+    slots: Mutex<Vec<Option<Waker>>>,
+}
+
+impl Waiters {
+    /// Register `waker` in the slot at `key`, allocating a slot if `key` is
+    /// `None`.
+    fn register(&self, key: &mut Option<usize>, waker: &Waker) {
+        let mut slots = self.slots.lock().unwrap_or_else(|err| err.into_inner());
+
+        match *key {
+            Some(index) => match &mut slots[index] {
+                Some(current) => current.clone_from(waker),
+                slot => *slot = Some(waker.clone()),
+            },
+            None => {
+                let index = match slots.iter().position(Option::is_none) {
+                    Some(index) => index,
+                    None => {
+                        slots.push(None);
+                        slots.len() - 1
+                    }
+                };
+
+                slots[index] = Some(waker.clone());
+                *key = Some(index);
+            }
+        }
+    }
+
+    /// Release the slot at `key`.
+    fn remove(&self, key: usize) {
+        let mut slots = self.slots.lock().unwrap_or_else(|err| err.into_inner());
+        slots[key] = None;
+    }
+
+    pub(crate) fn wake_all(&mut self) {
+        let slots = self.slots.get_mut().unwrap_or_else(|err| err.into_inner());
+        slots.drain(..).flatten().for_each(Waker::wake);
+    }
+}
+
+impl<T> Sender<T> {
+    /// Wait for the receiver to disconnect.
+    ///
+    /// Any number of these futures can be awaited at once, for instance by
+    /// several subsystems that each need to react when the receiver goes
+    /// away. If the receiver has already disconnected, the future resolves
+    /// immediately.
+    #[inline]
+    pub fn close_notified(&self) -> ClosedFut<'_, T> {
+        ClosedFut::new(&self.inner)
+    }
+}
+
+impl<T> Receiver<T> {
+    /// Wait for the sender to disconnect.
+    ///
+    /// Any number of these futures can be awaited at once. If the sender has
+    /// already disconnected, the future resolves immediately. Note that a
+    /// [peeked][Receiver::peek] item can still be received after the sender
+    /// disconnects.
+    #[inline]
+    pub fn close_notified(&self) -> ClosedFut<'_, T> {
+        ClosedFut::new(&self.inner)
+    }
+}
+
+/// Future for the [`Sender::close_notified`] and
+/// [`Receiver::close_notified`] methods, which resolves when the other half
+/// of the channel disconnects. See their documentation for details.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct ClosedFut<'a, T> {
+    inner: &'a Joint<Inner<T>>,
+
+    // Our slot in the waiters list, once we've registered
+    key: Option<usize>,
+}
+
+impl<'a, T> ClosedFut<'a, T> {
+    #[inline]
+    fn new(inner: &'a Joint<Inner<T>>) -> Self {
+        Self { inner, key: None }
+    }
+}
+
+impl<T> std::fmt::Debug for ClosedFut<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ClosedFut")
+            .field("inner", &self.inner)
+            .field("key", &self.key)
+            .finish()
+    }
+}
+
+impl<T> Future for ClosedFut<'_, T> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let this = &mut *self;
+
+        // While we hold the lock, `Inner` can't be dropped, so we can't miss
+        // the wakeup from its destructor.
+        let Some(lock) = this.inner.lock() else {
+            this.key = None;
+            return Poll::Ready(());
+        };
+
+        lock.close_waiters.register(&mut this.key, cx.waker());
+        Poll::Pending
+    }
+}
+
+impl<T> Drop for ClosedFut<'_, T> {
+    fn drop(&mut self) {
+        let Some(key) = self.key else { return };
+        let Some(lock) = self.inner.lock() else { return };
+        lock.close_waiters.remove(key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{future::join_all, FutureExt};
+
+    use crate::channel;
+
+    #[tokio::test]
+    async fn many_waiters_notified() {
+        let (sender, receiver) = channel::<i32>();
+
+        let receiver_task = tokio::task::spawn(async move {
+            tokio::task::yield_now().await;
+            drop(receiver);
+        });
+
+        join_all((0..4).map(|_| sender.close_notified())).await;
+        receiver_task.await.unwrap();
+
+        // Once closed, the future resolves immediately
+        assert_eq!(sender.close_notified().now_or_never(), Some(()));
+    }
+
+    #[test]
+    fn dropped_waiters_release_slots() {
+        let (sender, receiver) = channel::<i32>();
+
+        let mut first = sender.close_notified();
+        let mut second = sender.close_notified();
+        assert_eq!((&mut first).now_or_never(), None);
+        assert_eq!((&mut second).now_or_never(), None);
+        drop(first);
+
+        let mut third = receiver.close_notified();
+        assert_eq!((&mut third).now_or_never(), None);
+        assert_eq!(third.key, Some(0));
+        drop(third);
+
+        drop(receiver);
+        assert_eq!(second.now_or_never(), Some(()));
+    }
+}
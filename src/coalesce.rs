@@ -0,0 +1,464 @@
+/*!
+A keyed channel whose staged items coalesce: sending an item with the same
+key as one that's still waiting to be received replaces it.
+
+This is useful for streams of "latest value per entity" updates, where a
+slow consumer only cares about the most recent update for each key. Unlike
+the other channels in this crate, a send doesn't wait for the receiver;
+instead, the items waiting to be received are bounded by the number of
+distinct keys among them. To wait for the receiver to catch up, use
+[`Sender::flush`].
+
+Items are received in the order their keys were first staged. When an item
+replaces a staged one, it takes over that item's place in line, rather than
+moving to the back.
+
+```
+# futures::executor::block_on(async move {
+use handoff::coalesce;
+use futures::StreamExt;
+
+let (mut sender, receiver) = coalesce::channel();
+
+sender.send(("alice", 1)).unwrap();
+sender.send(("bob", 1)).unwrap();
+
+// Alice's first update hasn't been received yet, so it's replaced
+assert_eq!(sender.send(("alice", 2)).unwrap(), Some(1));
+
+drop(sender);
+let updates: Vec<_> = receiver.collect().await;
+assert_eq!(updates, [("alice", 2), ("bob", 1)]);
+# });
+```
+*/
+
+use std::{
+    collections::VecDeque,
+    fmt::{self, Debug, Formatter},
+    future::Future,
+    mem,
+    pin::Pin,
+    sync::{Arc, Mutex, MutexGuard},
+    task::{Context, Poll, Waker},
+};
+
+use futures_util::stream::{FusedStream, Stream, StreamExt};
+
+use crate::{SendError, TryRecvError};
+
+/// Create a coalescing channel. See the [module documentation][self] for
+/// details.
+#[must_use]
+pub fn channel<K: Eq, T>() -> (Sender<K, T>, Receiver<K, T>) {
+    let shared = Arc::new(Shared {
+        state: Mutex::new(State {
+            entries: VecDeque::new(),
+            sender_connected: true,
+            receiver_connected: true,
+            sender_waker: None,
+            receiver_waker: None,
+        }),
+    });
+
+    (
+        Sender {
+            shared: shared.clone(),
+        },
+        Receiver { shared },
+    )
+}
+
+struct Shared<K, T> {
+    state: Mutex<State<K, T>>,
+}
+
+impl<K, T> Shared<K, T> {
+    #[inline]
+    fn lock(&self) -> MutexGuard<'_, State<K, T>> {
+        // None of the critical sections can panic while the state is
+        // inconsistent, so it's fine to ignore poisoning.
+        self.state.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+struct State<K, T> {
+    // The staged items, at most one per key, in the order their keys were
+    // first staged
+    entries: VecDeque<(K, T)>,
+
+    sender_connected: bool,
+    receiver_connected: bool,
+
+    // The sender's waker, while it's waiting in `flush`
+    sender_waker: Option<Waker>,
+
+    // The receiver's waker, while it's waiting for an item
+    receiver_waker: Option<Waker>,
+}
+
+/// The sending half of a coalescing channel.
+///
+/// This object is created by the [`channel`] function. See the
+/// [module documentation][self] for details.
+pub struct Sender<K, T> {
+    shared: Arc<Shared<K, T>>,
+}
+
+impl<K: Eq, T> Sender<K, T> {
+    /// Stage an item for the receiver, without waiting for it to be
+    /// received.
+    ///
+    /// If an item with the same key is already staged, it's replaced, and
+    /// its value is returned. Otherwise, the item is added to the back of
+    /// the line, and this returns `None`. Finding a staged item takes time
+    /// proportional to the number of staged items. If the receiver has
+    /// disconnected, the item is returned in a [`SendError`].
+    pub fn send(&mut self, item: (K, T)) -> Result<Option<T>, SendError<(K, T)>> {
+        let mut state = self.shared.lock();
+
+        if !state.receiver_connected {
+            return Err(SendError(item));
+        }
+
+        let (key, value) = item;
+
+        if let Some((_, staged)) = state.entries.iter_mut().find(|(k, _)| *k == key) {
+            return Ok(Some(mem::replace(staged, value)));
+        }
+
+        state.entries.push_back((key, value));
+        let waker = state.receiver_waker.take();
+        drop(state);
+
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+
+        Ok(None)
+    }
+}
+
+impl<K, T> Sender<K, T> {
+    /// Wait until the receiver has received every staged item.
+    ///
+    /// If the receiver disconnects first, this returns an error; any items
+    /// it didn't receive are dropped.
+    #[inline]
+    pub fn flush(&mut self) -> FlushFut<'_, K, T> {
+        FlushFut { sender: self }
+    }
+
+    /// Check if the receiver is still connected.
+    ///
+    /// Note that the receiver may disconnect at any time, so a `true` result
+    /// is only a hint. Once this returns `false`, though, it will never again
+    /// return `true`.
+    #[inline]
+    #[must_use]
+    pub fn is_connected(&self) -> bool {
+        self.shared.lock().receiver_connected
+    }
+
+    /// The number of items that are staged, waiting to be received.
+    #[inline]
+    #[must_use]
+    pub fn staged_len(&self) -> usize {
+        self.shared.lock().entries.len()
+    }
+}
+
+impl<K, T> Drop for Sender<K, T> {
+    fn drop(&mut self) {
+        let mut state = self.shared.lock();
+        state.sender_connected = false;
+        let waker = state.receiver_waker.take();
+        drop(state);
+
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+impl<K, T> Debug for Sender<K, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sender")
+            .field("connected", &self.is_connected())
+            .field("staged", &self.staged_len())
+            .finish()
+    }
+}
+
+/// Future for the [`flush`][Sender::flush] method. See its documentation
+/// for details.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct FlushFut<'a, K, T> {
+    sender: &'a mut Sender<K, T>,
+}
+
+impl<K, T> Debug for FlushFut<'_, K, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FlushFut")
+            .field("sender", &self.sender)
+            .finish()
+    }
+}
+
+impl<K, T> Future for FlushFut<'_, K, T> {
+    type Output = Result<(), SendError<()>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.sender.shared.lock();
+
+        if !state.receiver_connected {
+            return Poll::Ready(Err(SendError(())));
+        }
+
+        if state.entries.is_empty() {
+            return Poll::Ready(Ok(()));
+        }
+
+        match &mut state.sender_waker {
+            Some(waker) => waker.clone_from(cx.waker()),
+            waker @ None => *waker = Some(cx.waker().clone()),
+        }
+
+        Poll::Pending
+    }
+}
+
+/// The receiving half of a coalescing channel.
+///
+/// This object is created by the [`channel`] function. Like the
+/// single-consumer [`Receiver`][crate::Receiver], it implements [`Stream`].
+/// See the [module documentation][self] for details.
+pub struct Receiver<K, T> {
+    shared: Arc<Shared<K, T>>,
+}
+
+impl<K, T> Receiver<K, T> {
+    /// Receive the next staged item, along with its key.
+    ///
+    /// This method will asynchronously block until the sender stages an
+    /// item, then return that item. Alternatively, if the sender disconnects
+    /// and there are no more staged items, this will return `None`.
+    #[inline]
+    pub fn recv(&mut self) -> RecvFut<'_, K, T> {
+        RecvFut { receiver: self }
+    }
+
+    /// Attempt to receive the next staged item without blocking.
+    pub fn try_recv(&mut self) -> Result<(K, T), TryRecvError> {
+        let mut state = self.shared.lock();
+
+        match self.take_entry(&mut state) {
+            Some(entry) => Ok(entry),
+            None if state.sender_connected => Err(TryRecvError::Empty),
+            None => Err(TryRecvError::Disconnected),
+        }
+    }
+
+    /// Check if the sender is still connected. Items it already staged can
+    /// still be received after it disconnects.
+    #[inline]
+    #[must_use]
+    pub fn is_connected(&self) -> bool {
+        self.shared.lock().sender_connected
+    }
+
+    /// Take the next staged item, waking the sender if that was the last
+    /// one.
+    fn take_entry(&self, state: &mut State<K, T>) -> Option<(K, T)> {
+        let entry = state.entries.pop_front()?;
+
+        if state.entries.is_empty() {
+            if let Some(waker) = state.sender_waker.take() {
+                waker.wake();
+            }
+        }
+
+        Some(entry)
+    }
+}
+
+impl<K, T> Drop for Receiver<K, T> {
+    fn drop(&mut self) {
+        let mut state = self.shared.lock();
+        state.receiver_connected = false;
+
+        let entries = mem::take(&mut state.entries);
+        let waker = state.sender_waker.take();
+        drop(state);
+
+        // The unreceived items are dropped outside the lock
+        drop(entries);
+
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+impl<K, T> Debug for Receiver<K, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Receiver")
+            .field("connected", &self.is_connected())
+            .finish()
+    }
+}
+
+impl<K, T> Stream for Receiver<K, T> {
+    type Item = (K, T);
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<(K, T)>> {
+        let mut state = self.shared.lock();
+
+        if let Some(entry) = self.take_entry(&mut state) {
+            return Poll::Ready(Some(entry));
+        }
+
+        if !state.sender_connected {
+            return Poll::Ready(None);
+        }
+
+        match &mut state.receiver_waker {
+            Some(waker) => waker.clone_from(cx.waker()),
+            waker @ None => *waker = Some(cx.waker().clone()),
+        }
+
+        Poll::Pending
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let state = self.shared.lock();
+        let staged = state.entries.len();
+
+        match state.sender_connected {
+            true => (staged, None),
+            false => (staged, Some(staged)),
+        }
+    }
+}
+
+impl<K, T> FusedStream for Receiver<K, T> {
+    fn is_terminated(&self) -> bool {
+        let state = self.shared.lock();
+        state.entries.is_empty() && !state.sender_connected
+    }
+}
+
+/// Future type for receiving a single item from a coalescing [`Receiver`].
+/// Created by the [`recv`][Receiver::recv] method; see its documentation for
+/// details.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct RecvFut<'a, K, T> {
+    receiver: &'a mut Receiver<K, T>,
+}
+
+impl<K, T> Debug for RecvFut<'_, K, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Recv")
+            .field("receiver", &self.receiver)
+            .finish()
+    }
+}
+
+impl<K, T> Future for RecvFut<'_, K, T> {
+    type Output = Option<(K, T)>;
+
+    #[inline]
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.receiver.poll_next_unpin(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cool_asserts::assert_matches;
+    use futures::{future::join, FutureExt, StreamExt};
+
+    use crate::{SendError, TryRecvError};
+
+    use super::channel;
+
+    #[test]
+    fn same_key_coalesces() {
+        let (mut sender, mut receiver) = channel();
+
+        assert_matches!(sender.send(("a", 1)), Ok(None));
+        assert_matches!(sender.send(("a", 2)), Ok(Some(1)));
+        assert_matches!(sender.send(("a", 3)), Ok(Some(2)));
+        assert_eq!(sender.staged_len(), 1);
+
+        assert_eq!(receiver.try_recv(), Ok(("a", 3)));
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Empty));
+
+        // Once received, the key no longer coalesces
+        assert_matches!(sender.send(("a", 4)), Ok(None));
+        assert_eq!(receiver.try_recv(), Ok(("a", 4)));
+    }
+
+    #[test]
+    fn different_keys_are_independent() {
+        let (mut sender, mut receiver) = channel();
+
+        for (key, value) in [("a", 1), ("b", 1), ("c", 1), ("b", 2), ("a", 2)] {
+            sender.send((key, value)).unwrap();
+        }
+
+        // Replaced items keep their place in line
+        assert_eq!(receiver.try_recv(), Ok(("a", 2)));
+        assert_eq!(receiver.try_recv(), Ok(("b", 2)));
+        assert_eq!(receiver.try_recv(), Ok(("c", 1)));
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Empty));
+    }
+
+    #[tokio::test]
+    async fn flush_waits_for_receiver() {
+        let (mut sender, mut receiver) = channel();
+
+        sender.send((1, "one")).unwrap();
+        sender.send((2, "two")).unwrap();
+
+        let mut flush = sender.flush();
+        assert!((&mut flush).now_or_never().is_none());
+
+        let recv_task = async {
+            let first = receiver.recv().await;
+            let second = receiver.recv().await;
+            (first, second)
+        };
+
+        let (flushed, received) = join(flush, recv_task).await;
+        flushed.unwrap();
+        assert_eq!(received, (Some((1, "one")), Some((2, "two"))));
+    }
+
+    #[tokio::test]
+    async fn sender_disconnect_drains_staged_items() {
+        let (mut sender, receiver) = channel();
+
+        let recv_task = tokio::task::spawn(receiver.collect::<Vec<_>>());
+        tokio::task::yield_now().await;
+
+        sender.send(("x", 1)).unwrap();
+        sender.send(("x", 2)).unwrap();
+        drop(sender);
+
+        let received = recv_task.await.unwrap();
+        assert!(received == [("x", 2)] || received == [("x", 1), ("x", 2)]);
+    }
+
+    #[test]
+    fn receiver_disconnect() {
+        let (mut sender, receiver) = channel();
+        sender.send(("a", 1)).unwrap();
+
+        drop(receiver);
+        assert!(!sender.is_connected());
+        assert_eq!(sender.staged_len(), 0);
+        assert_matches!(sender.send(("a", 2)), Err(SendError(("a", 2))));
+        assert_matches!(sender.flush().now_or_never(), Some(Err(SendError(()))));
+    }
+}
@@ -0,0 +1,201 @@
+/*!
+Concurrent processing of received items, bounded by the channel's
+backpressure.
+*/
+
+use std::{
+    fmt::Debug,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_util::stream::{FuturesUnordered, StreamExt};
+
+use crate::Receiver;
+
+impl<T> Receiver<T> {
+    /// Receive items from the sender, and process each one with a future
+    /// created by `f`, running at most `limit` of them concurrently. The
+    /// returned future completes once the sender disconnects and every
+    /// processing future has completed.
+    ///
+    /// This is like [`StreamExt::for_each_concurrent`], but because the
+    /// channel is unbuffered, the limit is also applied to the sender: while
+    /// `limit` items are being processed, no more items are received, so the
+    /// sender stays blocked until one of them completes.
+    ///
+    /// To avoid starving other tasks when the sender is always ready and the
+    /// processing futures complete immediately, the future yields to the
+    /// executor after receiving a number of items in a single poll; see
+    /// [`Builder::coop_budget`][crate::Builder::coop_budget].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `limit` is 0.
+    pub fn for_each_concurrent_bounded<Fut, F>(
+        self,
+        limit: usize,
+        f: F,
+    ) -> ForEachConcurrentFut<T, Fut, F>
+    where
+        F: FnMut(T) -> Fut,
+        Fut: Future<Output = ()>,
+    {
+        assert!(limit > 0, "concurrency limit must be nonzero");
+
+        ForEachConcurrentFut {
+            receiver: Some(self),
+            running: FuturesUnordered::new(),
+            limit,
+            f,
+        }
+    }
+}
+
+/// Future for the
+/// [`for_each_concurrent_bounded`][Receiver::for_each_concurrent_bounded]
+/// method. See its documentation for details.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct ForEachConcurrentFut<T, Fut, F> {
+    // This is `None` after the sender disconnects.
+    receiver: Option<Receiver<T>>,
+    running: FuturesUnordered<Fut>,
+    limit: usize,
+    f: F,
+}
+
+impl<T, Fut, F> Debug for ForEachConcurrentFut<T, Fut, F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ForEachConcurrent")
+            .field("receiver", &self.receiver)
+            .field("running", &self.running.len())
+            .field("limit", &self.limit)
+            .finish()
+    }
+}
+
+// The processing futures are pinned in their own allocations, and the
+// closure is never pinned.
+impl<T, Fut, F> Unpin for ForEachConcurrentFut<T, Fut, F> {}
+
+impl<T, Fut, F> Future for ForEachConcurrentFut<T, Fut, F>
+where
+    F: FnMut(T) -> Fut,
+    Fut: Future<Output = ()>,
+{
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let this = self.get_mut();
+        let mut budget = this
+            .receiver
+            .as_ref()
+            .map_or(u32::MAX, |receiver| receiver.coop_budget);
+
+        loop {
+            // Receive as many items as we have room for
+            while this.running.len() < this.limit {
+                let Some(receiver) = &mut this.receiver else { break };
+
+                match receiver.poll_next_unpin(cx) {
+                    Poll::Ready(Some(item)) => this.running.push((this.f)(item)),
+                    Poll::Ready(None) => this.receiver = None,
+                    Poll::Pending => break,
+                }
+
+                budget = budget.saturating_sub(1);
+
+                if budget == 0 {
+                    cx.waker().wake_by_ref();
+                    return Poll::Pending;
+                }
+            }
+
+            match this.running.poll_next_unpin(cx) {
+                // A future completed, so there might be room for another item
+                Poll::Ready(Some(())) => continue,
+
+                // Nothing is running; we're done if the sender is, too
+                Poll::Ready(None) if this.receiver.is_none() => return Poll::Ready(()),
+                Poll::Ready(None) | Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use crate::channel;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn runs_at_most_limit_at_once() {
+        let (mut sender, receiver) = channel();
+
+        let sender_task = tokio::task::spawn(async move {
+            for i in 0..20 {
+                sender.send(i).await.unwrap();
+            }
+        });
+
+        let running = Arc::new(AtomicUsize::new(0));
+        let max_running = Arc::new(AtomicUsize::new(0));
+        let processed = Arc::new(AtomicUsize::new(0));
+
+        receiver
+            .for_each_concurrent_bounded(3, |_item| {
+                let running = running.clone();
+                let max_running = max_running.clone();
+                let processed = processed.clone();
+
+                async move {
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_running.fetch_max(now, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(5)).await;
+                    running.fetch_sub(1, Ordering::SeqCst);
+                    processed.fetch_add(1, Ordering::SeqCst);
+                }
+            })
+            .await;
+
+        sender_task.await.unwrap();
+        assert_eq!(processed.load(Ordering::SeqCst), 20);
+        assert_eq!(max_running.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn limit_blocks_sender() {
+        let (mut sender, receiver) = channel();
+        let (release_tx, release_rx) = tokio::sync::watch::channel(false);
+
+        let process = tokio::task::spawn(receiver.for_each_concurrent_bounded(2, move |_item| {
+            let mut release_rx = release_rx.clone();
+            async move {
+                release_rx.wait_for(|&released| released).await.unwrap();
+            }
+        }));
+
+        sender.send(1).await.unwrap();
+        sender.send(2).await.unwrap();
+
+        // Both slots are busy, so the third item isn't taken
+        assert!(
+            tokio::time::timeout(Duration::from_millis(20), sender.send(3))
+                .await
+                .is_err()
+        );
+
+        release_tx.send(true).unwrap();
+        sender.send(3).await.unwrap();
+        drop(sender);
+        process.await.unwrap();
+    }
+}
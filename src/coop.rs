@@ -0,0 +1,192 @@
+/*!
+Cooperative yielding for tight send loops.
+*/
+
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use pin_project::pin_project;
+
+use crate::{SendError, SendFut, Sender};
+
+impl<T> Sender<T> {
+    /// Asynchronously send an item to the receiver, periodically yielding to
+    /// the executor if sends never have to wait.
+    ///
+    /// When the receiver is always ready and waiting, a producer can send
+    /// items in a tight loop without the surrounding task ever giving other
+    /// tasks a chance to run (for instance, if the sender and receiver are
+    /// driven by the same task, with `join`). To prevent this, `send_coop`
+    /// counts consecutive sends that were taken by an already-waiting
+    /// receiver. Once that count reaches the budget configured with
+    /// [`Builder::coop_budget`][crate::Builder::coop_budget], the next send
+    /// returns `Pending` once (waking itself immediately) before it makes its
+    /// item available, similar to tokio's cooperative scheduling. Any send
+    /// that has to wait for the receiver resets the count.
+    ///
+    /// Otherwise, this behaves identically to [`send`][Sender::send].
+    #[inline]
+    pub fn send_coop(&mut self, item: T) -> SendCoopFut<'_, T> {
+        SendCoopFut {
+            send: SendFut::new(&self.inner, item),
+            streak: &mut self.coop_streak,
+            budget: self.coop_budget,
+            budget_checked: false,
+            blocked: false,
+        }
+    }
+}
+
+/// Future for the [`send_coop`][Sender::send_coop] method. See its
+/// documentation for details.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+#[pin_project]
+#[derive(Debug)]
+pub struct SendCoopFut<'a, T> {
+    #[pin]
+    send: SendFut<'a, T>,
+    streak: &'a mut u32,
+    budget: u32,
+
+    // True once we've checked whether we need to yield before sending
+    budget_checked: bool,
+
+    // True if we yielded, or the receiver wasn't waiting when we staged our
+    // item
+    blocked: bool,
+}
+
+impl<T> Future for SendCoopFut<'_, T> {
+    type Output = Result<(), SendError<T>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+
+        if !*this.budget_checked {
+            *this.budget_checked = true;
+
+            // Yielding counts as blocking, so this send won't count towards
+            // the next streak.
+            if **this.streak >= *this.budget {
+                *this.blocked = true;
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+        }
+
+        let blocked = this.blocked;
+        let result = futures_util::ready!(this.send.poll_with_hook(cx, || *blocked = true));
+
+        if result.is_ok() {
+            match *blocked {
+                true => **this.streak = 0,
+                false => **this.streak += 1,
+            }
+        }
+
+        Poll::Ready(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        future::Future,
+        pin::pin,
+        task::{Context, Poll, Waker},
+    };
+
+    use futures::{future::join, FutureExt};
+
+    use crate::Builder;
+
+    /// Run a send and receive of `item` on the same task, returning the
+    /// number of times the send returned `Pending`.
+    fn send_into_waiting_receiver(
+        sender: &mut crate::Sender<i32>,
+        receiver: &mut crate::Receiver<i32>,
+        item: i32,
+    ) -> usize {
+        let mut cx = Context::from_waker(Waker::noop());
+        let mut recv = receiver.recv();
+        assert!(recv.poll_unpin(&mut cx).is_pending());
+
+        let mut send = pin!(sender.send_coop(item));
+        let mut pending = 0;
+
+        while send.as_mut().poll(&mut cx).is_pending() {
+            pending += 1;
+
+            if let Poll::Ready(received) = recv.poll_unpin(&mut cx) {
+                assert_eq!(received, Some(item));
+            }
+        }
+
+        pending
+    }
+
+    #[test]
+    fn yields_after_budget() {
+        let (mut sender, mut receiver) = Builder::new().coop_budget(3).channel();
+
+        let pending: Vec<usize> = (0..8)
+            .map(|i| send_into_waiting_receiver(&mut sender, &mut receiver, i))
+            .collect();
+
+        // Every send is pending once while the receiver takes the item; every
+        // fourth one also yields first.
+        assert_eq!(pending, [1, 1, 1, 2, 1, 1, 1, 2]);
+    }
+
+    #[tokio::test]
+    async fn blocking_send_resets_budget() {
+        let (mut sender, mut receiver) = Builder::new().coop_budget(1).channel();
+
+        for i in 0..4 {
+            // The receiver isn't waiting yet, so each send blocks and the
+            // budget is never exhausted.
+            let (sent, received) = join(sender.send_coop(i), receiver.recv()).await;
+            sent.unwrap();
+            assert_eq!(received, Some(i));
+        }
+
+        assert_eq!(sender.coop_streak, 0);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn send_consumes_tokio_budget() {
+        let (mut sender, mut receiver) = crate::channel();
+
+        // Complete as many sends as possible within a single poll of this
+        // task, stopping once a send is refused by tokio's budget.
+        let completed = std::future::poll_fn(|cx| {
+            for i in 0..1000 {
+                let mut send = pin!(sender.send(i));
+
+                if send.as_mut().poll(cx).is_pending() && receiver.try_recv().is_err() {
+                    return Poll::Ready(i);
+                }
+
+                if send.poll(cx).is_pending() {
+                    return Poll::Ready(i);
+                }
+            }
+
+            Poll::Ready(1000)
+        })
+        .await;
+
+        assert!(completed > 0);
+        assert!(completed < 1000, "the send loop never yielded");
+
+        // Once the task has yielded, sends proceed again
+        tokio::task::yield_now().await;
+        let (sent, received) = join(sender.send(1), receiver.recv()).await;
+        sent.unwrap();
+        assert_eq!(received, Some(1));
+    }
+}
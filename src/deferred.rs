@@ -0,0 +1,114 @@
+/*!
+Sends that construct their item lazily, directly in the send future.
+*/
+
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use pin_project::pin_project;
+
+use crate::{SendError, SendFut, Sender};
+
+impl<T> Sender<T> {
+    /// Asynchronously send an item to the receiver, constructing it with
+    /// `make` only once the send is first polled.
+    ///
+    /// The item is constructed directly in the future's staging slot, rather
+    /// than being moved into it, which saves a copy for very large items.
+    /// If the receiver has already disconnected when the send is first
+    /// polled, `make` is never called, so no work is wasted on an item that
+    /// can't be delivered. Otherwise, this behaves like
+    /// [`send`][Sender::send], except that on a disconnect the item is
+    /// dropped rather than returned.
+    #[inline]
+    pub fn send_with<F>(&mut self, make: F) -> SendWithFut<'_, T, F>
+    where
+        F: FnOnce() -> T,
+    {
+        SendWithFut {
+            send: SendFut::new_empty(&self.inner),
+            make: Some(make),
+        }
+    }
+}
+
+/// Future for the [`send_with`][Sender::send_with] method. See its
+/// documentation for details.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+#[pin_project]
+#[derive(Debug)]
+pub struct SendWithFut<'a, T, F> {
+    #[pin]
+    send: SendFut<'a, T>,
+
+    // This is `None` once the item has been constructed
+    make: Option<F>,
+}
+
+impl<T, F> Future for SendWithFut<'_, T, F>
+where
+    F: FnOnce() -> T,
+{
+    type Output = Result<(), SendError<()>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut this = self.project();
+
+        if let Some(make) = this.make.take() {
+            if !this.send.inner.alive() {
+                return Poll::Ready(Err(SendError(())));
+            }
+
+            this.send.as_mut().fill(make);
+        }
+
+        this.send.poll(cx).map_err(|SendError(_)| SendError(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use cool_asserts::assert_matches;
+    use futures::{future::join, FutureExt};
+
+    use crate::{channel, SendError};
+
+    #[tokio::test]
+    async fn constructs_lazily() {
+        let (mut sender, mut receiver) = channel();
+        let calls = Cell::new(0);
+
+        let send = sender.send_with(|| {
+            calls.set(calls.get() + 1);
+            Box::new([7_u8; 1 << 16])
+        });
+        assert_eq!(calls.get(), 0);
+
+        let (sent, received) = join(send, receiver.recv()).await;
+        sent.unwrap();
+        assert_eq!(calls.get(), 1);
+        assert!(received.unwrap().iter().all(|&byte| byte == 7));
+    }
+
+    #[test]
+    fn disconnected_receiver_skips_construction() {
+        let (mut sender, receiver) = channel::<i32>();
+        drop(receiver);
+
+        let send = sender.send_with(|| panic!("the item shouldn't be constructed"));
+        assert_matches!(send.now_or_never(), Some(Err(SendError(()))));
+    }
+
+    #[test]
+    fn dropped_before_polling() {
+        let (mut sender, mut receiver) = channel::<i32>();
+
+        drop(sender.send_with(|| panic!("the item shouldn't be constructed")));
+        assert!(receiver.try_recv().is_err());
+    }
+}
@@ -0,0 +1,242 @@
+/*!
+Diagnostic aids for detecting stuck channels in tests and CI.
+*/
+
+use std::{
+    fmt::Debug,
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering::Relaxed},
+        Mutex,
+    },
+    task::{Context, Poll, Waker},
+};
+
+use thiserror::Error;
+
+use crate::{Receiver, RecvFut};
+
+/// An error from [`recv_or_stalled`][Receiver::recv_or_stalled], indicating
+/// that the sender appears to be stuck.
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[error("receiver was polled {polls} times while the sender was connected, without receiving an item")]
+pub struct StalledError {
+    /// The number of times the receive was polled without progress.
+    pub polls: usize,
+}
+
+/// Counts of the wakers the receiver has registered, from
+/// [`waker_registrations`][Receiver::waker_registrations].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct WakerRegistrations {
+    /// The number of registrations with a waker that wouldn't wake the same
+    /// task as the previously registered one.
+    pub new: u64,
+
+    /// The number of registrations with a waker that would wake the same
+    /// task as the previously registered one.
+    pub reused: u64,
+}
+
+/// Tracks the receiver's waker registrations for [`WakerRegistrations`].
+///
+/// This is only ever updated by the receiver, so relaxed atomics are
+/// sufficient, and the lock is never contended; they're only needed because
+/// `Inner` is shared.
+#[derive(Debug, Default)]
+pub(crate) struct RegistrationCounter {
+    new: AtomicU64,
+    reused: AtomicU64,
+
+    // The most recently registered waker, which the sender may have since
+    // taken out of the `AtomicWaker`
+    last: Mutex<Option<Waker>>,
+}
+
+impl RegistrationCounter {
+    /// Record that the receiver registered `waker`.
+    pub(crate) fn record(&self, waker: &Waker) {
+        let mut last = self.last.lock().unwrap_or_else(|err| err.into_inner());
+
+        match &mut *last {
+            Some(last) if last.will_wake(waker) => {
+                self.reused.fetch_add(1, Relaxed);
+            }
+            last => {
+                *last = Some(waker.clone());
+                self.new.fetch_add(1, Relaxed);
+            }
+        }
+    }
+
+    fn snapshot(&self) -> WakerRegistrations {
+        WakerRegistrations {
+            new: self.new.load(Relaxed),
+            reused: self.reused.load(Relaxed),
+        }
+    }
+}
+
+impl<T> Receiver<T> {
+    /// Count how many times this receiver has registered a waker, split by
+    /// whether it was a new waker or the same one as the previous
+    /// registration.
+    ///
+    /// Every poll that doesn't receive an item registers the task's waker,
+    /// as does [`register_recv_waker`][Receiver::register_recv_waker]. A
+    /// steady stream of new wakers, where reused ones would be expected,
+    /// points at something rebuilding the receive future's context on each
+    /// poll, such as a `select!` in a loop. Returns `None` once the sender
+    /// disconnects.
+    #[must_use]
+    pub fn waker_registrations(&self) -> Option<WakerRegistrations> {
+        Some(self.inner.lock()?.registrations.snapshot())
+    }
+
+    /// Attempt to receive the next item from the sender, failing with a
+    /// [`StalledError`] if the receive is polled `polls` times without
+    /// receiving an item while the sender is still connected.
+    ///
+    /// This is a diagnostic aid, intended to make suspected deadlocks fail
+    /// quickly in tests rather than hang. `polls` is approximate: every poll
+    /// that doesn't complete counts against it, including spurious polls
+    /// that weren't caused by a wakeup. Otherwise, this behaves like
+    /// [`recv`][Receiver::recv], resolving to `Ok(None)` if the sender
+    /// disconnects.
+    #[inline]
+    pub fn recv_or_stalled(&mut self, polls: usize) -> RecvOrStalledFut<'_, T> {
+        RecvOrStalledFut {
+            recv: self.recv(),
+            polls: 0,
+            limit: polls,
+        }
+    }
+}
+
+/// Future for the [`recv_or_stalled`][Receiver::recv_or_stalled] method. See
+/// its documentation for details.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct RecvOrStalledFut<'a, T> {
+    recv: RecvFut<'a, T>,
+    polls: usize,
+    limit: usize,
+}
+
+impl<T> Debug for RecvOrStalledFut<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RecvOrStalled")
+            .field("receiver", &self.recv.receiver)
+            .field("polls", &self.polls)
+            .field("limit", &self.limit)
+            .finish()
+    }
+}
+
+impl<T> Future for RecvOrStalledFut<'_, T> {
+    type Output = Result<Option<T>, StalledError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if let Poll::Ready(item) = Pin::new(&mut self.recv).poll(cx) {
+            return Poll::Ready(Ok(item));
+        }
+
+        self.polls += 1;
+
+        match self.polls >= self.limit {
+            true => Poll::Ready(Err(StalledError { polls: self.polls })),
+            false => Poll::Pending,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        future::Future,
+        pin::pin,
+        sync::Arc,
+        task::{Context, Poll, Waker},
+    };
+
+    use cool_asserts::assert_matches;
+    use futures::FutureExt;
+
+    use crate::{channel, testing::CountingWaker};
+
+    use super::{StalledError, WakerRegistrations};
+
+    #[test]
+    fn stalled_sender() {
+        let (_sender, mut receiver) = channel::<i32>();
+        let mut cx = Context::from_waker(Waker::noop());
+        let mut recv = pin!(receiver.recv_or_stalled(3));
+
+        assert!(recv.as_mut().poll(&mut cx).is_pending());
+        assert!(recv.as_mut().poll(&mut cx).is_pending());
+        assert_eq!(
+            recv.as_mut().poll(&mut cx),
+            Poll::Ready(Err(StalledError { polls: 3 }))
+        );
+    }
+
+    #[test]
+    fn progress_before_stall() {
+        let (mut sender, mut receiver) = channel();
+
+        let mut send = Box::pin(sender.send(1));
+        assert!(send.as_mut().now_or_never().is_none());
+        assert_eq!(receiver.recv_or_stalled(1).now_or_never(), Some(Ok(Some(1))));
+        assert_matches!(send.now_or_never(), Some(Ok(())));
+
+        drop(sender);
+        assert_eq!(receiver.recv_or_stalled(1).now_or_never(), Some(Ok(None)));
+    }
+
+    #[test]
+    fn counts_waker_registrations() {
+        let (mut sender, mut receiver) = channel();
+        assert_eq!(receiver.waker_registrations(), Some(WakerRegistrations::default()));
+
+        let waker = Waker::from(Arc::new(CountingWaker::default()));
+        let mut cx = Context::from_waker(&waker);
+
+        for _ in 0..10 {
+            assert!(receiver.recv().poll_unpin(&mut cx).is_pending());
+        }
+
+        assert_eq!(
+            receiver.waker_registrations(),
+            Some(WakerRegistrations { new: 1, reused: 9 })
+        );
+
+        // Sending takes the registered waker, but registering it again still
+        // counts as a reuse
+        let mut send = Box::pin(sender.send(1));
+        assert!(send.as_mut().now_or_never().is_none());
+        assert_eq!(receiver.recv().poll_unpin(&mut cx), Poll::Ready(Some(1)));
+        assert!(receiver.recv().poll_unpin(&mut cx).is_pending());
+
+        assert_eq!(
+            receiver.waker_registrations(),
+            Some(WakerRegistrations { new: 1, reused: 10 })
+        );
+
+        // Alternating between tasks registers a new waker every time
+        let other = Waker::from(Arc::new(CountingWaker::default()));
+
+        for i in 0..4 {
+            let waker = if i % 2 == 0 { &other } else { &waker };
+            receiver.register_recv_waker(waker);
+        }
+
+        assert_eq!(
+            receiver.waker_registrations(),
+            Some(WakerRegistrations { new: 5, reused: 10 })
+        );
+
+        drop(send);
+        drop(sender);
+        assert_eq!(receiver.waker_registrations(), None);
+    }
+}
@@ -0,0 +1,389 @@
+/*!
+A pair of handoff channels bundled into a request/response duplex.
+
+One side, the [`Sender`], sends requests and waits for a response to each
+one; the other side, the [`Receiver`], receives requests and responds to
+them. Each half is an ordinary handoff channel, so a request blocks until the
+receiver takes it, and then until the receiver responds.
+
+Responses are always matched with the request they belong to. If a
+[`request`][Sender::request] is cancelled after the receiver took it, the
+receiver's eventual response to it is discarded, rather than being mistaken
+for the response to the next request.
+
+```
+# futures::executor::block_on(async move {
+use handoff::duplex;
+use futures::future::join;
+
+let (mut sender, mut receiver) = duplex::channel();
+
+let client = async move {
+    assert_eq!(sender.request(2).await.unwrap(), 4);
+    assert_eq!(sender.request(5).await.unwrap(), 25);
+};
+
+let server = async move {
+    while let Some(n) = receiver.recv().await {
+        receiver.respond(n * n).await.unwrap();
+    }
+};
+
+join(client, server).await;
+# });
+```
+*/
+
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_util::{ready, stream::StreamExt};
+use pin_project::pin_project;
+use thiserror::Error;
+
+use crate::SendError;
+
+/// Create a request/response duplex channel. See the [module
+/// documentation][self] for details.
+#[must_use]
+pub fn channel<Req, Resp>() -> (Sender<Req, Resp>, Receiver<Req, Resp>) {
+    let (request_sender, request_receiver) = crate::channel();
+    let (response_sender, response_receiver) = crate::channel();
+
+    (
+        Sender {
+            requests: request_sender,
+            responses: response_receiver,
+            next_id: 0,
+        },
+        Receiver {
+            requests: request_receiver,
+            responses: response_sender,
+            current: None,
+        },
+    )
+}
+
+/// The requesting half of a duplex channel.
+///
+/// This object is created by the [`channel`] function. See the [module
+/// documentation][self] for details.
+#[derive(Debug)]
+pub struct Sender<Req, Resp> {
+    requests: crate::Sender<(u64, Req)>,
+    responses: crate::Receiver<(u64, Resp)>,
+
+    // The ID to tag the next request with
+    next_id: u64,
+}
+
+impl<Req, Resp> Sender<Req, Resp> {
+    /// Asynchronously send a request to the receiver, then wait for its
+    /// response.
+    ///
+    /// If the receiver disconnects before taking the request, the request is
+    /// returned in a [`RequestError::Disconnected`]. If it disconnects after
+    /// taking the request but before responding, this returns
+    /// [`RequestError::NoResponse`].
+    #[inline]
+    pub fn request(&mut self, request: Req) -> RequestFut<'_, Req, Resp> {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        RequestFut {
+            send: self.requests.send((id, request)),
+            responses: &mut self.responses,
+            id,
+            sent: false,
+        }
+    }
+
+    /// Check if the receiver is still connected. See
+    /// [`crate::Sender::is_connected`] for details.
+    #[inline]
+    #[must_use]
+    pub fn is_connected(&self) -> bool {
+        self.requests.is_connected()
+    }
+}
+
+/// Future for the [`request`][Sender::request] method. See its documentation
+/// for details.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+#[pin_project]
+#[derive(Debug)]
+pub struct RequestFut<'a, Req, Resp> {
+    #[pin]
+    send: crate::SendFut<'a, (u64, Req)>,
+    responses: &'a mut crate::Receiver<(u64, Resp)>,
+    id: u64,
+
+    // True once the receiver has taken the request
+    sent: bool,
+}
+
+impl<Req, Resp> Future for RequestFut<'_, Req, Resp> {
+    type Output = Result<Resp, RequestError<Req>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut this = self.project();
+
+        if !*this.sent {
+            match this.send.as_mut().poll(cx) {
+                Poll::Ready(Ok(())) => *this.sent = true,
+                Poll::Ready(Err(SendError((_, request)))) => {
+                    return Poll::Ready(Err(RequestError::Disconnected(request)))
+                }
+                Poll::Pending => {}
+            }
+        }
+
+        // Responses are received even while our request is still waiting to
+        // be taken: any response with a different ID belongs to an earlier
+        // request that was cancelled while waiting for it, and the receiver
+        // can't take our request until that response is discarded.
+        loop {
+            match ready!(this.responses.poll_next_unpin(cx)) {
+                Some((id, response)) if id == *this.id => return Poll::Ready(Ok(response)),
+                Some(_) => continue,
+                None => break,
+            }
+        }
+
+        // The receiver drops its request receiver before its response
+        // sender, so by now, the send is guaranteed to be finished.
+        Poll::Ready(match *this.sent {
+            true => Err(RequestError::NoResponse),
+            false => match this.send.poll(cx) {
+                Poll::Ready(Err(SendError((_, request)))) => {
+                    Err(RequestError::Disconnected(request))
+                }
+                Poll::Ready(Ok(())) | Poll::Pending => Err(RequestError::NoResponse),
+            },
+        })
+    }
+}
+
+/// The responding half of a duplex channel.
+///
+/// This object is created by the [`channel`] function. See the [module
+/// documentation][self] for details.
+#[derive(Debug)]
+pub struct Receiver<Req, Resp> {
+    requests: crate::Receiver<(u64, Req)>,
+    responses: crate::Sender<(u64, Resp)>,
+
+    // The ID of the most recently received request, until it's responded to
+    current: Option<u64>,
+}
+
+impl<Req, Resp> Receiver<Req, Resp> {
+    /// Attempt to receive the next request.
+    ///
+    /// This method will asynchronously block until the sender sends a
+    /// request, then return it. If the sender disconnects, this will return
+    /// `None`. The request should be answered with
+    /// [`respond`][Receiver::respond]; receiving another request first
+    /// abandons it, and the sender will never get a response to it.
+    #[inline]
+    pub fn recv(&mut self) -> RecvFut<'_, Req, Resp> {
+        RecvFut { receiver: self }
+    }
+
+    /// Asynchronously send a response to the most recently received request.
+    ///
+    /// This method will asynchronously block until the sender has received
+    /// the response. If the sender disconnects, this will instead return a
+    /// [`SendError`] containing the response. If the sender cancelled the
+    /// request, the response is discarded once the sender makes its next
+    /// request.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there's no request waiting for a response, either because
+    /// none was received or because it was already responded to.
+    #[inline]
+    pub fn respond(&mut self, response: Resp) -> RespondFut<'_, Resp> {
+        let id = self
+            .current
+            .take()
+            .expect("there's no request to respond to");
+
+        RespondFut {
+            send: self.responses.send((id, response)),
+        }
+    }
+
+    /// Check if the sender is still connected. See
+    /// [`crate::Receiver::is_connected`] for details.
+    #[inline]
+    #[must_use]
+    pub fn is_connected(&self) -> bool {
+        self.requests.is_connected()
+    }
+}
+
+/// Future for the [`recv`][Receiver::recv] method of a duplex [`Receiver`].
+/// See its documentation for details.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+#[derive(Debug)]
+pub struct RecvFut<'a, Req, Resp> {
+    receiver: &'a mut Receiver<Req, Resp>,
+}
+
+impl<Req, Resp> Future for RecvFut<'_, Req, Resp> {
+    type Output = Option<Req>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Req>> {
+        let receiver = &mut *self.receiver;
+
+        Poll::Ready(
+            ready!(receiver.requests.poll_next_unpin(cx)).map(|(id, request)| {
+                receiver.current = Some(id);
+                request
+            }),
+        )
+    }
+}
+
+/// Future for the [`respond`][Receiver::respond] method. See its
+/// documentation for details.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+#[pin_project]
+#[derive(Debug)]
+pub struct RespondFut<'a, Resp> {
+    #[pin]
+    send: crate::SendFut<'a, (u64, Resp)>,
+}
+
+impl<Resp> Future for RespondFut<'_, Resp> {
+    type Output = Result<(), SendError<Resp>>;
+
+    #[inline]
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.project()
+            .send
+            .poll(cx)
+            .map_err(|SendError((_, response))| SendError(response))
+    }
+}
+
+/// An error from a duplex [`request`][Sender::request].
+#[derive(Error, Clone, Debug, Copy, PartialEq, Eq)]
+pub enum RequestError<Req> {
+    /// The receiver disconnected before taking the request. The request is
+    /// returned.
+    #[error("tried to send a request on a disconnected channel")]
+    Disconnected(Req),
+
+    /// The receiver took the request, but disconnected without responding
+    /// to it.
+    #[error("the receiver disconnected without responding to the request")]
+    NoResponse,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        future::Future,
+        pin::pin,
+        task::{Context, Waker},
+    };
+
+    use cool_asserts::assert_matches;
+    use futures::future::join;
+
+    use super::{channel, RequestError};
+    use crate::SendError;
+
+    #[tokio::test]
+    async fn responses_match_requests_in_order() {
+        let (mut sender, mut receiver) = channel();
+
+        let server = tokio::task::spawn(async move {
+            while let Some(request) = receiver.recv().await {
+                receiver.respond(format!("re: {request}")).await.unwrap();
+            }
+        });
+
+        for i in 0..5 {
+            assert_eq!(sender.request(i).await.unwrap(), format!("re: {i}"));
+        }
+
+        drop(sender);
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn cancelled_request_response_is_discarded() {
+        let (mut sender, mut receiver) = channel();
+        let mut cx = Context::from_waker(Waker::noop());
+
+        // The receiver takes the first request, but the sender gives up on it
+        {
+            let mut request = pin!(sender.request(1));
+            assert!(request.as_mut().poll(&mut cx).is_pending());
+            assert_eq!(receiver.recv().await, Some(1));
+            assert!(request.poll(&mut cx).is_pending());
+        }
+
+        let server = async {
+            receiver.respond(10).await.unwrap();
+            assert_eq!(receiver.recv().await, Some(2));
+            receiver.respond(20).await.unwrap();
+        };
+
+        let (response, ()) = join(sender.request(2), server).await;
+        assert_eq!(response, Ok(20));
+    }
+
+    #[tokio::test]
+    async fn receiver_disconnects_before_taking_request() {
+        let (mut sender, receiver) = channel::<i32, i32>();
+        drop(receiver);
+
+        assert!(!sender.is_connected());
+        assert_matches!(sender.request(1).await, Err(RequestError::Disconnected(1)));
+    }
+
+    #[tokio::test]
+    async fn receiver_disconnects_without_responding() {
+        let (mut sender, mut receiver) = channel::<i32, i32>();
+
+        let server = async move {
+            assert_eq!(receiver.recv().await, Some(1));
+        };
+
+        let (response, ()) = join(sender.request(1), server).await;
+        assert_matches!(response, Err(RequestError::NoResponse));
+    }
+
+    #[tokio::test]
+    async fn sender_disconnects() {
+        let (mut sender, mut receiver) = channel::<i32, i32>();
+
+        // The sender gives up on its request and disconnects before the
+        // response is taken
+        {
+            let mut request = pin!(sender.request(1));
+            let mut cx = Context::from_waker(Waker::noop());
+            assert!(request.as_mut().poll(&mut cx).is_pending());
+            assert_eq!(receiver.recv().await, Some(1));
+        }
+        drop(sender);
+
+        assert_matches!(receiver.respond(10).await, Err(SendError(10)));
+        assert!(!receiver.is_connected());
+        assert_eq!(receiver.recv().await, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "there's no request to respond to")]
+    fn respond_without_request() {
+        let (_sender, mut receiver) = channel::<i32, i32>();
+        drop(receiver.respond(1));
+    }
+}
@@ -0,0 +1,129 @@
+/*!
+Sending every item from an iterator.
+*/
+
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_util::ready;
+use pin_project::pin_project;
+use twinsies::Joint;
+
+use crate::{Inner, SendError, SendFut, Sender};
+
+impl<T> Sender<T> {
+    /// Asynchronously send every item from `items` to the receiver, in
+    /// order, one at a time.
+    ///
+    /// Each item is only pulled from the iterator once the previous one was
+    /// taken. If the receiver disconnects, the item that couldn't be sent is
+    /// returned in a [`SendError`], and the rest of the iterator is dropped.
+    ///
+    /// ```
+    /// # futures::executor::block_on(async move {
+    /// use futures::{future::join, StreamExt};
+    ///
+    /// let (mut sender, receiver) = handoff::channel();
+    ///
+    /// let send_task = async move { sender.extend(1..=3).await };
+    /// let (sent, received) = join(send_task, receiver.collect::<Vec<_>>()).await;
+    ///
+    /// sent.unwrap();
+    /// assert_eq!(received, [1, 2, 3]);
+    /// # });
+    /// ```
+    #[inline]
+    pub fn extend<I>(&mut self, items: I) -> ExtendFut<'_, T, I::IntoIter>
+    where
+        I: IntoIterator<Item = T>,
+    {
+        ExtendFut {
+            inner: &self.inner,
+            items: items.into_iter(),
+            send: None,
+        }
+    }
+}
+
+/// Future for the [`extend`][Sender::extend] method. See its documentation
+/// for details.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+#[pin_project]
+#[derive(Debug)]
+pub struct ExtendFut<'a, T, I> {
+    inner: &'a Joint<Inner<T>>,
+    items: I,
+
+    // The send in progress, if any
+    #[pin]
+    send: Option<SendFut<'a, T>>,
+}
+
+impl<T, I: Iterator<Item = T>> Future for ExtendFut<'_, T, I> {
+    type Output = Result<(), SendError<T>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut this = self.project();
+
+        loop {
+            if let Some(send) = this.send.as_mut().as_pin_mut() {
+                let result = ready!(send.poll(cx));
+                this.send.set(None);
+                result?;
+            }
+
+            let Some(item) = this.items.next() else { return Poll::Ready(Ok(())) };
+            this.send.set(Some(SendFut::new(this.inner, item)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cool_asserts::assert_matches;
+    use futures::{future::join, FutureExt, StreamExt};
+
+    use crate::{channel, SendError};
+
+    #[tokio::test]
+    async fn sends_every_item() {
+        let (mut sender, receiver) = channel();
+
+        let (sent, received) = join(
+            async move { sender.extend(vec!["a", "b", "c"]).await },
+            receiver.collect::<Vec<_>>(),
+        )
+        .await;
+
+        sent.unwrap();
+        assert_eq!(received, ["a", "b", "c"]);
+    }
+
+    #[tokio::test]
+    async fn stops_at_disconnect() {
+        let (mut sender, mut receiver) = channel();
+        let mut pulled = 0;
+
+        let items = (0..10).inspect(|_| pulled += 1);
+        let recv_task = async move {
+            let first = receiver.next().await;
+            drop(receiver);
+            first
+        };
+
+        let (sent, first) = join(sender.extend(items), recv_task).await;
+
+        assert_eq!(first, Some(0));
+        assert_matches!(sent, Err(SendError(1)));
+        assert_eq!(pulled, 2);
+    }
+
+    #[test]
+    fn empty_iterator() {
+        let (mut sender, _receiver) = channel::<i32>();
+        assert_matches!(sender.extend([]).now_or_never(), Some(Ok(())));
+    }
+}
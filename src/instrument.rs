@@ -0,0 +1,427 @@
+/*!
+Generic observability hooks for channel operations, which let users wire up
+any kind of logging or metrics without this crate depending on them.
+*/
+
+use std::{
+    fmt::Debug,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use pin_project::pin_project;
+
+use crate::{Receiver, RecvFut, SendError, SendFut, Sender};
+
+/// A phase transition in a send operation, reported to the hook passed to
+/// [`Sender::send_instrumented`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SendPhase {
+    /// The item was made available to the receiver, and the sender is now
+    /// blocked waiting for it to be taken. This is reported at most once per
+    /// send.
+    Staged,
+
+    /// The receiver took the item; the send succeeded.
+    Taken,
+
+    /// The receiver disconnected before taking the item; the send failed.
+    Failed,
+}
+
+impl<T> Sender<T> {
+    /// Asynchronously send an item to the receiver, reporting each phase of
+    /// the send to `hook`.
+    ///
+    /// This behaves identically to [`send`][Sender::send], but calls `hook`
+    /// with a [`SendPhase`] as the send progresses: [`Staged`] once the item
+    /// is made available to the receiver, and then exactly one of [`Taken`]
+    /// or [`Failed`] when the send completes. A send that fails before the
+    /// item was ever staged reports only [`Failed`].
+    ///
+    /// The hook is called from inside the future's `poll`, so it should be
+    /// cheap. It's always called after the channel's internal state is
+    /// consistent, so a panicking hook won't corrupt the channel or lose the
+    /// item (dropping the future will reclaim it as usual).
+    ///
+    /// [`Staged`]: SendPhase::Staged
+    /// [`Taken`]: SendPhase::Taken
+    /// [`Failed`]: SendPhase::Failed
+    #[inline]
+    pub fn send_instrumented<F>(&mut self, item: T, hook: F) -> InstrumentedSendFut<'_, T, F>
+    where
+        F: FnMut(SendPhase),
+    {
+        InstrumentedSendFut {
+            send: self.send(item),
+            hook,
+            staged: false,
+        }
+    }
+}
+
+/// Future for the [`send_instrumented`][Sender::send_instrumented] method.
+/// See its documentation for details.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+#[pin_project]
+pub struct InstrumentedSendFut<'a, T, F> {
+    #[pin]
+    send: SendFut<'a, T>,
+    hook: F,
+    staged: bool,
+}
+
+impl<T, F> Debug for InstrumentedSendFut<'_, T, F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InstrumentedSendFut")
+            .field("send", &self.send)
+            .field("staged", &self.staged)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T, F> Future for InstrumentedSendFut<'_, T, F>
+where
+    F: FnMut(SendPhase),
+{
+    type Output = Result<(), SendError<T>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+
+        match this.send.poll(cx) {
+            Poll::Pending => {
+                if !*this.staged {
+                    *this.staged = true;
+                    (this.hook)(SendPhase::Staged);
+                }
+
+                Poll::Pending
+            }
+            Poll::Ready(result) => {
+                (this.hook)(match result {
+                    Ok(()) => SendPhase::Taken,
+                    Err(_) => SendPhase::Failed,
+                });
+
+                Poll::Ready(result)
+            }
+        }
+    }
+}
+
+impl<T> Sender<T> {
+    /// Wrap this sender so that `hook` is called every time a send has to
+    /// block waiting for the receiver.
+    ///
+    /// A send "blocks" when its item is staged while the receiver isn't
+    /// already parked waiting for one; that is, every time the producer
+    /// outpaces the consumer. This directly measures backpressure events
+    /// from the producer's perspective. Sends into an already-waiting
+    /// receiver, and sends that fail due to a disconnect, don't call the
+    /// hook.
+    ///
+    /// The hook is called from inside the send future's `poll`, so it should
+    /// be cheap.
+    #[inline]
+    #[must_use]
+    pub fn inspect_blocking<F>(&mut self, hook: F) -> InspectBlocking<'_, T, F>
+    where
+        F: FnMut(),
+    {
+        InspectBlocking { sender: self, hook }
+    }
+}
+
+/// A [`Sender`] wrapper that reports backpressure events. Created by the
+/// [`inspect_blocking`][Sender::inspect_blocking] method; see its
+/// documentation for details.
+pub struct InspectBlocking<'a, T, F> {
+    sender: &'a mut Sender<T>,
+    hook: F,
+}
+
+impl<T, F> InspectBlocking<'_, T, F>
+where
+    F: FnMut(),
+{
+    /// Asynchronously send an item to the receiver, calling the hook if the
+    /// send blocks. Otherwise, this is identical to [`Sender::send`].
+    #[inline]
+    pub fn send(&mut self, item: T) -> InspectBlockingSendFut<'_, T, F> {
+        InspectBlockingSendFut {
+            send: self.sender.send(item),
+            hook: &mut self.hook,
+        }
+    }
+
+    /// Get a reference to the underlying sender.
+    #[inline]
+    #[must_use]
+    pub fn get_ref(&self) -> &Sender<T> {
+        self.sender
+    }
+}
+
+impl<T, F> Debug for InspectBlocking<'_, T, F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InspectBlocking")
+            .field("sender", &self.sender)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Future for the [`send`][InspectBlocking::send] method of
+/// [`InspectBlocking`]. See its documentation for details.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+#[pin_project]
+pub struct InspectBlockingSendFut<'a, T, F> {
+    #[pin]
+    send: SendFut<'a, T>,
+    hook: &'a mut F,
+}
+
+impl<T, F> Debug for InspectBlockingSendFut<'_, T, F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InspectBlockingSendFut")
+            .field("send", &self.send)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T, F> Future for InspectBlockingSendFut<'_, T, F>
+where
+    F: FnMut(),
+{
+    type Output = Result<(), SendError<T>>;
+
+    #[inline]
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        this.send.poll_with_hook(cx, this.hook)
+    }
+}
+
+/// A phase transition in a receive operation, reported to the observer passed
+/// to [`Receiver::recv_observed`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RecvPhase {
+    /// No item was available, so the receive returned `Pending` and is
+    /// waiting to be woken.
+    Parked,
+
+    /// The receive was polled again after it parked.
+    Woke,
+
+    /// An item was received.
+    Got,
+
+    /// The sender disconnected, so the receive resolved to `None`.
+    Disconnected,
+}
+
+impl<T> Receiver<T> {
+    /// Receive the next item from the sender, reporting each phase of the
+    /// receive to `observer`.
+    ///
+    /// This behaves identically to [`recv`][Receiver::recv], but calls
+    /// `observer` with a [`RecvPhase`] as the receive progresses: [`Parked`]
+    /// each time it has to wait, [`Woke`] each time it's polled again after
+    /// parking, and finally exactly one of [`Got`] or [`Disconnected`]. This
+    /// is the receiving counterpart to
+    /// [`send_instrumented`][Sender::send_instrumented].
+    ///
+    /// The observer is called from inside the future's `poll`, so it should
+    /// be cheap. It's always called after the channel's internal state is
+    /// consistent, so a panicking observer won't corrupt the channel.
+    ///
+    /// [`Parked`]: RecvPhase::Parked
+    /// [`Woke`]: RecvPhase::Woke
+    /// [`Got`]: RecvPhase::Got
+    /// [`Disconnected`]: RecvPhase::Disconnected
+    #[inline]
+    pub fn recv_observed<F>(&mut self, observer: F) -> ObservedRecvFut<'_, T, F>
+    where
+        F: FnMut(RecvPhase),
+    {
+        ObservedRecvFut {
+            recv: self.recv(),
+            observer,
+            parked: false,
+        }
+    }
+}
+
+/// Future for the [`recv_observed`][Receiver::recv_observed] method. See its
+/// documentation for details.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct ObservedRecvFut<'a, T, F> {
+    recv: RecvFut<'a, T>,
+    observer: F,
+    parked: bool,
+}
+
+impl<T, F> Debug for ObservedRecvFut<'_, T, F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ObservedRecvFut")
+            .field("receiver", &self.recv.receiver)
+            .field("parked", &self.parked)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T, F> Unpin for ObservedRecvFut<'_, T, F> {}
+
+impl<T, F> Future for ObservedRecvFut<'_, T, F>
+where
+    F: FnMut(RecvPhase),
+{
+    type Output = Option<T>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let this = &mut *self;
+
+        if this.parked {
+            this.parked = false;
+            (this.observer)(RecvPhase::Woke);
+        }
+
+        match Pin::new(&mut this.recv).poll(cx) {
+            Poll::Pending => {
+                this.parked = true;
+                (this.observer)(RecvPhase::Parked);
+                Poll::Pending
+            }
+            Poll::Ready(item) => {
+                (this.observer)(match item {
+                    Some(_) => RecvPhase::Got,
+                    None => RecvPhase::Disconnected,
+                });
+
+                Poll::Ready(item)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, pin::pin};
+
+    use cool_asserts::assert_matches;
+    use futures::{future::join, FutureExt};
+
+    use crate::{channel, SendError};
+
+    use super::{RecvPhase, SendPhase};
+
+    #[tokio::test]
+    async fn phases_in_order() {
+        let (mut sender, mut receiver) = channel();
+        let mut phases = Vec::new();
+
+        let (sent, received) = join(
+            sender.send_instrumented(1, |phase| phases.push(phase)),
+            receiver.recv(),
+        )
+        .await;
+
+        sent.unwrap();
+        assert_eq!(received, Some(1));
+        assert_eq!(phases, [SendPhase::Staged, SendPhase::Taken]);
+    }
+
+    #[tokio::test]
+    async fn failed_after_disconnect() {
+        let (mut sender, receiver) = channel();
+        let mut phases = Vec::new();
+
+        let receiver_task = tokio::task::spawn(async move {
+            tokio::task::yield_now().await;
+            drop(receiver);
+        });
+
+        assert_matches!(
+            sender
+                .send_instrumented(1, |phase| phases.push(phase))
+                .await,
+            Err(SendError(1))
+        );
+        receiver_task.await.unwrap();
+        assert_eq!(phases, [SendPhase::Staged, SendPhase::Failed]);
+
+        phases.clear();
+        assert_matches!(
+            sender
+                .send_instrumented(2, |phase| phases.push(phase))
+                .await,
+            Err(SendError(2))
+        );
+        assert_eq!(phases, [SendPhase::Failed]);
+    }
+
+    #[test]
+    fn inspect_blocking_only_when_receiver_not_waiting() {
+        let (mut sender, mut receiver) = channel();
+        let blocked = Cell::new(0);
+        let mut sender = sender.inspect_blocking(|| blocked.set(blocked.get() + 1));
+
+        // The receiver is already waiting, so this send doesn't block
+        {
+            let mut recv = receiver.recv();
+            assert!((&mut recv).now_or_never().is_none());
+            let mut send = pin!(sender.send(1));
+            assert!(send.as_mut().now_or_never().is_none());
+            assert_eq!(recv.now_or_never(), Some(Some(1)));
+            assert_matches!(send.now_or_never(), Some(Ok(())));
+        }
+        assert_eq!(blocked.get(), 0);
+
+        // Nobody is waiting, so this one does
+        {
+            let mut send = pin!(sender.send(2));
+            assert!(send.as_mut().now_or_never().is_none());
+            assert_eq!(receiver.recv().now_or_never(), Some(Some(2)));
+            assert_matches!(send.now_or_never(), Some(Ok(())));
+        }
+        assert_eq!(blocked.get(), 1);
+    }
+
+    #[test]
+    fn recv_phases_in_order() {
+        let (mut sender, mut receiver) = channel();
+        let phases = Cell::new(Vec::new());
+        let observe = |phase| {
+            let mut list = phases.take();
+            list.push(phase);
+            phases.set(list);
+        };
+
+        {
+            let mut recv = pin!(receiver.recv_observed(observe));
+            assert!(recv.as_mut().now_or_never().is_none());
+            assert!(recv.as_mut().now_or_never().is_none());
+
+            let mut send = pin!(sender.send(1));
+            assert!(send.as_mut().now_or_never().is_none());
+            assert_eq!(recv.now_or_never(), Some(Some(1)));
+            assert_matches!(send.now_or_never(), Some(Ok(())));
+        }
+
+        assert_eq!(
+            phases.take(),
+            [
+                RecvPhase::Parked,
+                RecvPhase::Woke,
+                RecvPhase::Parked,
+                RecvPhase::Woke,
+                RecvPhase::Got,
+            ]
+        );
+
+        drop(sender);
+        assert_eq!(receiver.recv_observed(observe).now_or_never(), Some(None));
+        assert_eq!(phases.take(), [RecvPhase::Disconnected]);
+    }
+}
@@ -0,0 +1,397 @@
+/*!
+A handoff channel that lends items to the receiver, and hands them back to
+the sender once the receiver is done with them.
+
+This is useful for object pools, where the items are reusable buffers: the
+[`Sender`] lends a buffer with [`lease`][Sender::lease], and the
+[`Receiver`] gets it wrapped in a [`Lease`] guard. Once the guard is dropped,
+the buffer, including any changes the receiver made to it, is handed back to
+the sender, which can then reuse it for the next item.
+
+```
+# futures::executor::block_on(async move {
+use handoff::lease;
+use futures::future::join;
+
+let (mut sender, mut receiver) = lease::channel();
+
+let producer = async move {
+    let mut buffer = Vec::with_capacity(64);
+
+    for i in 0..3 {
+        buffer.clear();
+        buffer.push(i);
+        buffer = sender.lease(buffer).await.unwrap();
+    }
+};
+
+let consumer = async move {
+    let mut received = Vec::new();
+
+    while let Some(buffer) = receiver.recv().await {
+        received.extend_from_slice(&buffer);
+    }
+
+    received
+};
+
+let ((), received) = join(producer, consumer).await;
+assert_eq!(received, [0, 1, 2]);
+# });
+```
+*/
+
+use std::{
+    future::Future,
+    ops::{Deref, DerefMut},
+    pin::Pin,
+    sync::{Arc, Mutex, MutexGuard},
+    task::{Context, Poll, Waker},
+};
+
+use futures_util::{
+    ready,
+    stream::{FusedStream, Stream, StreamExt},
+};
+use pin_project::pin_project;
+
+use crate::{SendError, SendFut};
+
+/// Create a lending channel. See the [module documentation][self] for
+/// details.
+#[must_use]
+pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
+    let (sender, receiver) = crate::channel();
+
+    (Sender { leases: sender }, Receiver { leases: receiver })
+}
+
+/// Where a leased item is put when its [`Lease`] is dropped, until the
+/// sender picks it up. Each lease has its own slot, so an item returned
+/// after its [`LeaseFut`] was dropped can't be mistaken for a later one.
+#[derive(Debug)]
+struct ReturnSlot<T> {
+    state: Mutex<ReturnState<T>>,
+}
+
+#[derive(Debug)]
+struct ReturnState<T> {
+    // The item, once it's been returned
+    item: Option<T>,
+
+    // The sender's waker, while it's waiting for the item to be returned
+    waker: Option<Waker>,
+}
+
+impl<T> ReturnSlot<T> {
+    #[inline]
+    fn lock(&self) -> MutexGuard<'_, ReturnState<T>> {
+        // None of the critical sections can panic, so it's fine to ignore
+        // poisoning.
+        self.state.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+/// The lending half of a lending channel.
+///
+/// This object is created by the [`channel`] function. See the [module
+/// documentation][self] for details.
+#[derive(Debug)]
+pub struct Sender<T> {
+    leases: crate::Sender<Lease<T>>,
+}
+
+impl<T> Sender<T> {
+    /// Asynchronously lend an item to the receiver, then wait for it to be
+    /// handed back.
+    ///
+    /// This method will asynchronously block until the receiver takes the
+    /// item, and then until the receiver drops the [`Lease`] wrapping it,
+    /// at which point it resolves to the item. If the receiver disconnects
+    /// before taking the item, the item is returned in a [`SendError`].
+    ///
+    /// Once the receiver has taken the item, only dropping the `Lease` can
+    /// complete this future; if the receiver leaks it, this never resolves.
+    /// If this future is dropped first, the item is dropped when it's
+    /// returned.
+    #[inline]
+    pub fn lease(&mut self, item: T) -> LeaseFut<'_, T> {
+        let slot = Arc::new(ReturnSlot {
+            state: Mutex::new(ReturnState {
+                item: None,
+                waker: None,
+            }),
+        });
+
+        let lease = Lease {
+            item: Some(item),
+            slot: slot.clone(),
+        };
+
+        LeaseFut {
+            send: self.leases.send(lease),
+            slot,
+            sent: false,
+        }
+    }
+
+    /// Check if the receiver is still connected. See
+    /// [`crate::Sender::is_connected`] for details.
+    #[inline]
+    #[must_use]
+    pub fn is_connected(&self) -> bool {
+        self.leases.is_connected()
+    }
+}
+
+/// Future for the [`lease`][Sender::lease] method. See its documentation for
+/// details.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+#[pin_project]
+#[derive(Debug)]
+pub struct LeaseFut<'a, T> {
+    #[pin]
+    send: SendFut<'a, Lease<T>>,
+    slot: Arc<ReturnSlot<T>>,
+
+    // True once the receiver has taken the lease
+    sent: bool,
+}
+
+impl<T> Future for LeaseFut<'_, T> {
+    type Output = Result<T, SendError<T>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+
+        if !*this.sent {
+            if let Err(SendError(mut lease)) = ready!(this.send.poll(cx)) {
+                let item = lease.item.take().expect("an untaken lease still has its item");
+                return Poll::Ready(Err(SendError(item)));
+            }
+
+            *this.sent = true;
+        }
+
+        let mut state = this.slot.lock();
+
+        if let Some(item) = state.item.take() {
+            return Poll::Ready(Ok(item));
+        }
+
+        match &mut state.waker {
+            Some(waker) => waker.clone_from(cx.waker()),
+            waker @ None => *waker = Some(cx.waker().clone()),
+        }
+
+        Poll::Pending
+    }
+}
+
+/// The borrowing half of a lending channel.
+///
+/// This object is created by the [`channel`] function. Like the ordinary
+/// [`Receiver`][crate::Receiver], it implements [`Stream`]. See the [module
+/// documentation][self] for details.
+#[derive(Debug)]
+pub struct Receiver<T> {
+    leases: crate::Receiver<Lease<T>>,
+}
+
+impl<T> Receiver<T> {
+    /// Attempt to receive the next leased item.
+    ///
+    /// This method will asynchronously block until the sender lends an
+    /// item, then return it, wrapped in a [`Lease`] that hands it back to
+    /// the sender when it's dropped. If the sender disconnects, this will
+    /// return `None`.
+    #[inline]
+    pub fn recv(&mut self) -> RecvFut<'_, T> {
+        RecvFut { receiver: self }
+    }
+
+    /// Check if the sender is still connected. See
+    /// [`crate::Receiver::is_connected`] for details.
+    #[inline]
+    #[must_use]
+    pub fn is_connected(&self) -> bool {
+        self.leases.is_connected()
+    }
+}
+
+impl<T> Stream for Receiver<T> {
+    type Item = Lease<T>;
+
+    #[inline]
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Lease<T>>> {
+        self.leases.poll_next_unpin(cx)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.leases.size_hint()
+    }
+}
+
+impl<T> FusedStream for Receiver<T> {
+    #[inline]
+    fn is_terminated(&self) -> bool {
+        self.leases.is_terminated()
+    }
+}
+
+/// Future for the [`recv`][Receiver::recv] method of a lending
+/// [`Receiver`]. See its documentation for details.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+#[derive(Debug)]
+pub struct RecvFut<'a, T> {
+    receiver: &'a mut Receiver<T>,
+}
+
+impl<T> Future for RecvFut<'_, T> {
+    type Output = Option<Lease<T>>;
+
+    #[inline]
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.receiver.poll_next_unpin(cx)
+    }
+}
+
+/// An item lent to the receiver of a lending channel.
+///
+/// The item can be accessed and modified through `Deref` and `DerefMut`.
+/// When the `Lease` is dropped, the item is handed back to the sender.
+#[derive(Debug)]
+pub struct Lease<T> {
+    // This is only `None` while the lease is being dropped, or after the
+    // sender reclaimed an untaken lease's item.
+    item: Option<T>,
+    slot: Arc<ReturnSlot<T>>,
+}
+
+impl<T> Deref for Lease<T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        self.item.as_ref().expect("a live lease always has its item")
+    }
+}
+
+impl<T> DerefMut for Lease<T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut T {
+        self.item.as_mut().expect("a live lease always has its item")
+    }
+}
+
+impl<T> Drop for Lease<T> {
+    fn drop(&mut self) {
+        let Some(item) = self.item.take() else { return };
+
+        let mut state = self.slot.lock();
+        state.item = Some(item);
+        let waker = state.waker.take();
+        drop(state);
+
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        future::Future,
+        pin::pin,
+        task::{Context, Poll, Waker},
+    };
+
+    use cool_asserts::assert_matches;
+    use futures::{future::join, FutureExt};
+
+    use crate::SendError;
+
+    use super::channel;
+
+    #[tokio::test]
+    async fn same_buffer_is_returned() {
+        let (mut sender, mut receiver) = channel::<Vec<u8>>();
+
+        let mut buffer = Vec::with_capacity(16);
+        let address = buffer.as_ptr();
+
+        let consumer = async move {
+            while let Some(mut buffer) = receiver.recv().await {
+                let len = buffer.len() as u8;
+                buffer.push(len);
+            }
+        };
+
+        let producer = async move {
+            for _ in 0..3 {
+                buffer = sender.lease(buffer).await.unwrap();
+                assert_eq!(buffer.as_ptr(), address);
+            }
+
+            buffer
+        };
+
+        let (buffer, ()) = join(producer, consumer).await;
+        assert_eq!(buffer, [0, 1, 2]);
+    }
+
+    #[test]
+    fn waits_for_lease_to_drop() {
+        let (mut sender, mut receiver) = channel();
+        let mut cx = Context::from_waker(Waker::noop());
+
+        let mut lease_fut = pin!(sender.lease(String::from("buffer")));
+        assert!(lease_fut.as_mut().poll(&mut cx).is_pending());
+
+        let mut lease = receiver.recv().now_or_never().flatten().unwrap();
+        assert!(lease_fut.as_mut().poll(&mut cx).is_pending());
+
+        lease.push_str(" (used)");
+        assert!(lease_fut.as_mut().poll(&mut cx).is_pending());
+
+        drop(lease);
+        assert_matches!(lease_fut.poll(&mut cx), Poll::Ready(Ok(buffer)) => {
+            assert_eq!(buffer, "buffer (used)");
+        });
+    }
+
+    #[test]
+    fn returned_after_future_dropped() {
+        let (mut sender, mut receiver) = channel();
+        let mut cx = Context::from_waker(Waker::noop());
+
+        let first = {
+            let mut lease_fut = pin!(sender.lease(1));
+            assert!(lease_fut.as_mut().poll(&mut cx).is_pending());
+            receiver.recv().now_or_never().flatten().unwrap()
+        };
+
+        let mut lease_fut = pin!(sender.lease(2));
+        assert!(lease_fut.as_mut().poll(&mut cx).is_pending());
+        let second = receiver.recv().now_or_never().flatten().unwrap();
+
+        // The abandoned lease's item doesn't go to the new lease
+        drop(first);
+        assert!(lease_fut.as_mut().poll(&mut cx).is_pending());
+
+        drop(second);
+        assert_matches!(lease_fut.poll(&mut cx), Poll::Ready(Ok(2)));
+    }
+
+    #[test]
+    fn disconnected() {
+        let (mut sender, receiver) = channel();
+        drop(receiver);
+
+        assert_matches!(sender.lease(vec![1]).now_or_never(), Some(Err(SendError(item))) => {
+            assert_eq!(item, [1]);
+        });
+    }
+}
@@ -99,6 +99,24 @@ let recv_task = async move {
 join(send_task, recv_task).await;
 # });
 ```
+
+# Features
+
+- `blocking`: Adds `block_on_recv`, `BlockingSender`, the `scoped` module,
+  and other helpers for using the channel from synchronous code without
+  pulling in an async runtime.
+- `bytes`: Adds the `bytes` module, for handing off reference-counted byte
+  frames that the receiver can split into messages without copying.
+- `metrics`: Adds `Sender::estimated_rate`, an estimate of how fast the
+  receiver is taking items. Implies `time`.
+- `reactor`: Adds `Receiver::readiness_fd`, a file descriptor that becomes
+  readable when an item is available, for driving the receiver from an
+  `epoll` event loop. This is only available on Linux.
+- `time`: Adds time-based operations, like `Receiver::throttled`. These use
+  a small built-in timer thread, so they work with any async runtime.
+- `tokio`: Makes [`Sender::send`] consume tokio's cooperative scheduling
+  budget, so that a tight send loop yields to other tasks on the same worker.
+  This has no effect outside of a tokio runtime.
 */
 
 #![deny(missing_docs)]
@@ -109,14 +127,14 @@ use std::{
     fmt::Debug,
     future::Future,
     hint::unreachable_unchecked,
-    ops::Not,
+    ops::{ControlFlow, Not},
     pin::Pin,
     ptr::{self, NonNull},
     sync::atomic::{
-        AtomicPtr,
+        AtomicBool, AtomicPtr, AtomicU64,
         Ordering::{Acquire, Relaxed, Release},
     },
-    task::{Context, Poll},
+    task::{Context, Poll, Waker},
     thread,
 };
 
@@ -127,13 +145,13 @@ trait UnsafeCellExt<T> {
 
 impl<T> UnsafeCellExt<T> for UnsafeCell<T> {
     #[inline]
-    #[must_use]
     fn get_non_null(&self) -> NonNull<T> {
         NonNull::new(self.get()).expect("UnsafeCell shouldn't return a null pointer")
     }
 }
 
 use futures_util::{
+    ready,
     stream::{FusedStream, Stream, StreamExt},
     task::AtomicWaker,
 };
@@ -142,6 +160,88 @@ use pinned_aliasable::Aliasable;
 use thiserror::Error;
 use twinsies::Joint;
 
+/// Run any test hook installed at this point in the protocol. Compiles to
+/// nothing outside of tests.
+macro_rules! inject {
+    ($point:ident) => {
+        #[cfg(test)]
+        crate::testing::inject(crate::testing::Point::$point);
+    };
+}
+
+mod ack;
+mod any;
+pub mod array;
+#[cfg(feature = "blocking")]
+mod blocking;
+mod both;
+mod builder;
+#[cfg(feature = "bytes")]
+pub mod bytes;
+mod cancel;
+mod closed;
+pub mod coalesce;
+mod concurrent;
+mod coop;
+mod deferred;
+#[cfg(feature = "diagnostics")]
+mod diagnostics;
+pub mod duplex;
+mod extend;
+mod instrument;
+pub mod lease;
+mod limit;
+#[cfg(feature = "metrics")]
+mod metrics;
+pub mod mpmc;
+mod pipeline;
+pub mod priority;
+#[cfg(all(feature = "reactor", target_os = "linux"))]
+mod reactor;
+#[cfg(feature = "blocking")]
+pub mod scoped;
+mod seq;
+mod sink;
+mod status;
+#[cfg(test)]
+mod testing;
+#[cfg(feature = "time")]
+mod timeout;
+#[cfg(feature = "time")]
+mod timer;
+
+pub mod stream;
+
+pub use ack::{Ack, SendAckedFut};
+pub use any::{send_to_any, SendToAnyFut};
+#[cfg(feature = "blocking")]
+pub use blocking::{block_on_recv, block_on_send, BlockingSender};
+pub use both::{send_both, SendBothError, SendBothFut};
+pub use builder::Builder;
+pub use cancel::{
+    AbortHandle, AbortableSendFut, CancelOutcome, RecvOrFutureFut, SendCancellableFut,
+    SendOrCancel, SendOrFut, WeakSendFut,
+};
+pub use closed::ClosedFut;
+pub use concurrent::ForEachConcurrentFut;
+pub use coop::SendCoopFut;
+pub use deferred::SendWithFut;
+#[cfg(feature = "diagnostics")]
+pub use diagnostics::{RecvOrStalledFut, StalledError, WakerRegistrations};
+pub use extend::ExtendFut;
+pub use instrument::{
+    InspectBlocking, InspectBlockingSendFut, InstrumentedSendFut, ObservedRecvFut, RecvPhase,
+    SendPhase,
+};
+pub use limit::{RateLimiter, SendLimitedFut};
+pub use pipeline::{PipelineFlushFut, PipelineSendFut, SendPipeline, SendTicket};
+pub use seq::{channel_seq, SeqSender};
+pub use sink::SenderSink;
+pub use status::{RecvHintedFut, RecvStatus, RecvStatusFut, RecvTimedFut};
+pub use stream::zip;
+#[cfg(feature = "time")]
+pub use timeout::{CollectTimeoutFut, SendTimeoutFut};
+
 /// Identical to `unreachable_unchecked`, but panics in debug mode. Still
 /// requires unsafe.
 macro_rules! debug_unreachable {
@@ -157,21 +257,196 @@ macro_rules! debug_unreachable {
 /// asynchronous components. All sends over this channel will block until the
 /// receiver receives the sent item. See [crate documentation][crate] for
 /// details.
+///
+/// To configure the channel, use a [`Builder`] instead.
+///
+/// # Allocation
+///
+/// Creating a channel performs exactly one allocation, for the state shared
+/// by the two halves. Sending and receiving never allocate: a sent item is
+/// never moved into the channel, but lent to the receiver from the send
+/// future itself. (Registering a waker clones it, which may allocate,
+/// depending on the executor.)
+#[inline]
 pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
-    let (send_joint, recv_joint) = Joint::new(Inner {
-        sent_item: AtomicPtr::default(),
-        sender_waker: AtomicWaker::new(),
-        receiver_waker: AtomicWaker::new(),
-    });
+    Builder::new().channel()
+}
+
+/// The two halves of a rendezvous channel, as returned by
+/// [`rendezvous_channel`].
+pub type Rendezvous<T> = (Sender<T>, Receiver<T>);
+
+/// Create a rendezvous channel: a channel with no buffer, where every send
+/// waits for the receiver to take the item.
+///
+/// This is identical to [`channel`], including its allocation guarantees;
+/// it exists to make the unbuffered semantics explicit at call sites.
+#[inline]
+pub fn rendezvous_channel<T>() -> Rendezvous<T> {
+    channel()
+}
 
-    (Sender { inner: send_joint }, Receiver { inner: recv_joint })
+/// Create an unbuffered channel for handing off boxed, possibly unsized,
+/// values, such as trait objects or slices.
+///
+/// This is the same as [`channel`], but it makes it convenient to name the
+/// unsized type once, at the point the channel is created. Because the
+/// channel's item type is known to be `Box<T>`, boxed values passed to
+/// [`send`][Sender::send] (or [`send_or_drop`][Sender::send_or_drop]) are
+/// unsized automatically:
+///
+/// ```
+/// # futures::executor::block_on(async move {
+/// use handoff::channel_boxed;
+/// use futures::future::join;
+///
+/// let (mut sender, mut receiver) = channel_boxed::<dyn FnOnce() -> i32 + Send>();
+///
+/// let send_task = async move {
+///     assert!(sender.send_or_drop(Box::new(|| 1)).await);
+///     assert!(sender.send_or_drop(Box::new(move || 2)).await);
+/// };
+///
+/// let recv_task = async move {
+///     let mut total = 0;
+///
+///     while let Some(callback) = receiver.recv().await {
+///         total += callback();
+///     }
+///
+///     total
+/// };
+///
+/// let ((), total) = join(send_task, recv_task).await;
+/// assert_eq!(total, 3);
+/// # });
+/// ```
+///
+/// Only the box itself is moved through the channel; the pointed-to value is
+/// never moved or copied.
+#[inline]
+pub fn channel_boxed<T: ?Sized>() -> (Sender<Box<T>>, Receiver<Box<T>>) {
+    channel()
+}
+
+/// Reset a sender and receiver to a fresh channel connecting the two of them,
+/// without needing to replace either object.
+///
+/// This is useful for pools that recycle channel endpoints: a pair that was
+/// disconnected (because one of the halves had previously been paired with
+/// something else that was dropped, for instance) becomes usable again. The
+/// previous channel of each half is dropped exactly as though the half itself
+/// had been dropped, so any other half it was connected to sees a disconnect,
+/// and an item that was [peeked][Receiver::peek] but not received is dropped.
+/// Each half keeps its [coop budget][Builder::coop_budget].
+///
+/// Like [`channel`], this allocates the new channel's shared state.
+///
+/// ```
+/// # futures::executor::block_on(async move {
+/// use handoff::{channel, reconnect};
+/// use futures::future::join;
+///
+/// let (mut sender, old_receiver) = channel();
+/// let (old_sender, mut receiver) = channel();
+/// drop(old_receiver);
+/// drop(old_sender);
+/// assert!(!sender.is_connected());
+/// assert!(!receiver.is_connected());
+///
+/// reconnect(&mut sender, &mut receiver);
+/// let (sent, received) = join(sender.send(1), receiver.recv()).await;
+/// sent.unwrap();
+/// assert_eq!(received, Some(1));
+/// # });
+/// ```
+pub fn reconnect<T>(sender: &mut Sender<T>, receiver: &mut Receiver<T>) {
+    let (mut old_sender, mut old_receiver) = channel();
+
+    std::mem::swap(&mut sender.inner, &mut old_sender.inner);
+    sender.coop_streak = 0;
+
+    std::mem::swap(&mut receiver.inner, &mut old_receiver.inner);
+    std::mem::swap(&mut receiver.peeked, &mut old_receiver.peeked);
+    std::mem::swap(&mut receiver.prefetched, &mut old_receiver.prefetched);
+    #[cfg(feature = "time")]
+    std::mem::swap(&mut receiver.peeked_wait, &mut old_receiver.peeked_wait);
+
+    // The new channel doesn't have the readiness descriptor armed yet, so
+    // make sure that the event loop checks on it.
+    #[cfg(all(feature = "reactor", target_os = "linux"))]
+    if let Some(event) = receiver.readiness.get() {
+        event.signal();
+    }
 }
 
+// Every access to `Inner` goes through `Joint::lock`. That lock only fails
+// once the other half has been dropped (or is being dropped); its
+// compare-exchange retries spurious failures and contention with the other
+// half's locks. So a failed lock is always a genuine disconnect, never a
+// temporary condition. A successful lock keeps `Inner` alive until the lock is
+// dropped, even if the other half disconnects in the meantime.
 struct Inner<T> {
     // When this is not null, there's an object that a sender is trying to send
     // (and is asynchronously blocked until the send completes)
     sent_item: AtomicPtr<Option<T>>,
 
+    // When this is true, the receiver has taken the sender's item, but is
+    // holding it (for instance, because it was peeked) and hasn't released
+    // the sender yet. The sender should stay blocked until this is cleared.
+    item_held: AtomicBool,
+
+    // When the receiver takes an item, it hands the sender's pointer back
+    // here, rather than restoring it to `sent_item`. Once the sender sees its
+    // own pointer here, it has exclusive access to its (now empty) item slot
+    // again.
+    taken_item: AtomicPtr<Option<T>>,
+
+    // The number of items the receiver has taken so far. This is only written
+    // by the receiver, just before it hands a taken item's pointer back, so
+    // the sender sees it updated once it reclaims its pointer.
+    taken_count: AtomicU64,
+
+    // How many times the sender spins before it starts yielding, while it
+    // waits for the receiver to hand back its item pointer. See
+    // `Builder::reclaim_spins`.
+    reclaim_spins: u32,
+
+    // Set by the receiver as soon as it starts dropping, while it still holds
+    // a lock. The `Joint` only disconnects once that lock is released, so
+    // this lets a concurrent send fail right away, rather than staging an
+    // item that can never be taken and waiting for the disconnect to wake
+    // it. Nothing is published through this flag, so it's only ever accessed
+    // with relaxed ordering.
+    receiver_closing: AtomicBool,
+
+    // While this is set, the receiver wakes the sender each time it starts
+    // waiting for an item, so that `send_to_any` can tell which receivers are
+    // ready. It's only set while a `send_to_any` is in progress.
+    wants_demand: AtomicBool,
+
+    // Set by the receiver when it starts waiting for an item while
+    // `wants_demand` is set, and cleared once it takes an item or stops
+    // waiting, or once the `send_to_any` finishes. `send_to_any` reads this
+    // to find a ready receiver, so that it never registers the receiver's
+    // waker itself.
+    receiver_waiting: AtomicBool,
+
+    // When the sender last staged an item, as a timestamp from
+    // `timer::now_nanos`. This is written just before the item pointer is
+    // stored, so the receiver always sees the timestamp for the item it takes.
+    #[cfg(feature = "time")]
+    staged_at: AtomicU64,
+
+    // The sender's estimate of the receiver's throughput, updated each time
+    // a send completes.
+    #[cfg(feature = "metrics")]
+    rate: metrics::RateEstimate,
+
+    // Counts of the wakers registered by the receiver.
+    #[cfg(feature = "diagnostics")]
+    registrations: diagnostics::RegistrationCounter,
+
     // The waker owned by the sender. Should be signalled when the receiver
     // takes a value (or disconnects)
     sender_waker: AtomicWaker,
@@ -179,6 +454,9 @@ struct Inner<T> {
     // The waker owned by the receiver. Should be signalled when the sender has
     // an item to send (or disconnects)
     receiver_waker: AtomicWaker,
+
+    // Wakers for any number of tasks waiting for either half to disconnect
+    close_waiters: closed::Waiters,
 }
 
 unsafe impl<T> Send for Inner<T> {}
@@ -186,10 +464,23 @@ unsafe impl<T> Sync for Inner<T> {}
 
 impl<T> Inner<T> {
     /// The sender uses this to take an item pointer that it placed there, to
-    /// regain exclusive access to its item.
+    /// regain exclusive access to its item. The pointer is either still in
+    /// `sent_item`, if the receiver didn't take the item, or it was handed
+    /// back in `taken_item`, if it did.
     #[inline]
     fn reclaim_sent_item_pointer(&self, item_pointer: NonNull<Option<T>>) {
+        let mut spins = 0;
+
         loop {
+            // In the common case, the receiver has already taken the item, so
+            // we check for that first; a plain load is much cheaper than a
+            // failed compare-exchange.
+            let current = self.sent_item.load(Relaxed);
+
+            if current.is_null() && self.taken_item.load(Acquire) == item_pointer.as_ptr() {
+                break;
+            }
+
             match self.sent_item.compare_exchange_weak(
                 item_pointer.as_ptr(),
                 ptr::null_mut(),
@@ -201,13 +492,18 @@ impl<T> Inner<T> {
                 // Spurious failure
                 Err(current) if current == item_pointer.as_ptr() => continue,
 
-                // Receiver owns the value; spin while we wait for it
-                //
-                // TODO: consider using something like the spinner from
-                // parking_lot_core. We're pretty certain that another thread is
-                // working with the pointer, though, so for now we're content to
-                // do a full yield and let it have a chance to finish its work.
-                Err(current) if current.is_null() => thread::yield_now(),
+                // Receiver owns the value; spin while we wait for it to either
+                // restore it or hand it back as taken. We're pretty certain
+                // that another thread is working with the pointer, so unless
+                // the channel was configured to spin first, we do a full
+                // yield and let it have a chance to finish its work.
+                Err(current) if current.is_null() => match spins < self.reclaim_spins {
+                    true => {
+                        spins += 1;
+                        std::hint::spin_loop();
+                    }
+                    false => thread::yield_now(),
+                },
 
                 // Something very wrong happened
                 Err(current) => unsafe {
@@ -220,6 +516,211 @@ impl<T> Inner<T> {
             }
         }
     }
+
+    /// The receiver uses this to hand the pointer to an item it took back to
+    /// the sender, waking the sender unless the item is being held.
+    #[inline]
+    fn hand_back_taken(&self, sent_item_ptr: NonNull<Option<T>>) {
+        // This must be `Release`: it pairs with the sender's `Acquire` load
+        // in `reclaim_sent_item_pointer`, ordering our `take` of the item
+        // (and the `taken_count` update) before the sender reuses or drops
+        // the slot. It's already the only ordered operation on the taken
+        // path, since the restore compare-exchange is skipped entirely.
+        self.taken_item.store(sent_item_ptr.as_ptr(), Release);
+
+        // If we're holding the sender's item, there's no point in waking it
+        // until the item is released.
+        if self.item_held.load(Relaxed).not() {
+            self.sender_waker.wake();
+        }
+    }
+
+    /// Poll to receive an item; see [`try_recv_with`][Inner::try_recv_with]
+    /// for details about `op`.
+    fn poll_recv_with<F, R>(&self, cx: &mut Context<'_>, op: F) -> Poll<R>
+    where
+        F: FnOnce(&mut Option<T>) -> R,
+    {
+        // We have to try twice, because we first try to take an item, then
+        // register a waker, then we have to try again after registering the
+        // waker. This avoids a race where we fail to retrieve and item, then
+        // the sender places an item, then the sender calls wake() before we've
+        // registered our waker.
+        //
+        // TODO: bench {recv; register(waker); recv} against {register(waker); recv}
+        //
+        // Note that nothing on the receiving side ever clears the registered
+        // waker except for drops, so spurious polls are harmless: each one
+        // re-registers the waker before its final check, so a subsequent
+        // store by the sender will always wake the most recently used waker.
+        let op = match self.try_recv_with(op) {
+            Ok(result) => return Poll::Ready(result),
+            Err(op) => op,
+        };
+
+        self.receiver_waker.register(cx.waker());
+        #[cfg(feature = "diagnostics")]
+        self.registrations.record(cx.waker());
+
+        match self.try_recv_with(op) {
+            Ok(result) => Poll::Ready(result),
+            Err(_) => {
+                self.signal_demand();
+                Poll::Pending
+            }
+        }
+    }
+
+    /// The receiver uses this after registering its waker, to tell a
+    /// `send_to_any` in progress that it's waiting for an item. Channels
+    /// without a `send_to_any` only pay for a relaxed load.
+    ///
+    /// This only needs relaxed ordering: `send_to_any` sets `wants_demand`
+    /// before it takes our waker to ask us to confirm that we're waiting,
+    /// and both registering and taking the waker are read-modify-writes of
+    /// the same `AtomicWaker`. So either it finds and wakes our waker, and we
+    /// see the flag when we're polled again, or we see the flag now.
+    #[inline]
+    fn signal_demand(&self) {
+        if self.wants_demand.load(Relaxed) {
+            self.receiver_waiting.store(true, Relaxed);
+            self.sender_waker.wake();
+        }
+    }
+
+    /// The receiver uses this when it stops waiting for an item without
+    /// receiving one, such as when a receive future is dropped, so that
+    /// neither the sender nor `send_to_any` mistake it for a waiting
+    /// receiver.
+    #[inline]
+    fn stop_waiting(&self) {
+        self.receiver_waiting.store(false, Relaxed);
+        drop(self.receiver_waker.take())
+    }
+
+    /// The receiver uses this to acquire the sent item pointer and operate on
+    /// the sent item. If there's an item available, `op` is called with
+    /// exclusive access to the sender's item slot (which is guaranteed to
+    /// contain `Some` item); it should `take` the item if it wants to complete
+    /// the send. If there's no item available, `op` is returned in an `Err`.
+    ///
+    /// The pointer is always returned to the sender (and the sender woken)
+    /// after `op` runs, even if `op` panics.
+    fn try_recv_with<F, R>(&self, op: F) -> Result<R, F>
+    where
+        F: FnOnce(&mut Option<T>) -> R,
+    {
+        // Receivers are frequently polled while there's nothing to receive,
+        // especially in `select!` loops, which poll every branch each time
+        // the task wakes. Check with a plain load first, so that these polls
+        // don't need exclusive access to the cache line for the swap.
+        if self.sent_item.load(Relaxed).is_null() {
+            return Err(op);
+        }
+
+        // Acquire the pointer. As long as we have it, we have exclusive
+        // access to the item. The sender will wait for us to return the
+        // pointer before dropping (or, if it leaks, the value is pinned, so
+        // the pointer is valid forever in that case).
+        //
+        // This must be `Acquire`, even on the fast path where we end up
+        // taking the item: it pairs with the sender's `Release` store of the
+        // pointer, and is the only thing ordering the sender's write of the
+        // item before our read of it. A `Relaxed` swap would let us observe
+        // the pointer before the item it points to.
+        let sent_item_ptr = self.sent_item.swap(ptr::null_mut(), Acquire);
+
+        // If there wasn't a pointer available, we've already registered our
+        // waker, so at this point we're waiting for a signal to try another
+        // receive operation.
+        let Some(sent_item_ptr) = NonNull::new(sent_item_ptr) else { return Err(op) };
+
+        let guard = RestoreItemPointer {
+            inner: self,
+            sent_item_ptr,
+        };
+
+        inject!(ReceiverAcquired);
+
+        // Check if there's actually an item at the pointer. Because taken
+        // items are never restored to the slot, this only happens if a send
+        // future was polled again after it completed. We hand the pointer
+        // straight back as taken, without counting it as a newly taken item.
+        //
+        // SAFETY: Because we acquired the `sent_item_ptr` (replacing it
+        // with a null ptr), we have exclusive access to it.
+        if unsafe { sent_item_ptr.as_ref() }.is_none() {
+            std::mem::forget(guard);
+            self.hand_back_taken(sent_item_ptr);
+            return Err(op);
+        }
+
+        // SAFETY: we still have exclusive access to the item, since the guard
+        // hasn't restored the pointer yet.
+        let result = op(unsafe { guard.sent_item_ptr.as_ptr().as_mut() }.unwrap());
+
+        // If there was a leak and a new sent item arrived while we were
+        // working, we already got an item, so we have to leave the new one
+        // there until a subsequent `recv`.
+        let _ = guard.restore();
+
+        // Having taken an item, we're no longer waiting for one. This is only
+        // written if it's set, so that channels without a `send_to_any` don't
+        // pay for the store.
+        if self.receiver_waiting.load(Relaxed) {
+            self.receiver_waiting.store(false, Relaxed);
+        }
+
+        Ok(result)
+    }
+}
+
+/// Protocol invariant audits. These validate the full expected state at key
+/// points in the protocol, to catch regressions earlier than the narrow
+/// `debug_unreachable` checks would. They're only run in debug builds.
+impl<T> Inner<T> {
+    /// Audit the state immediately after a sender staged its item.
+    ///
+    /// The receiver may already be taking the item concurrently, so this
+    /// only checks states that no interleaving can produce: an unrelated
+    /// pointer in the slot, or our pointer handed back as taken while it's
+    /// still staged (which means a stale `taken_item` wasn't cleared before
+    /// staging, and the sender would mistake its item for taken).
+    #[inline]
+    fn audit_staged(&self, item_pointer: NonNull<Option<T>>) {
+        if cfg!(debug_assertions) {
+            // `taken_item` is loaded first: if it holds our pointer, the
+            // receiver already swapped it out of the slot, and the `Acquire`
+            // ensures that we see that.
+            let taken = self.taken_item.load(Acquire);
+            let current = self.sent_item.load(Relaxed);
+
+            assert!(
+                current.is_null() || current == item_pointer.as_ptr(),
+                "audit failed: after staging {item_pointer:p}, the sent item \
+                slot contains an unrelated pointer ({current:p})"
+            );
+
+            assert!(
+                taken != item_pointer.as_ptr() || current.is_null(),
+                "audit failed: after staging {item_pointer:p}, it was reported \
+                as taken while still in the sent item slot"
+            );
+        }
+    }
+
+    /// Audit the state while the receiver holds a peeked item, before it
+    /// releases the sender.
+    #[inline]
+    fn audit_holding(&self) {
+        if cfg!(debug_assertions) {
+            assert!(
+                self.item_held.load(Relaxed),
+                "audit failed: the receiver is holding a peeked item, but the \
+                sender was already released"
+            );
+        }
+    }
 }
 
 /// Whenever `Inner` drops, it means a disconnect is happening. Inform the
@@ -229,6 +730,82 @@ impl<T> Drop for Inner<T> {
     fn drop(&mut self) {
         self.sender_waker.wake();
         self.receiver_waker.wake();
+        self.close_waiters.wake_all();
+    }
+}
+
+/// While the receiver holds the sent item pointer, the sender can't make any
+/// progress (and will spin if it tries to reclaim the pointer). This guard
+/// ensures that the pointer is always returned, either restored to the slot or
+/// handed back as taken, even if the receiver panics while it holds it.
+struct RestoreItemPointer<'a, T> {
+    inner: &'a Inner<T>,
+    sent_item_ptr: NonNull<Option<T>>,
+}
+
+impl<T> RestoreItemPointer<'_, T> {
+    /// Return the pointer to the sender. Returns an error if the item wasn't
+    /// taken and a new pointer appeared in the slot while we were working,
+    /// which indicates a sender leak.
+    #[inline]
+    fn restore(self) -> Result<(), ()> {
+        let result = self.try_restore();
+        std::mem::forget(self);
+        result
+    }
+
+    fn try_restore(&self) -> Result<(), ()> {
+        inject!(ReceiverRestoring);
+
+        // If we took the item, we hand the pointer back through `taken_item`
+        // instead of restoring it. This is a plain store, rather than a
+        // compare-exchange, since `taken_item` is only ever written by us; and
+        // the sender doesn't need to compare-exchange to reclaim it, either.
+        //
+        // SAFETY: we still have exclusive access to the item.
+        if unsafe { self.sent_item_ptr.as_ref() }.is_none() {
+            let taken_count = self.inner.taken_count.load(Relaxed);
+            self.inner.taken_count.store(taken_count + 1, Relaxed);
+            self.inner.hand_back_taken(self.sent_item_ptr);
+            return Ok(());
+        }
+
+        // We don't need to retry (non-spurious) failures, since the presence
+        // of a new non-null pointer indicates a sender leak, which means we
+        // can simply drop the `sent_item_ptr` outright.
+        match self.inner.sent_item.compare_exchange(
+            ptr::null_mut(),
+            self.sent_item_ptr.as_ptr(),
+            Release,
+            Relaxed,
+        ) {
+            // We restored the pointer, but we're holding the sender's item, so
+            // there's no point in waking it until the item is released.
+            Ok(_) if self.inner.item_held.load(Relaxed) => Ok(()),
+
+            // We restored the pointer, so we need to wake the sender so it
+            // can proceed with the drop
+            Ok(_) => {
+                self.inner.sender_waker.wake();
+                Ok(())
+            }
+
+            // Somehow the pointer to a pinned object found its way back into
+            // the slot. This shouldn't be possible, since that memory should be
+            // usable until the sender finishes sending, and it can't drop until
+            // we restore the pointer.
+            Err(p) if p == self.sent_item_ptr.as_ptr() => unsafe { debug_unreachable!() },
+
+            // There was a leak and a new sent item arrived while we were
+            // working.
+            Err(_) => Err(()),
+        }
+    }
+}
+
+impl<T> Drop for RestoreItemPointer<'_, T> {
+    fn drop(&mut self) {
+        let _ = self.try_restore();
     }
 }
 
@@ -236,8 +813,22 @@ impl<T> Drop for Inner<T> {
 ///
 /// This object is created by the [`channel`] function. See [crate
 /// documentation][crate] for details.
+///
+/// This is a single-producer channel, so a `Sender` can't be cloned (see
+/// [`duplicate`][Sender::duplicate]). For multiple producers, use the
+/// [`mpmc`] channel instead.
+///
+/// ```compile_fail,E0599
+/// let (sender, _receiver) = handoff::channel::<i32>();
+/// let sender2 = sender.clone();
+/// ```
 pub struct Sender<T> {
     inner: Joint<Inner<T>>,
+
+    // The number of consecutive `send_coop` calls that completed without
+    // blocking, and the number allowed before forcing a yield.
+    coop_streak: u32,
+    coop_budget: u32,
 }
 
 impl<T> Sender<T> {
@@ -246,26 +837,134 @@ impl<T> Sender<T> {
     /// This method will asynchronously block until the receiver has received
     /// the item. If the receiver disconnects, this will instead return a
     /// [`SendError`] containing the item that failed to send.
+    ///
+    /// With the `tokio` feature enabled, each completed send consumes a unit
+    /// of tokio's [cooperative scheduling budget][coop]. Once the
+    /// budget is exhausted, the send returns `Pending` (scheduling the task to
+    /// be polled again) before making its item available, so that a loop of
+    /// sends can't starve other tasks.
+    ///
+    /// [coop]: https://docs.rs/tokio/latest/tokio/task/coop/index.html
     #[inline]
-    #[must_use]
     pub fn send(&mut self, item: T) -> SendFut<'_, T> {
-        SendFut {
-            item: Aliasable::new(UnsafeCell::new(Some(item))),
-            inner: &self.inner,
-            item_lent: false,
+        SendFut::new(&self.inner, item)
+    }
+
+    /// Check if the receiver is still connected.
+    ///
+    /// Note that the receiver may disconnect at any time, so a `true` result
+    /// is only a hint. Once this returns `false`, though, it will never again
+    /// return `true`.
+    #[inline]
+    #[must_use = "checking the connection has no effect on its own"]
+    pub fn is_connected(&self) -> bool {
+        self.inner.alive()
+    }
+
+    /// Check if this sender has an item staged that the receiver hasn't
+    /// taken yet, or that the receiver [peeked][Receiver::peek] but hasn't
+    /// received. In other words, this is `true` while a send is blocked
+    /// waiting for the consumer.
+    ///
+    /// This reads the same state that [`flush`][Sender::flush] waits on, so
+    /// it adds no cost to sending. Like [`is_connected`][Sender::is_connected],
+    /// the result is only a hint, since the receiver may take the item at any
+    /// time. It's always `false` once the receiver disconnects.
+    #[inline]
+    #[must_use = "checking the send state has no effect on its own"]
+    pub fn is_send_in_flight(&self) -> bool {
+        match self.inner.lock() {
+            Some(lock) => {
+                lock.sent_item.load(Acquire).is_null().not() || lock.item_held.load(Acquire)
+            }
+            None => false,
         }
     }
 
-    // TODO: `Sink` implementation. This will require wrapping the sender. Need
-    // to decide if we prefer a by-move or by-ref sink (probably the latter).
-    // Alternatively, create a crate with a general-purpose adapter between
-    // `async fn send` and `Sink`.
+    /// The number of items the channel can hold without a receiver taking
+    /// them. This is always 0, since every send waits for the receiver.
+    #[inline]
+    #[must_use]
+    pub const fn capacity(&self) -> usize {
+        0
+    }
+
+    /// Asynchronously send an item to the receiver, discarding it if the
+    /// receiver disconnects.
+    ///
+    /// This is the same as [`send`][Sender::send], but for fire-and-forget
+    /// cases where the item doesn't need to be recovered after a disconnect.
+    /// The future resolves to `true` if the item was delivered, or `false` if
+    /// the receiver disconnected (in which case the item is dropped).
+    #[inline]
+    pub fn send_or_drop(&mut self, item: T) -> SendOrDropFut<'_, T> {
+        SendOrDropFut {
+            send: self.send(item),
+        }
+    }
+
+    /// Register a waker to be woken when the receiver takes an in-progress
+    /// send's item, or disconnects.
+    ///
+    /// This is intended for custom reactors that drive the channel without a
+    /// full [`Context`]. It can be called repeatedly; each call replaces the
+    /// previously registered waker, including one registered by a pending
+    /// [`send`][Sender::send] future. Note that there's no equivalent
+    /// `try_send`, because an item can only be handed over while the sending
+    /// future that owns it is alive.
+    #[inline]
+    pub fn register_send_waker(&mut self, waker: &Waker) {
+        match self.inner.lock() {
+            Some(lock) => lock.sender_waker.register(waker),
+            None => waker.wake_by_ref(),
+        }
+    }
+
+    /// Attempt to create another sender for this channel. This always fails
+    /// with a [`DuplicateError`], because this is a single-producer channel.
+    ///
+    /// This exists to point anyone looking for a way to clone a sender at
+    /// the [`mpmc`] channel, whose senders can be cloned.
+    #[inline]
+    pub fn duplicate(&self) -> Result<Self, DuplicateError> {
+        Err(DuplicateError)
+    }
+
+    /// Wait until no item from this sender is in flight: that is, until the
+    /// receiver has taken any staged item, and released any item it's
+    /// holding after a [`peek`][Receiver::peek].
+    ///
+    /// Because every [`send`][Sender::send] waits for its item to be taken,
+    /// this resolves immediately after any completed send. It only has to
+    /// wait if a send future was leaked (with [`std::mem::forget`]) while its
+    /// item was staged, or if its item is still held by the receiver. This
+    /// makes it a reliable barrier for shutdown ordering, like ensuring that
+    /// the last item landed before closing. If the receiver disconnects, this
+    /// returns an error, since nothing in flight can be delivered anymore.
+    #[inline]
+    pub fn flush(&mut self) -> FlushFut<'_, T> {
+        FlushFut { inner: &self.inner }
+    }
+
+    /// Wait until no item from this sender is in flight, like
+    /// [`flush`][Sender::flush], and then disconnect the sender.
+    ///
+    /// This is a single step for clean shutdowns: once the future resolves,
+    /// the last item has been taken by the receiver (or the receiver is gone),
+    /// and the sender has been dropped, so the receiver will see the
+    /// disconnect after its last item.
+    #[inline]
+    pub fn close_after_flush(self) -> CloseAfterFlushFut<T> {
+        CloseAfterFlushFut { sender: Some(self) }
+    }
 }
 
 impl<T> Debug for Sender<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Sender")
             .field("inner", &self.inner)
+            .field("coop_streak", &self.coop_streak)
+            .field("coop_budget", &self.coop_budget)
             .finish()
     }
 }
@@ -274,6 +973,7 @@ unsafe impl<T: Send> Send for Sender<T> {}
 
 /// Future for sending a single item through a [`Sender`], created by the
 /// [`send`][Sender::send] method. See its documentation for details.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
 #[pin_project(PinnedDrop)]
 pub struct SendFut<'a, T> {
     // Implementation note: It is critically important to remember that the
@@ -290,6 +990,10 @@ pub struct SendFut<'a, T> {
     // and we need to re-acquire the pointer before doing anything with
     // it.
     item_lent: bool,
+
+    // If awaiting_release is true, the receiver has taken our item, but is
+    // holding it without having released us yet (see `Inner::item_held`).
+    awaiting_release: bool,
 }
 
 impl<T> Debug for SendFut<'_, T> {
@@ -298,6 +1002,7 @@ impl<T> Debug for SendFut<'_, T> {
             .field("item", &"<in transit>")
             .field("inner", &self.inner)
             .field("item_lent", &self.item_lent)
+            .field("awaiting_release", &self.awaiting_release)
             .finish()
     }
 }
@@ -307,6 +1012,65 @@ impl<T> Future for SendFut<'_, T> {
 
     #[inline]
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // Outside of a tokio runtime, the budget is unconstrained, so this
+        // always proceeds.
+        #[cfg(feature = "tokio")]
+        let coop = ready!(tokio::task::coop::poll_proceed(cx));
+
+        let result = ready!(self.poll_with_hook(cx, || {}));
+
+        #[cfg(feature = "tokio")]
+        coop.made_progress();
+
+        Poll::Ready(result)
+    }
+}
+
+impl<'a, T> SendFut<'a, T> {
+    #[inline]
+    fn new(inner: &'a Joint<Inner<T>>, item: T) -> Self {
+        SendFut {
+            item: Aliasable::new(UnsafeCell::new(Some(item))),
+            inner,
+            item_lent: false,
+            awaiting_release: false,
+        }
+    }
+
+    /// Create a send future whose item slot starts out empty. The item must
+    /// be written with [`fill`][SendFut::fill] before the future is polled.
+    #[inline]
+    fn new_empty(inner: &'a Joint<Inner<T>>) -> Self {
+        SendFut {
+            item: Aliasable::new(UnsafeCell::new(None)),
+            inner,
+            item_lent: false,
+            awaiting_release: false,
+        }
+    }
+
+    /// Construct the item directly in the empty item slot of a future created
+    /// with [`new_empty`][SendFut::new_empty].
+    #[inline]
+    fn fill(self: Pin<&mut Self>, make: impl FnOnce() -> T) {
+        let this = self.project();
+        debug_assert!(!*this.item_lent, "can't fill a send that's in progress");
+
+        // Safety: the item was never lent, so we have exclusive access to it.
+        let slot = unsafe { this.item.as_ref().get().get_non_null().as_mut() };
+        debug_assert!(slot.is_none(), "can't fill a send that already has an item");
+        *slot = Some(make());
+    }
+
+    /// Shared logic for polling the send. `on_block` is called if the item is
+    /// staged while the receiver isn't waiting for it, which means that the
+    /// sender will be blocked until the receiver gets around to it.
+    #[inline]
+    pub(crate) fn poll_with_hook(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        on_block: impl FnOnce(),
+    ) -> Poll<Result<(), SendError<T>>> {
         let this = self.project();
 
         let mut item_pointer = this.item.as_ref().get().get_non_null();
@@ -340,10 +1104,37 @@ impl<T> Future for SendFut<'_, T> {
             *this.item_lent = false;
 
             // We've acquired exclusive access to the item pointer; we can check
-            // to see if the item was taken yet.
+            // to see if the item was taken yet. If the receiver is holding it,
+            // we have to wait for it to be released.
             if unsafe { item_pointer.as_ref() }.is_none() {
+                *this.awaiting_release = true;
+            }
+        }
+
+        if *this.awaiting_release {
+            // In the common case, the receiver took the item outright, and
+            // we're done. We only need to register our waker if the receiver
+            // is still holding the item.
+            if lock.item_held.load(Acquire).not() {
+                *this.awaiting_release = false;
+                #[cfg(feature = "metrics")]
+                lock.rate.record();
                 return Poll::Ready(Ok(()));
             }
+
+            // Register before checking again, so that we don't miss a
+            // concurrent release.
+            lock.sender_waker.register(cx.waker());
+
+            return match lock.item_held.load(Acquire) {
+                true => Poll::Pending,
+                false => {
+                    *this.awaiting_release = false;
+                    #[cfg(feature = "metrics")]
+                    lock.rate.record();
+                    Poll::Ready(Ok(()))
+                }
+            };
         }
 
         // At this point, we've either never been polled before, or we have been
@@ -360,26 +1151,58 @@ impl<T> Future for SendFut<'_, T> {
             "Don't poll futures after they returned success"
         );
 
+        // If the receiver is already being dropped, don't bother staging.
+        if lock.receiver_closing.load(Relaxed) {
+            // Safety: we haven't staged the item, or we've reclaimed its
+            // pointer, so we have exclusive access to it.
+            return Poll::Ready(match unsafe { item_pointer.as_mut() }.take() {
+                Some(item) => Err(SendError(item)),
+                None => Ok(()),
+            });
+        }
+
+        // If a previous send at this same address was taken, its pointer
+        // might still be in `taken_item`; it must be cleared before we stage,
+        // so that we don't mistake it for our item having been taken.
+        if lock.taken_item.load(Relaxed) == item_pointer.as_ptr() {
+            lock.taken_item.store(ptr::null_mut(), Relaxed);
+        }
+
         lock.sender_waker.register(cx.waker());
+        #[cfg(feature = "time")]
+        lock.staged_at.store(timer::now_nanos(), Relaxed);
         lock.sent_item.store(item_pointer.as_ptr(), Release);
-        lock.receiver_waker.wake();
         *this.item_lent = true;
+        inject!(SenderStaged);
+        lock.audit_staged(item_pointer);
+
+        // If there's no registered waker, the receiver isn't currently parked
+        // waiting for an item.
+        match lock.receiver_waker.take() {
+            Some(waker) => waker.wake(),
+            None => on_block(),
+        }
 
         Poll::Pending
     }
-}
 
-#[pinned_drop]
-impl<T> PinnedDrop for SendFut<'_, T> {
-    fn drop(self: Pin<&mut Self>) {
+    /// If `Inner` currently has access to our `item`, take it back, so that
+    /// we have exclusive access to it again. After this, the receiver can no
+    /// longer take the item.
+    fn withdraw(self: Pin<&mut Self>) {
         let this = self.project();
 
-        // We only need to do extra drop work if `Inner` has exclusive access to
+        // We only need to do extra work if `Inner` has exclusive access to
         // our `item`.
         if this.item_lent.not() {
             return;
         };
 
+        // For consistency, we always update this field before reclaiming the
+        // pointer, since we're definitely going to have reclaimed it by the
+        // time we return.
+        *this.item_lent = false;
+
         // If we disconnected, there's nothing else we need to do. Even if
         // `item_lent` was true, `inner` was dropped and implicitly doesn't have
         // access to the `item` anymore.
@@ -387,24 +1210,117 @@ impl<T> PinnedDrop for SendFut<'_, T> {
             return;
         };
 
-        // When an individual send future drops, we can immediately
+        // When an individual send future is withdrawn, we can immediately
         // erase the waker. No send notification are necessary until a
         // new send future appears.
         drop(lock.sender_waker.take());
 
         let item_pointer = this.item.into_ref().get().get_non_null();
 
-        // Okay, we need to acquire the pointer before we can drop. This
-        // might involve spinning if the receiver is working with it
-        // right now.
+        // Okay, we need to acquire the pointer. This might involve spinning if
+        // the receiver is working with it right now.
         lock.reclaim_sent_item_pointer(item_pointer);
-        // Now that we've reclaimed the pointer, we don't need to do
-        // anything else. The drop can proceed normally.
+    }
+
+    /// Cancel this send, returning the item if the receiver hasn't taken it
+    /// yet. If this returns `None`, the item was (or is being) received, and
+    /// the send should be considered a success.
+    ///
+    /// After this is called, the future is finished and must not be polled
+    /// again.
+    pub(crate) fn reclaim(mut self: Pin<&mut Self>) -> Option<T> {
+        self.as_mut().withdraw();
+
+        let this = self.project();
+        *this.awaiting_release = false;
+
+        // Safety: we just withdrew the item, so we have exclusive access to it.
+        unsafe { this.item.as_ref().get().get_non_null().as_mut() }.take()
+    }
+}
+
+#[pinned_drop]
+impl<T> PinnedDrop for SendFut<'_, T> {
+    fn drop(self: Pin<&mut Self>) {
+        // Once we've withdrawn the item, we don't need to do anything else.
+        // The drop can proceed normally.
+        self.withdraw();
     }
 }
 
 unsafe impl<T: Send> Send for SendFut<'_, T> {}
 
+/// Future for the [`send_or_drop`][Sender::send_or_drop] method. See its
+/// documentation for details.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+#[pin_project]
+#[derive(Debug)]
+pub struct SendOrDropFut<'a, T> {
+    #[pin]
+    send: SendFut<'a, T>,
+}
+
+impl<T> Future for SendOrDropFut<'_, T> {
+    type Output = bool;
+
+    #[inline]
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<bool> {
+        self.project().send.poll(cx).map(|result| result.is_ok())
+    }
+}
+
+/// Future for the [`flush`][Sender::flush] method. See its documentation for
+/// details.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+#[derive(Debug)]
+pub struct FlushFut<'a, T> {
+    inner: &'a Joint<Inner<T>>,
+}
+
+impl<T> Future for FlushFut<'_, T> {
+    type Output = Result<(), SendError<()>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let Some(lock) = self.inner.lock() else { return Poll::Ready(Err(SendError(()))) };
+
+        // The receiver wakes the sender whenever it takes or releases an
+        // item, so register before checking to avoid missing either.
+        lock.sender_waker.register(cx.waker());
+
+        match lock.sent_item.load(Acquire).is_null() && lock.item_held.load(Acquire).not() {
+            true => Poll::Ready(Ok(())),
+            false => Poll::Pending,
+        }
+    }
+}
+
+/// Future for the [`close_after_flush`][Sender::close_after_flush] method.
+/// See its documentation for details.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+#[derive(Debug)]
+pub struct CloseAfterFlushFut<T> {
+    // This is `None` once the sender has been closed.
+    sender: Option<Sender<T>>,
+}
+
+impl<T> Future for CloseAfterFlushFut<T> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let sender = self
+            .sender
+            .as_mut()
+            .expect("can't poll CloseAfterFlushFut after it completed");
+
+        // A flush only fails if the receiver is gone, in which case there's
+        // nothing left to wait for.
+        let _ = ready!(Pin::new(&mut sender.flush()).poll(cx));
+
+        self.sender = None;
+        Poll::Ready(())
+    }
+}
+
 // TODO: verify that this is sound. I believe it is in all practical
 // cases, since there isn't actually any uncontrolled mechanism in this
 // crate by which a reference to `item` might be used while it's owned
@@ -418,9 +1334,64 @@ unsafe impl<T> Sync for SendFut<'_, T> {}
 ///
 /// [`Receiver`] only provides a simple [`recv`][Receiver::recv] method on its
 /// own, but it also implements [`futures::StreamExt`][StreamExt], which provides a number
-/// of additional helpful iterator-like methods.
+/// of additional helpful iterator-like methods. Because the receiver is
+/// [`Unpin`], `&mut Receiver` is also a [`Stream`], so a receiver can be
+/// partially consumed by combinators that take the stream by value, like
+/// `(&mut receiver).take(3)`, and still be used afterward.
+///
+/// The receiver, and each future it returns, is [`Send`] whenever `T` is. So
+/// a receiver can be moved into a stream generator, like
+/// `async_stream::stream!` or [`unfold`][futures_util::stream::unfold], and
+/// the derived stream spawned onto a multithreaded runtime:
+///
+/// ```
+/// # futures::executor::block_on(async move {
+/// use futures::{future::join, stream, StreamExt};
+///
+/// let (mut sender, receiver) = handoff::channel();
+///
+/// let doubled = stream::unfold(receiver, |mut receiver| async move {
+///     let item = receiver.next().await?;
+///     Some((item * 2, receiver))
+/// });
+///
+/// let send_task = async move { sender.extend([1, 2, 3]).await.unwrap() };
+/// let ((), items) = join(send_task, doubled.collect::<Vec<_>>()).await;
+/// assert_eq!(items, [2, 4, 6]);
+/// # });
+/// ```
+///
+/// This is a single-consumer channel, so a `Receiver` can't be cloned. For
+/// multiple consumers, use the [`mpmc`] channel instead.
+///
+/// ```compile_fail,E0599
+/// let (_sender, receiver) = handoff::channel::<i32>();
+/// let receiver2 = receiver.clone();
+/// ```
 pub struct Receiver<T> {
     inner: Joint<Inner<T>>,
+
+    // An item that was taken from the sender by `peek`, but not yet received.
+    // While this is `Some`, the sender is blocked (see `Inner::item_held`),
+    // unless the item was prefetched.
+    peeked: Option<T>,
+
+    // True if the peeked item was taken by `prefetch`, which already released
+    // the sender.
+    prefetched: bool,
+
+    // How long the peeked item was staged before we took it.
+    #[cfg(feature = "time")]
+    peeked_wait: std::time::Duration,
+
+    // The descriptor from `readiness_fd`, once it's been created. It's also
+    // registered as the receiver's waker whenever `try_recv` is called.
+    #[cfg(all(feature = "reactor", target_os = "linux"))]
+    readiness: std::sync::OnceLock<std::sync::Arc<reactor::EventFd>>,
+
+    // The number of items that a draining future (like `fold_until`) can
+    // take in a single poll before it yields.
+    coop_budget: u32,
 }
 
 impl<T> Receiver<T> {
@@ -429,18 +1400,402 @@ impl<T> Receiver<T> {
     /// This method will asynchronously block until the sender sends an item,
     /// then return that item. Alternatively, if the sender disconnects, this
     /// will return `None`.
+    ///
+    /// # Cancellation
+    ///
+    /// This method is cancel safe. The item is taken from the sender and the
+    /// sender is woken in a single step within one `poll`, so there's no
+    /// point at which the future can be dropped with the item partially
+    /// received. If the future is dropped before it completes, the sender's
+    /// item stays where it is, the sender isn't woken, and the item will be
+    /// delivered by a subsequent receive.
+    ///
+    /// This makes `recv` suitable for use as a branch in a `select!` loop,
+    /// which creates a new future and polls it each time the task wakes,
+    /// whichever branch woke it. Polling while no item is available is cheap
+    /// (it only reads the item slot, without writing to it), and each poll
+    /// replaces the previously registered waker, so the receiver is woken
+    /// when an item arrives no matter how many times it was polled before.
     #[inline]
     pub fn recv(&mut self) -> RecvFut<'_, T> {
         RecvFut { receiver: self }
     }
-}
 
-unsafe impl<T: Send> Send for Receiver<T> {}
+    /// Receive the next item from the sender, or resolve to `default` if the
+    /// sender disconnects.
+    ///
+    /// This is equivalent to `recv().await.unwrap_or(default)`. If `default`
+    /// is expensive to compute, use [`recv_or_else`][Receiver::recv_or_else]
+    /// instead, which only computes it if it's needed.
+    #[inline]
+    pub fn recv_or(&mut self, default: T) -> RecvOrFut<'_, T> {
+        RecvOrFut {
+            recv: self.recv(),
+            default: Some(default),
+        }
+    }
+
+    /// Receive the next item from the sender, or resolve to the result of
+    /// `default` if the sender disconnects. `default` is only called if the
+    /// channel is disconnected.
+    #[inline]
+    pub fn recv_or_else<F>(&mut self, default: F) -> RecvOrElseFut<'_, T, F>
+    where
+        F: FnOnce() -> T,
+    {
+        RecvOrElseFut {
+            recv: self.recv(),
+            default: Some(default),
+        }
+    }
+
+    /// Convert this receiver into a future that receives a single item, then
+    /// resolves to that item along with the receiver itself.
+    ///
+    /// This is like [`StreamExt::into_future`], but concretely typed. It's
+    /// useful when a `'static` future is needed for a single receive, after
+    /// which the receiver can be reused.
+    ///
+    /// If the future is dropped before it completes, the receiver is dropped
+    /// with it, which disconnects the channel; a staged item is returned to
+    /// the sender in a [`SendError`], rather than lost. To cancel the receive
+    /// without disconnecting, use [`RecvOwnedFut::into_inner`].
+    #[inline]
+    pub fn into_future(self) -> RecvOwnedFut<T> {
+        RecvOwnedFut {
+            receiver: Some(self),
+        }
+    }
+
+    /// Receive the next item from the sender, and process it in place by
+    /// calling `f` with a reference to it.
+    ///
+    /// `f` is called exactly once, when an item arrives; the sender remains
+    /// blocked while `f` runs, and is unblocked once it returns. The item is
+    /// then dropped, and the result of `f` is returned. This is useful for
+    /// summarizing large items without moving them. If the sender disconnects,
+    /// this returns `None` without calling `f`.
+    ///
+    /// If `f` panics, the sender is still woken, but the item is not consumed;
+    /// it will be delivered by a subsequent receive.
+    #[inline]
+    pub fn recv_with<F, R>(&mut self, f: F) -> RecvWithFut<'_, T, F>
+    where
+        F: FnOnce(&T) -> R,
+    {
+        RecvWithFut {
+            receiver: self,
+            f: Some(f),
+        }
+    }
+
+    /// Receive items from the sender, combining them into an accumulator with
+    /// `f` until it returns [`ControlFlow::Break`].
+    ///
+    /// Each item is passed to `f` along with the current accumulator (which
+    /// starts as `init`); `f` returns the next accumulator, wrapped in either
+    /// [`ControlFlow::Continue`] to keep receiving or [`ControlFlow::Break`]
+    /// to stop. The future resolves to the final accumulator, either when `f`
+    /// breaks or when the sender disconnects.
+    ///
+    /// Unlike [`StreamExt::fold`], this only borrows the receiver, so it can
+    /// continue to be used after the fold stops early.
+    ///
+    /// To avoid starving other tasks when the sender is always ready, the
+    /// future yields to the executor after taking a number of items in a
+    /// single poll; see [`Builder::coop_budget`].
+    #[inline]
+    pub fn fold_until<B, F>(&mut self, init: B, f: F) -> FoldUntilFut<'_, T, B, F>
+    where
+        F: FnMut(B, T) -> ControlFlow<B, B>,
+    {
+        FoldUntilFut {
+            receiver: self,
+            acc: Some(init),
+            f,
+        }
+    }
+
+    /// Wait for the next item from the sender, and return a reference to it
+    /// without receiving it.
+    ///
+    /// The sender remains blocked while the item is peeked; it's only released
+    /// once the item is actually received (by [`recv`][Receiver::recv], the
+    /// [`Stream`] implementation, etc). Peeking repeatedly returns the same
+    /// item. If the sender disconnects, this returns `None`.
+    ///
+    /// If the receiver is dropped while an item is peeked, the send is
+    /// considered to have succeeded.
+    #[inline]
+    pub fn peek(&mut self) -> PeekFut<'_, T> {
+        PeekFut {
+            receiver: Some(self),
+        }
+    }
+
+    /// Receive the next item into a one-item buffer inside the receiver,
+    /// releasing the sender right away.
+    ///
+    /// The next [`recv`][Receiver::recv] (or any other receive) returns the
+    /// buffered item instantly, while the sender has already moved on to
+    /// its next item. This smooths out handoff latency for a consumer that
+    /// can't always be waiting, at the cost of strict rendezvous timing: the
+    /// sender's send completes before the consumer actually uses the item,
+    /// and if the receiver is dropped with an item buffered, the item is
+    /// lost even though the send succeeded.
+    ///
+    /// If an item is already buffered, or [peeked][Receiver::peek], this
+    /// completes immediately, releasing the sender of a peeked item. If the
+    /// sender disconnects, this completes without buffering anything.
+    ///
+    /// ```
+    /// # futures::executor::block_on(async move {
+    /// use futures::future::join;
+    ///
+    /// let (mut sender, mut receiver) = handoff::channel();
+    ///
+    /// // The send completes as soon as the item is prefetched
+    /// let (sent, ()) = join(sender.send(1), receiver.prefetch()).await;
+    /// sent.unwrap();
+    /// drop(sender);
+    ///
+    /// assert_eq!(receiver.recv().await, Some(1));
+    /// assert_eq!(receiver.recv().await, None);
+    /// # });
+    /// ```
+    #[inline]
+    pub fn prefetch(&mut self) -> PrefetchFut<'_, T> {
+        PrefetchFut { receiver: self }
+    }
+
+    /// Poll to peek at the next item from the sender. This is the poll-based
+    /// equivalent of [`peek`][Receiver::peek]; see its documentation for
+    /// details.
+    pub fn poll_peek(&mut self, cx: &mut Context<'_>) -> Poll<Option<&T>> {
+        self.poll_fill_peeked(cx).map(|_| self.peeked.as_ref())
+    }
+
+    /// Poll until an item is ready to be taken with
+    /// [`take_staged`][Receiver::take_staged], without taking it. Resolves to
+    /// `None` if the sender disconnects.
+    ///
+    /// Together with `take_staged`, this splits receiving into two phases,
+    /// so that the receiver can decide whether to take an item at all once
+    /// it knows one is available. Readiness is established by
+    /// [peeking][Receiver::peek], so once this resolves to `Some`, the
+    /// sender can't withdraw its item, and it stays blocked until the item
+    /// is taken.
+    #[inline]
+    pub fn poll_peek_ready(&mut self, cx: &mut Context<'_>) -> Poll<Option<()>> {
+        self.poll_fill_peeked(cx).map(|ready| ready.then_some(()))
+    }
+
+    /// Take the item that [`poll_peek_ready`][Receiver::poll_peek_ready]
+    /// signaled was ready, waking the sender just like any other receive.
+    ///
+    /// This should only be called after `poll_peek_ready` resolved to
+    /// `Some`, in which case it's guaranteed to return the item. Otherwise,
+    /// it behaves like [`try_recv`][Receiver::try_recv], returning `None` if
+    /// no item is staged.
+    #[inline]
+    pub fn take_staged(&mut self) -> Option<T> {
+        self.try_recv().ok()
+    }
+
+    /// Check if the sender is still connected.
+    ///
+    /// Note that the sender may disconnect at any time, so a `true` result is
+    /// only a hint. Once this returns `false`, though, it will never again
+    /// return `true`. Even if the sender is disconnected, a previously
+    /// [peeked][Receiver::peek] item can still be received.
+    #[inline]
+    #[must_use = "checking the connection has no effect on its own"]
+    pub fn is_connected(&self) -> bool {
+        self.inner.alive()
+    }
+
+    /// The number of items the channel can hold without a receiver taking
+    /// them. This is always 0, since every send waits for the receiver.
+    #[inline]
+    #[must_use]
+    pub const fn capacity(&self) -> usize {
+        0
+    }
+
+    /// The number of items that are ready to be received without waiting:
+    /// 1 if a sender has an item staged, or if an item was
+    /// [peeked][Receiver::peek] but not yet received, and 0 otherwise.
+    ///
+    /// This only reads the channel's state, without waking or otherwise
+    /// affecting the sender. Like [`is_connected`][Receiver::is_connected],
+    /// the result is only a hint, since the sender may stage an item at any
+    /// time.
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        if self.peeked.is_some() {
+            return 1;
+        }
+
+        match self.inner.lock() {
+            Some(lock) => lock.sent_item.load(Relaxed).is_null().not() as usize,
+            None => 0,
+        }
+    }
+
+    /// Check if there are no items ready to be received without waiting.
+    /// See [`len`][Receiver::len] for details.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Attempt to receive an item without blocking.
+    ///
+    /// This succeeds only if a sender is currently blocked sending an item
+    /// (or an item was previously [peeked][Receiver::peek]). Otherwise, it
+    /// returns [`TryRecvError::Empty`], or [`TryRecvError::Disconnected`] if
+    /// the sender has disconnected. Combined with
+    /// [`register_recv_waker`][Receiver::register_recv_waker], this allows
+    /// the channel to be driven by a custom reactor instead of as a future.
+    #[must_use = "if the result is unused, a received item is dropped"]
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        if let Some(item) = self.peeked.take() {
+            self.release_held_item();
+            return Ok(item);
+        }
+
+        let Some(lock) = self.inner.lock() else { return Err(TryRecvError::Disconnected) };
+
+        // Reset the readiness descriptor, if any, and re-arm it before
+        // checking for an item, so that it's signalled for the next one.
+        #[cfg(all(feature = "reactor", target_os = "linux"))]
+        if let Some(event) = self.readiness.get() {
+            event.drain();
+            lock.receiver_waker.register(&event.waker());
+        }
+
+        lock.try_recv_with(|slot| slot.take().expect("slot is guaranteed to be full"))
+            .map_err(|_| TryRecvError::Empty)
+    }
+
+    /// Attempt to receive an item without blocking, converting a failure
+    /// into a custom error.
+    ///
+    /// This is the same as [`try_recv`][Receiver::try_recv], but calls
+    /// `on_empty` or `on_disconnect` to create the error, instead of
+    /// returning a [`TryRecvError`].
+    #[inline]
+    pub fn try_recv_mapped<E>(
+        &mut self,
+        on_empty: impl FnOnce() -> E,
+        on_disconnect: impl FnOnce() -> E,
+    ) -> Result<T, E> {
+        self.try_recv().map_err(|err| match err {
+            TryRecvError::Empty => on_empty(),
+            TryRecvError::Disconnected => on_disconnect(),
+        })
+    }
+
+    /// Register a waker to be woken when the sender has an item available,
+    /// or disconnects.
+    ///
+    /// This is intended for custom reactors, which can register interest
+    /// with this method and then call [`try_recv`][Receiver::try_recv] once
+    /// woken. It can be called repeatedly; each call replaces the previously
+    /// registered waker, including one registered by polling this receiver.
+    /// Because the item might have arrived before the waker was registered,
+    /// you should always call `try_recv` after registering.
+    #[inline]
+    pub fn register_recv_waker(&mut self, waker: &Waker) {
+        match self.inner.lock() {
+            Some(lock) => {
+                lock.receiver_waker.register(waker);
+                #[cfg(feature = "diagnostics")]
+                lock.registrations.record(waker);
+                lock.signal_demand();
+            }
+            None => waker.wake_by_ref(),
+        }
+    }
+
+    /// Poll until there's a peeked item, taking it from the sender if
+    /// necessary. Resolves to `false` if the sender disconnected.
+    fn poll_fill_peeked(&mut self, cx: &mut Context<'_>) -> Poll<bool> {
+        if self.peeked.is_some() {
+            return Poll::Ready(true);
+        }
+
+        let Some(lock) = self.inner.lock() else { return Poll::Ready(false) };
+        let peeked = &mut self.peeked;
+        #[cfg(feature = "time")]
+        let peeked_wait = &mut self.peeked_wait;
+
+        lock.poll_recv_with(cx, |slot| {
+            // This must happen before the item pointer is handed back, so
+            // that the sender can't observe an empty slot without also
+            // observing that we're holding its item.
+            lock.item_held.store(true, Relaxed);
+            *peeked = slot.take();
+
+            #[cfg(feature = "time")]
+            {
+                *peeked_wait = timer::elapsed_since(lock.staged_at.load(Relaxed));
+            }
+        })
+        .map(|()| true)
+    }
+
+    /// Shared logic for polling to receive an item. `op` is given exclusive
+    /// access to the sent item slot, which is guaranteed to contain an item;
+    /// see [`Inner::try_recv_with`] for details.
+    fn poll_recv_with<F, R>(&mut self, cx: &mut Context<'_>, op: F) -> Poll<Option<R>>
+    where
+        F: FnOnce(&mut Option<T>) -> R,
+    {
+        if self.peeked.is_some() {
+            let result = op(&mut self.peeked);
+
+            if self.peeked.is_none() {
+                self.release_held_item();
+            }
+
+            return Poll::Ready(Some(result));
+        }
+
+        let Some(lock) = self.inner.lock() else { return Poll::Ready(None) };
+        lock.poll_recv_with(cx, op).map(Some)
+    }
+
+    /// Release the sender after a peeked item was received.
+    fn release_held_item(&mut self) {
+        // A prefetched item's sender was already released
+        if std::mem::take(&mut self.prefetched) {
+            return;
+        }
+
+        if let Some(lock) = self.inner.lock() {
+            lock.audit_holding();
+            lock.item_held.store(false, Release);
+            lock.sender_waker.wake();
+        }
+    }
+}
+
+unsafe impl<T: Send> Send for Receiver<T> {}
+
+// The peeked item is never pinned, so the receiver is `Unpin` regardless of
+// `T`.
+impl<T> Unpin for Receiver<T> {}
 
 impl<T> Debug for Receiver<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Receiver")
             .field("inner", &self.inner)
+            .field("peeked", &self.peeked.as_ref().map(|_| "<item>"))
+            .field("prefetched", &self.prefetched)
+            .field("coop_budget", &self.coop_budget)
             .finish()
     }
 }
@@ -448,94 +1803,21 @@ impl<T> Debug for Receiver<T> {
 impl<T> Stream for Receiver<T> {
     type Item = T;
 
-    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        let Some(lock) = self.inner.lock() else { return Poll::Ready(None) };
-
-        // All of the logic for actually attempting to take an item from
-        // `inner`. We have to call this twice, because we first try to take an
-        // item, then register a waker, then we have to try again after
-        // registering the waker. This avoids a race where we fail to retrieve
-        // and item, then the sender places an item, then the sender calls
-        // wake() before we've registered our waker.
-        //
-        // TODO: bench {recv; register(waker); recv} against {register(waker); recv}
-
-        let try_recv = || loop {
-            // Acquire the pointer. As long as we have it, we have exclusive
-            // access to the item. The sender will wait for us to return the
-            // pointer before dropping (or, if it leaks, the value is pinned, so
-            // the pointer is valid forever in that case).
-            let sent_item_ptr = lock.sent_item.swap(ptr::null_mut(), Acquire);
-
-            // If there wasn't a pointer available, we've already registered our
-            // waker, so at this point we're waiting for a signal to try another
-            // receive operation.
-            let Some(mut sent_item_ptr) = NonNull::new(sent_item_ptr) else {
-                return Poll::Pending
-            };
-
-            // Try to read the item from the pointer. It's possible that we've
-            // already taken it and this is a spurious poll.
-            //
-            // SAFETY: Because we acquired the `sent_item_ptr` (replacing it
-            // with a null ptr), we have exclusive access to it.
-            let sent_item = unsafe { sent_item_ptr.as_mut() }.take();
-
-            // We don't need to retry (non-spurious) failures, since the
-            // presence of a new non-null pointer indicates a sender leak, which
-            // means we can simply drop the `sent_item_ptr` outright.
-            match lock.sent_item.compare_exchange(
-                ptr::null_mut(),
-                sent_item_ptr.as_ptr(),
-                Release,
-                Relaxed,
-            ) {
-                // We restored the pointer, so we need to wake the sender so it
-                // can proceed with the drop
-                Ok(_) => lock.sender_waker.wake(),
-
-                // Somehow the pointer to a pinned object found its way back
-                // into the slot. This shouldn't be possible, since that memory
-                // should be usable until the sender finishes sending, and it
-                // can't drop until we restore the pointer.
-                Err(p) if p == sent_item_ptr.as_ptr() => unsafe { debug_unreachable!() },
-
-                // There was a leak and a new sent item arrived while we were
-                // working. If we didn't receive an item, we can retry receiving
-                // this *new* item.
-                Err(_) if sent_item.is_none() => continue,
-
-                // There was a leak and a new sent item arrived while we were
-                // working. We already got an item, so we have to leave the new
-                // one there until a subsequent `recv`.
-                Err(_) => {}
-            }
-
-            return match sent_item {
-                Some(item) => Poll::Ready(Some(item)),
-                None => Poll::Pending,
-            };
-        };
-
-        match try_recv() {
-            Poll::Ready(item) => Poll::Ready(item),
-            Poll::Pending => {
-                lock.receiver_waker.register(cx.waker());
-                try_recv()
-            }
-        }
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.poll_recv_with(cx, Option::take).map(Option::flatten)
     }
 
     #[inline]
-    #[must_use]
     fn size_hint(&self) -> (usize, Option<usize>) {
-        (0, if self.inner.alive() { None } else { Some(0) })
+        let peeked = self.peeked.is_some() as usize;
+        let upper = self.inner.alive().not().then_some(peeked);
+        (peeked, upper)
     }
 }
 
 impl<T> FusedStream for Receiver<T> {
     fn is_terminated(&self) -> bool {
-        !self.inner.alive()
+        self.peeked.is_none() && !self.inner.alive()
     }
 }
 
@@ -545,12 +1827,15 @@ impl<T> FusedStream for Receiver<T> {
 impl<T> Drop for Receiver<T> {
     fn drop(&mut self) {
         let Some(lock) = self.inner.lock() else { return };
-        drop(lock.receiver_waker.take())
+        lock.receiver_closing.store(true, Relaxed);
+        inject!(ReceiverClosing);
+        lock.stop_waiting()
     }
 }
 
 /// Future type for receiving a single item from a [`Receiver`]. Created by the
 /// [`recv`][Receiver::recv] method; see its documentation for details.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
 pub struct RecvFut<'a, T> {
     receiver: &'a mut Receiver<T>,
 }
@@ -576,185 +1861,1872 @@ impl<T> Drop for RecvFut<'_, T> {
     #[inline]
     fn drop(&mut self) {
         let Some(lock) = self.receiver.inner.lock() else { return };
-        drop(lock.receiver_waker.take())
+        lock.stop_waiting()
     }
 }
 
-/// An error from a [`send()`][Sender::send] operation.
-///
-/// This error means the send failed due to a disconnect; this is the only way
-/// sends can fail. The error contains the item that failed to send.
-#[derive(Error, Clone, Debug, Copy)]
-#[error("tried to send on a disconnected channel")]
-pub struct SendError<T>(
-    /// The item that failed to send
-    pub T,
-);
+/// Future type for receiving a single item from a [`Receiver`], with a
+/// fallback. Created by the [`recv_or`][Receiver::recv_or] method; see its
+/// documentation for details.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct RecvOrFut<'a, T> {
+    recv: RecvFut<'a, T>,
+    // This is `None` after the future completes.
+    default: Option<T>,
+}
 
-#[cfg(test)]
-mod tests {
-    use std::thread;
+impl<T: Debug> Debug for RecvOrFut<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RecvOr")
+            .field("receiver", &self.recv.receiver)
+            .field("default", &self.default)
+            .finish()
+    }
+}
 
-    use cool_asserts::assert_matches;
-    use futures::{executor::block_on, StreamExt};
+impl<T> Future for RecvOrFut<'_, T> {
+    type Output = T;
 
-    use super::{channel, SendError};
+    #[inline]
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        let item = ready!(Pin::new(&mut self.recv).poll(cx));
+        let default = self
+            .default
+            .take()
+            .expect("Don't poll futures after they completed");
+
+        Poll::Ready(item.unwrap_or(default))
+    }
+}
 
-    #[tokio::test]
-    async fn basic_test() {
-        let (mut sender, receiver) = channel();
+// The default is never pinned, so we can be `Unpin` regardless of `T`.
+impl<T> Unpin for RecvOrFut<'_, T> {}
+
+/// Future type for receiving a single item from a [`Receiver`], with a
+/// lazily computed fallback. Created by the
+/// [`recv_or_else`][Receiver::recv_or_else] method; see its documentation
+/// for details.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct RecvOrElseFut<'a, T, F> {
+    recv: RecvFut<'a, T>,
+    // This is `None` after the future completes.
+    default: Option<F>,
+}
 
-        let sender_task = tokio::task::spawn(async move {
-            sender.send(1).await.unwrap();
-            sender.send(2).await.unwrap();
-            sender.send(3).await.unwrap();
-            sender.send(4).await.unwrap();
-        });
+impl<T, F> Debug for RecvOrElseFut<'_, T, F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RecvOrElse")
+            .field("receiver", &self.recv.receiver)
+            .field("default", &self.default.as_ref().map(|_| "<closure>"))
+            .finish()
+    }
+}
 
-        let data: Vec<i32> = receiver.collect().await;
-        sender_task.await.unwrap();
+impl<T, F> Future for RecvOrElseFut<'_, T, F>
+where
+    F: FnOnce() -> T,
+{
+    type Output = T;
 
-        assert_eq!(data, [1, 2, 3, 4]);
+    #[inline]
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        let item = ready!(Pin::new(&mut self.recv).poll(cx));
+        let default = self
+            .default
+            .take()
+            .expect("Don't poll futures after they completed");
+
+        Poll::Ready(item.unwrap_or_else(default))
     }
+}
 
-    #[tokio::test]
-    async fn taskless() {
-        let (mut sender, mut receiver) = channel();
+// The closure is never pinned, so we can be `Unpin` regardless of `F`.
+impl<T, F> Unpin for RecvOrElseFut<'_, T, F> {}
 
-        let (send, recv) = futures::future::join(sender.send(1), receiver.next()).await;
-        send.unwrap();
-        assert_eq!(recv.unwrap(), 1);
+/// Future type for receiving a single item from an owned [`Receiver`].
+/// Created by the [`into_future`][Receiver::into_future] method; see its
+/// documentation for details.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct RecvOwnedFut<T> {
+    // This is `None` after the future completes, since the output owns it.
+    receiver: Option<Receiver<T>>,
+}
 
-        let (recv, send) = futures::future::join(receiver.next(), sender.send(2)).await;
-        send.unwrap();
-        assert_eq!(recv.unwrap(), 2);
+impl<T> RecvOwnedFut<T> {
+    /// Cancel the receive, returning the receiver. Any item the sender is
+    /// currently sending remains available to be received. Returns `None` if
+    /// the future already completed.
+    #[inline]
+    #[must_use]
+    pub fn into_inner(self) -> Option<Receiver<T>> {
+        self.receiver
     }
+}
 
-    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
-    async fn multi_thread_tasks() {
-        let (mut sender, mut receiver) = channel();
-
-        let sender_task = tokio::task::spawn(async move {
-            for i in 0..1_000 {
-                sender.send(i).await.unwrap();
-            }
-        });
+impl<T> Debug for RecvOwnedFut<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RecvOwned")
+            .field("receiver", &self.receiver)
+            .finish()
+    }
+}
 
-        let receiver_task = tokio::task::spawn(async move {
-            for i in 0..1_000 {
-                assert_eq!(receiver.next().await.unwrap(), i);
-            }
-        });
+impl<T> Future for RecvOwnedFut<T> {
+    type Output = (Option<T>, Receiver<T>);
 
-        sender_task.await.unwrap();
-        receiver_task.await.unwrap();
-    }
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let receiver = self
+            .receiver
+            .as_mut()
+            .expect("Don't poll futures after they completed");
 
-    #[test]
-    fn sync_test() {
-        let (mut sender, mut receiver) = channel();
+        let item = ready!(receiver.poll_next_unpin(cx));
+        let receiver = self.receiver.take().unwrap();
 
-        let sender_thread = thread::Builder::new()
-            .name("sender thread".to_owned())
-            .spawn(move || {
-                block_on(async move {
-                    for i in 0..1_000 {
-                        sender.send(i).await.unwrap();
-                    }
-                })
-            })
-            .expect("failed to spawn thread");
+        Poll::Ready((item, receiver))
+    }
+}
 
-        for i in 0..1_000 {
-            assert_eq!(block_on(receiver.next()).unwrap(), i);
-        }
+/// Future type for peeking at the next item from a [`Receiver`]. Created by
+/// the [`peek`][Receiver::peek] method; see its documentation for details.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct PeekFut<'a, T> {
+    // This is `None` after the future completes, since the output borrows it.
+    receiver: Option<&'a mut Receiver<T>>,
+}
 
-        sender_thread.join().unwrap();
+impl<T> Debug for PeekFut<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Peek")
+            .field("receiver", &self.receiver)
+            .finish()
     }
+}
 
-    #[test]
-    fn sync_test_two_threads() {
-        let (mut sender, mut receiver) = channel();
+impl<'a, T> Future for PeekFut<'a, T> {
+    type Output = Option<&'a T>;
 
-        let sender_thread = thread::spawn(move || {
-            for i in 0..100 {
-                block_on(sender.send(i)).expect("receiver disconnected");
-            }
-        });
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let receiver = self
+            .receiver
+            .take()
+            .expect("Don't poll futures after they completed");
 
-        let receiver_thread = thread::spawn(move || {
-            for i in 0..100 {
-                let value = block_on(receiver.recv()).expect("sender disconnected");
-                assert_eq!(value, i);
+        match receiver.poll_fill_peeked(cx) {
+            Poll::Ready(_) => Poll::Ready(receiver.peeked.as_ref()),
+            Poll::Pending => {
+                self.receiver = Some(receiver);
+                Poll::Pending
             }
-        });
+        }
+    }
+}
 
-        sender_thread.join().expect("sender panicked");
-        receiver_thread.join().expect("receiver panicked");
+impl<T> Drop for PeekFut<'_, T> {
+    #[inline]
+    fn drop(&mut self) {
+        let Some(receiver) = &self.receiver else { return };
+        let Some(lock) = receiver.inner.lock() else { return };
+        lock.stop_waiting()
     }
+}
 
-    #[tokio::test]
-    async fn basic_sender_close() {
-        let (sender, mut receiver) = channel();
+/// Future type for prefetching the next item into a [`Receiver`]. Created by
+/// the [`prefetch`][Receiver::prefetch] method; see its documentation for
+/// details.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+#[derive(Debug)]
+pub struct PrefetchFut<'a, T> {
+    receiver: &'a mut Receiver<T>,
+}
 
-        drop(sender);
+impl<T> Future for PrefetchFut<'_, T> {
+    type Output = ();
 
-        let out: Option<i32> = receiver.recv().await;
-        assert_eq!(out, None);
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let receiver = &mut *self.receiver;
+
+        if ready!(receiver.poll_fill_peeked(cx)) && !receiver.prefetched {
+            receiver.release_held_item();
+            receiver.prefetched = true;
+        }
+
+        Poll::Ready(())
     }
+}
 
-    #[tokio::test]
-    async fn basic_receiver_close() {
-        let (mut sender, receiver) = channel();
+impl<T> Drop for PrefetchFut<'_, T> {
+    #[inline]
+    fn drop(&mut self) {
+        let Some(lock) = self.receiver.inner.lock() else { return };
+        lock.stop_waiting()
+    }
+}
 
-        drop(receiver);
+/// Future type for receiving and processing a single item from a
+/// [`Receiver`]. Created by the [`recv_with`][Receiver::recv_with] method;
+/// see its documentation for details.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct RecvWithFut<'a, T, F> {
+    receiver: &'a mut Receiver<T>,
+    f: Option<F>,
+}
 
-        assert_matches!(sender.send(1).await, Err(SendError(1)));
+impl<T, F> Debug for RecvWithFut<'_, T, F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RecvWith")
+            .field("receiver", &self.receiver)
+            .field("f", &self.f.as_ref().map(|_| "<closure>"))
+            .finish()
+    }
+}
+
+impl<T, F, R> Future for RecvWithFut<'_, T, F>
+where
+    F: FnOnce(&T) -> R,
+{
+    type Output = Option<R>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let f = &mut this.f;
+
+        let poll = this.receiver.poll_recv_with(cx, |slot| {
+            let f = f.take().expect("Don't poll futures after they completed");
+            let result = f(slot.as_ref().expect("slot is guaranteed to be full"));
+            (result, slot.take())
+        });
+
+        // The item itself is dropped here, after the sender has been released.
+        poll.map(|out| out.map(|(result, _item)| result))
+    }
+}
+
+// The closure is never pinned, so we can be `Unpin` regardless of `F`.
+impl<T, F> Unpin for RecvWithFut<'_, T, F> {}
+
+impl<T, F> Drop for RecvWithFut<'_, T, F> {
+    #[inline]
+    fn drop(&mut self) {
+        let Some(lock) = self.receiver.inner.lock() else { return };
+        lock.stop_waiting()
+    }
+}
+
+/// Future type for folding items from a [`Receiver`]. Created by the
+/// [`fold_until`][Receiver::fold_until] method; see its documentation for
+/// details.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct FoldUntilFut<'a, T, B, F> {
+    receiver: &'a mut Receiver<T>,
+    // This is `None` after the future completes.
+    acc: Option<B>,
+    f: F,
+}
+
+impl<T, B: Debug, F> Debug for FoldUntilFut<'_, T, B, F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FoldUntil")
+            .field("receiver", &self.receiver)
+            .field("acc", &self.acc)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T, B, F> Future for FoldUntilFut<'_, T, B, F>
+where
+    F: FnMut(B, T) -> ControlFlow<B, B>,
+{
+    type Output = B;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+
+        // If the sender is on another thread and always ready, this could
+        // loop indefinitely, so we yield once we've used up our budget.
+        let mut budget = this.receiver.coop_budget;
+
+        loop {
+            let item = ready!(this.receiver.poll_next_unpin(cx));
+            let acc = this
+                .acc
+                .take()
+                .expect("Don't poll futures after they completed");
+
+            let Some(item) = item else { return Poll::Ready(acc) };
+
+            match (this.f)(acc, item) {
+                ControlFlow::Continue(acc) => this.acc = Some(acc),
+                ControlFlow::Break(acc) => return Poll::Ready(acc),
+            }
+
+            budget = budget.saturating_sub(1);
+
+            if budget == 0 {
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+        }
+    }
+}
+
+// Neither the accumulator nor the closure is ever pinned.
+impl<T, B, F> Unpin for FoldUntilFut<'_, T, B, F> {}
+
+impl<T, B, F> Drop for FoldUntilFut<'_, T, B, F> {
+    #[inline]
+    fn drop(&mut self) {
+        let Some(lock) = self.receiver.inner.lock() else { return };
+        lock.stop_waiting()
+    }
+}
+
+/// An error from a [`send()`][Sender::send] operation.
+///
+/// This error means the send failed due to a disconnect; this is the only way
+/// sends can fail. The error contains the item that failed to send.
+///
+/// This type is `#[repr(transparent)]`, so it's guaranteed to have the same
+/// layout as `T`.
+#[derive(Error, Clone, Debug, Copy)]
+#[error("tried to send on a disconnected channel")]
+#[repr(transparent)]
+pub struct SendError<T>(
+    /// The item that failed to send
+    pub T,
+);
+
+impl<T> SendError<T> {
+    /// Get the item that failed to send.
+    #[inline]
+    #[must_use]
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+
+    /// Get an adapter that displays this error along with the item that
+    /// failed to send, for logging.
+    ///
+    /// The error's own [`Display`][std::fmt::Display] implementation never
+    /// includes the item, so that its text is the same for every `T`.
+    #[inline]
+    #[must_use]
+    pub fn with_item(&self) -> SendErrorWithItem<'_, T> {
+        SendErrorWithItem { error: self }
+    }
+}
+
+/// Adapter that displays a [`SendError`] along with the item that failed to
+/// send. Created by the [`SendError::with_item`] method.
+#[derive(Debug, Clone, Copy)]
+pub struct SendErrorWithItem<'a, T> {
+    error: &'a SendError<T>,
+}
+
+impl<T: std::fmt::Display> std::fmt::Display for SendErrorWithItem<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.error, self.error.0)
+    }
+}
+
+/// An error from a [`try_recv`][Receiver::try_recv] operation.
+#[derive(Error, Clone, Debug, Copy, PartialEq, Eq, Hash)]
+pub enum TryRecvError {
+    /// The sender isn't currently sending an item.
+    #[error("no item is available to receive")]
+    Empty,
+
+    /// The sender disconnected.
+    #[error("tried to receive on a disconnected channel")]
+    Disconnected,
+}
+
+/// The error returned by [`Sender::duplicate`]. A handoff channel has
+/// exactly one sender; use the [`mpmc`] channel for multiple producers.
+#[derive(Error, Clone, Debug, Copy, PartialEq, Eq, Hash)]
+#[error("a handoff channel has a single producer; use `handoff::mpmc` for multiple senders")]
+pub struct DuplicateError;
+
+/// An error from a receive operation with a time limit, such as
+/// `Receiver::recv_blocking_timeout`, indicating that no item was sent in
+/// time.
+#[derive(Error, Clone, Debug, Copy, PartialEq, Eq, Hash)]
+#[error("timed out waiting for the sender to send an item")]
+pub struct RecvTimeoutError;
+
+/// An error from a send operation with a time limit, such as
+/// `Sender::send_timeout`.
+///
+/// In either case, the error contains the item that failed to send.
+#[derive(Error, Clone, Debug, Copy, PartialEq, Eq)]
+pub enum SendTimeoutError<T> {
+    /// The time limit elapsed before the receiver took the item.
+    #[error("timed out waiting for the receiver to take the item")]
+    Timeout(T),
+
+    /// The receiver disconnected before taking the item.
+    #[error("tried to send on a disconnected channel")]
+    Disconnected(T),
+}
+
+impl<T> SendTimeoutError<T> {
+    /// Get the item that failed to send.
+    #[inline]
+    #[must_use]
+    pub fn into_inner(self) -> T {
+        match self {
+            SendTimeoutError::Timeout(item) | SendTimeoutError::Disconnected(item) => item,
+        }
+    }
+}
+
+impl<T> From<SendError<T>> for SendTimeoutError<T> {
+    #[inline]
+    fn from(SendError(item): SendError<T>) -> Self {
+        SendTimeoutError::Disconnected(item)
+    }
+}
+
+/// An error from a [`weak_send`][Sender::weak_send].
+///
+/// In either case, the error contains the item that failed to send.
+#[derive(Error, Clone, Debug, Copy, PartialEq, Eq)]
+pub enum WeakSendError<T> {
+    /// The receiver didn't take the item within one scheduling round.
+    #[error("the receiver didn't take the item without waiting")]
+    WouldBlock(T),
+
+    /// The receiver disconnected before taking the item.
+    #[error("tried to send on a disconnected channel")]
+    Disconnected(T),
+}
+
+impl<T> WeakSendError<T> {
+    /// Get the item that failed to send.
+    #[inline]
+    #[must_use]
+    pub fn into_inner(self) -> T {
+        match self {
+            WeakSendError::WouldBlock(item) | WeakSendError::Disconnected(item) => item,
+        }
+    }
+}
+
+impl<T> From<SendError<T>> for WeakSendError<T> {
+    #[inline]
+    fn from(SendError(item): SendError<T>) -> Self {
+        WeakSendError::Disconnected(item)
+    }
+}
+
+/// An error from a [`send_abortable`][Sender::send_abortable].
+///
+/// In either case, the error contains the item that failed to send.
+#[derive(Error, Clone, Debug, Copy, PartialEq, Eq)]
+pub enum AbortableSendError<T> {
+    /// The send was aborted before the receiver took the item.
+    #[error("the send was aborted")]
+    Aborted(T),
+
+    /// The receiver disconnected before taking the item.
+    #[error("tried to send on a disconnected channel")]
+    Disconnected(T),
+}
+
+impl<T> AbortableSendError<T> {
+    /// Get the item that failed to send.
+    #[inline]
+    #[must_use]
+    pub fn into_inner(self) -> T {
+        match self {
+            AbortableSendError::Aborted(item) | AbortableSendError::Disconnected(item) => item,
+        }
+    }
+}
+
+impl<T> From<SendError<T>> for AbortableSendError<T> {
+    #[inline]
+    fn from(SendError(item): SendError<T>) -> Self {
+        AbortableSendError::Disconnected(item)
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{future::Future, ops::ControlFlow, thread};
+
+    use cool_asserts::assert_matches;
+    use futures::{executor::block_on, FutureExt, StreamExt};
+
+    use super::{
+        channel, channel_boxed, reconnect, testing::CountingWaker, SendError, TryRecvError,
+    };
 
     #[tokio::test]
-    async fn sender_close_while_waiting() {
-        let (sender, mut receiver) = channel();
+    async fn basic_test() {
+        let (mut sender, receiver) = channel();
 
         let sender_task = tokio::task::spawn(async move {
-            tokio::task::yield_now().await;
-            drop(sender);
+            sender.send(1).await.unwrap();
+            sender.send(2).await.unwrap();
+            sender.send(3).await.unwrap();
+            sender.send(4).await.unwrap();
         });
 
-        let out: Option<i32> = receiver.recv().await;
-        assert_eq!(out, None);
+        let data: Vec<i32> = receiver.collect().await;
         sender_task.await.unwrap();
+
+        assert_eq!(data, [1, 2, 3, 4]);
     }
 
     #[tokio::test]
-    async fn receiver_close_while_waiting() {
-        let (mut sender, receiver) = channel();
+    async fn taskless() {
+        let (mut sender, mut receiver) = channel();
+
+        let (send, recv) = futures::future::join(sender.send(1), receiver.next()).await;
+        send.unwrap();
+        assert_eq!(recv.unwrap(), 1);
+
+        let (recv, send) = futures::future::join(receiver.next(), sender.send(2)).await;
+        send.unwrap();
+        assert_eq!(recv.unwrap(), 2);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn multi_thread_tasks() {
+        let (mut sender, mut receiver) = channel();
+
+        let sender_task = tokio::task::spawn(async move {
+            for i in 0..1_000 {
+                sender.send(i).await.unwrap();
+            }
+        });
 
         let receiver_task = tokio::task::spawn(async move {
-            tokio::task::yield_now().await;
-            drop(receiver);
+            for i in 0..1_000 {
+                assert_eq!(receiver.next().await.unwrap(), i);
+            }
         });
 
-        assert_matches!(sender.send(1).await, Err(SendError(1)));
+        sender_task.await.unwrap();
         receiver_task.await.unwrap();
     }
 
-    #[tokio::test]
-    async fn sender_cancels() {
+    #[test]
+    fn sync_test() {
         let (mut sender, mut receiver) = channel();
 
-        let sender_task = tokio::task::spawn(async move {
-            sender.send(1).await.unwrap();
-            sender.send(2).await.unwrap();
+        let sender_thread = thread::Builder::new()
+            .name("sender thread".to_owned())
+            .spawn(move || {
+                block_on(async move {
+                    for i in 0..1_000 {
+                        sender.send(i).await.unwrap();
+                    }
+                })
+            })
+            .expect("failed to spawn thread");
+
+        for i in 0..1_000 {
+            assert_eq!(block_on(receiver.next()).unwrap(), i);
+        }
+
+        sender_thread.join().unwrap();
+    }
+
+    #[test]
+    fn sync_test_two_threads() {
+        let (mut sender, mut receiver) = channel();
+
+        let sender_thread = thread::spawn(move || {
+            for i in 0..100 {
+                block_on(sender.send(i)).expect("receiver disconnected");
+            }
         });
 
-        assert_eq!(receiver.next().await.unwrap(), 1);
-        sender_task.abort();
-        assert_matches!(receiver.next().await, None);
-        assert_matches!(sender_task.await, Err(err) => assert!(err.is_cancelled()));
+        let receiver_thread = thread::spawn(move || {
+            for i in 0..100 {
+                let value = block_on(receiver.recv()).expect("sender disconnected");
+                assert_eq!(value, i);
+            }
+        });
+
+        sender_thread.join().expect("sender panicked");
+        receiver_thread.join().expect("receiver panicked");
+    }
+
+    #[test]
+    fn mixed_receives_two_threads() {
+        let (mut sender, mut receiver) = channel();
+
+        let sender_thread = thread::spawn(move || {
+            for i in 0..10_000 {
+                block_on(sender.send(i)).expect("receiver disconnected");
+            }
+        });
+
+        // Exercise each way the receiver can hand the sender's item back
+        let receiver_thread = thread::spawn(move || {
+            for i in 0..10_000 {
+                let value = match i % 3 {
+                    0 => block_on(receiver.recv()),
+                    1 => block_on(receiver.recv_with(|&value| value)),
+                    _ => {
+                        assert_eq!(block_on(receiver.peek()), Some(&i));
+                        block_on(receiver.recv())
+                    }
+                };
+
+                assert_eq!(value, Some(i));
+            }
+
+            assert_eq!(block_on(receiver.recv()), None);
+        });
+
+        sender_thread.join().expect("sender panicked");
+        receiver_thread.join().expect("receiver panicked");
     }
 
-    // TODO: test sender leak
+    #[test]
+    fn concurrent_drop_and_poll() {
+        use std::sync::{Arc, Barrier};
+
+        // Each item is a clone of this, so that a lost or doubly dropped item
+        // would show up in its reference count.
+        let token = Arc::new(());
+
+        for i in 0..2_000 {
+            let (mut sender, mut receiver) = channel();
+            let barrier = Arc::new(Barrier::new(2));
+
+            let sender_thread = thread::spawn({
+                let barrier = barrier.clone();
+                let item = token.clone();
+
+                move || {
+                    barrier.wait();
+
+                    match i % 2 {
+                        // Wait for the send to resolve one way or the other
+                        0 => Some(block_on(sender.send(item)).is_ok()),
+
+                        // Abandon the send after staging it
+                        _ => {
+                            let _ = sender.send(item).now_or_never();
+                            None
+                        }
+                    }
+                }
+            });
+
+            let receiver_thread = thread::spawn({
+                let barrier = barrier.clone();
+
+                move || {
+                    barrier.wait();
+                    receiver.recv().now_or_never().flatten()
+                }
+            });
+
+            let sent = sender_thread.join().expect("sender panicked");
+            let received = receiver_thread.join().expect("receiver panicked");
+
+            // A send that resolved succeeded if and only if the receiver got
+            // the item.
+            if let Some(sent) = sent {
+                assert_eq!(sent, received.is_some());
+            }
+
+            drop(received);
+            assert_eq!(Arc::strong_count(&token), 1);
+        }
+    }
+
+    #[test]
+    fn reconnect_after_disconnect() {
+        use std::{
+            pin::pin,
+            task::{Context, Poll, Waker},
+        };
+
+        let (mut sender, mut receiver) = channel();
+        let mut cx = Context::from_waker(Waker::noop());
+
+        // Leave an item peeked, then disconnect by reconnecting each half to
+        // a fresh partner that's immediately dropped.
+        {
+            let mut send = pin!(sender.send(1));
+            assert!(send.as_mut().poll(&mut cx).is_pending());
+            assert_eq!(receiver.poll_peek(&mut cx), Poll::Ready(Some(&1)));
+
+            let (mut other_sender, _) = channel();
+            reconnect(&mut other_sender, &mut receiver);
+
+            // The receiver's old channel was disconnected, so the pending send
+            // finishes; its item was taken by the peek.
+            assert_matches!(send.poll(&mut cx), Poll::Ready(Ok(())));
+        }
+
+        let (_, mut other_receiver) = channel();
+        reconnect(&mut sender, &mut other_receiver);
+        drop(other_receiver);
+
+        assert!(!sender.is_connected());
+        assert!(!receiver.is_connected());
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Disconnected));
+
+        reconnect(&mut sender, &mut receiver);
+        assert!(sender.is_connected());
+        assert!(receiver.is_connected());
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Empty));
+
+        let sender_thread = thread::spawn(move || {
+            for i in 0..3 {
+                block_on(sender.send(i)).unwrap();
+            }
+        });
+
+        let items: Vec<i32> = block_on(receiver.collect());
+        assert_eq!(items, [0, 1, 2]);
+        sender_thread.join().unwrap();
+    }
+
+    #[tokio::test]
+    async fn basic_sender_close() {
+        let (sender, mut receiver) = channel();
+
+        drop(sender);
+
+        let out: Option<i32> = receiver.recv().await;
+        assert_eq!(out, None);
+    }
+
+    #[tokio::test]
+    async fn basic_receiver_close() {
+        let (mut sender, receiver) = channel();
+
+        drop(receiver);
+
+        assert_matches!(sender.send(1).await, Err(SendError(1)));
+    }
+
+    #[tokio::test]
+    async fn sender_close_while_waiting() {
+        let (sender, mut receiver) = channel();
+
+        let sender_task = tokio::task::spawn(async move {
+            tokio::task::yield_now().await;
+            drop(sender);
+        });
+
+        let out: Option<i32> = receiver.recv().await;
+        assert_eq!(out, None);
+        sender_task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn receiver_close_while_waiting() {
+        let (mut sender, receiver) = channel();
+
+        let receiver_task = tokio::task::spawn(async move {
+            tokio::task::yield_now().await;
+            drop(receiver);
+        });
+
+        assert_matches!(sender.send(1).await, Err(SendError(1)));
+        receiver_task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn sender_cancels() {
+        let (mut sender, mut receiver) = channel();
+
+        let sender_task = tokio::task::spawn(async move {
+            sender.send(1).await.unwrap();
+            sender.send(2).await.unwrap();
+        });
+
+        assert_eq!(receiver.next().await.unwrap(), 1);
+        sender_task.abort();
+        assert_matches!(receiver.next().await, None);
+        assert_matches!(sender_task.await, Err(err) => assert!(err.is_cancelled()));
+    }
+
+    #[tokio::test]
+    async fn recv_with() {
+        let (mut sender, mut receiver) = channel();
+
+        let sender_task = tokio::task::spawn(async move {
+            sender.send(vec![1, 2, 3]).await.unwrap();
+            sender.send(vec![4, 5]).await.unwrap();
+        });
+
+        assert_eq!(receiver.recv_with(|v: &Vec<i32>| v.len()).await, Some(3));
+        assert_eq!(receiver.recv_with(|v| v.iter().sum::<i32>()).await, Some(9));
+        sender_task.await.unwrap();
+
+        assert_eq!(receiver.recv_with(|_| panic!("no item")).await, None::<()>);
+    }
+
+    #[tokio::test]
+    async fn recv_or_falls_back_on_disconnect() {
+        let (mut sender, mut receiver) = channel();
+
+        let sender_task = tokio::task::spawn(async move { sender.send(1).await });
+        assert_eq!(receiver.recv_or(0).await, 1);
+        sender_task.await.unwrap().unwrap();
+
+        assert_eq!(receiver.recv_or(0).await, 0);
+    }
+
+    #[tokio::test]
+    async fn recv_or_else_is_lazy() {
+        let (mut sender, mut receiver) = channel();
+
+        let sender_task = tokio::task::spawn(async move { sender.send(1).await });
+        assert_eq!(receiver.recv_or_else(|| panic!("sender is connected")).await, 1);
+        sender_task.await.unwrap().unwrap();
+
+        assert_eq!(receiver.recv_or_else(|| 0).await, 0);
+    }
+
+    #[tokio::test]
+    async fn partial_consumption_by_ref() {
+        let (mut sender, mut receiver) = channel();
+
+        let sender_task = tokio::task::spawn(async move {
+            for i in 0..5 {
+                sender.send(i).await.unwrap();
+            }
+        });
+
+        assert_eq!(receiver.next().await, Some(0));
+
+        let taken: Vec<i32> = (&mut receiver).take(2).collect().await;
+        assert_eq!(taken, [1, 2]);
+
+        let rest: Vec<i32> = receiver.collect().await;
+        assert_eq!(rest, [3, 4]);
+        sender_task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn into_future_returns_receiver() {
+        let (mut sender, receiver) = channel();
+
+        let recv_task = tokio::task::spawn(receiver.into_future());
+        sender.send(1).await.unwrap();
+        let (item, mut receiver) = recv_task.await.unwrap();
+        assert_eq!(item, Some(1));
+
+        let sender_task = tokio::task::spawn(async move { sender.send(2).await });
+        assert_eq!(receiver.recv().await, Some(2));
+        sender_task.await.unwrap().unwrap();
+
+        let (item, _receiver) = receiver.into_future().await;
+        assert_eq!(item, None);
+    }
+
+    #[test]
+    fn cancelled_into_future_keeps_item() {
+        let (mut sender, receiver) = channel();
+        let mut send = Box::pin(sender.send(1));
+
+        let mut recv = receiver.into_future();
+        assert!((&mut recv).now_or_never().is_none());
+        assert!(send.as_mut().now_or_never().is_none());
+
+        // Cancelling with `into_inner` leaves the staged item in place
+        let receiver = recv.into_inner().unwrap();
+        let recv = receiver.into_future();
+        assert!(send.as_mut().now_or_never().is_none());
+
+        // Cancelling by dropping returns the item to the sender
+        drop(recv);
+        assert_matches!(send.now_or_never(), Some(Err(SendError(1))));
+    }
+
+    #[tokio::test]
+    async fn fold_until_stops_early() {
+        let (mut sender, mut receiver) = channel();
+
+        let sender_task = tokio::task::spawn(async move {
+            for i in [1, 2, 3, 0, 10, 20] {
+                sender.send(i).await.unwrap();
+            }
+        });
+
+        let sum = receiver
+            .fold_until(0, |acc, item| match item {
+                0 => ControlFlow::Break(acc),
+                item => ControlFlow::Continue(acc + item),
+            })
+            .await;
+        assert_eq!(sum, 6);
+
+        // The receiver is still usable afterwards
+        assert!(receiver.is_connected());
+        assert_eq!(receiver.recv().await, Some(10));
+
+        let rest = receiver
+            .fold_until(Vec::new(), |mut acc, item| {
+                acc.push(item);
+                ControlFlow::Continue(acc)
+            })
+            .await;
+        assert_eq!(rest, [20]);
+        sender_task.await.unwrap();
+    }
+
+    #[test]
+    fn fold_until_yields_after_budget() {
+        use std::{
+            sync::Arc,
+            task::{Context, Poll, Waker},
+        };
+
+        let (sender, mut receiver) = crate::Builder::new().coop_budget(4).channel();
+
+        let sender_thread = thread::spawn(move || {
+            let mut sender = sender;
+            for i in 0..1000 {
+                block_on(sender.send(i)).unwrap();
+            }
+        });
+
+        let waker = Arc::new(CountingWaker::default());
+        let waker_handle = Waker::from(waker.clone());
+        let mut cx = Context::from_waker(&waker_handle);
+
+        let taken = std::cell::Cell::new(0);
+        let mut fold = receiver.fold_until(0, |count, _| {
+            taken.set(taken.get() + 1);
+            ControlFlow::Continue(count + 1)
+        });
+
+        let total = loop {
+            let poll = fold.poll_unpin(&mut cx);
+            assert!(taken.replace(0) <= 4, "took more items than the budget");
+
+            match poll {
+                Poll::Ready(total) => break total,
+                Poll::Pending => thread::yield_now(),
+            }
+        };
+
+        sender_thread.join().unwrap();
+        assert_eq!(total, 1000);
+    }
+
+    #[tokio::test]
+    async fn recv_with_panic_releases_sender() {
+        let (mut sender, mut receiver) = channel();
+
+        let sender_task = tokio::task::spawn(async move { sender.send(1).await });
+
+        let result = std::panic::AssertUnwindSafe(receiver.recv_with(|_: &i32| panic!("oops")))
+            .catch_unwind()
+            .await;
+        assert!(result.is_err());
+
+        // The item wasn't consumed, so the next receive gets it.
+        assert_eq!(receiver.recv().await, Some(1));
+        sender_task.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn send_or_drop() {
+        let (mut sender, mut receiver) = channel();
+
+        let receiver_task = tokio::task::spawn(async move {
+            assert_eq!(receiver.recv().await, Some(1));
+        });
+
+        assert!(sender.send_or_drop(1).await);
+        receiver_task.await.unwrap();
+        assert!(!sender.send_or_drop(2).await);
+    }
+
+    #[tokio::test]
+    async fn boxed_unsized() {
+        let (mut sender, mut receiver) = channel_boxed::<[i32]>();
+
+        let sender_task = tokio::task::spawn(async move {
+            sender.send(Box::new([1, 2, 3])).await.unwrap();
+            sender.send(vec![4, 5].into_boxed_slice()).await.unwrap();
+        });
+
+        assert_eq!(receiver.recv().await.as_deref(), Some(&[1, 2, 3][..]));
+        assert_eq!(receiver.recv().await.as_deref(), Some(&[4, 5][..]));
+        assert_eq!(receiver.recv().await, None);
+        sender_task.await.unwrap();
+    }
+
+    #[test]
+    fn reactor_driven_receive() {
+        use std::{pin::pin, sync::Arc, task::Waker};
+
+        let (mut sender, mut receiver) = channel();
+
+        let recv_waker = Arc::new(CountingWaker::default());
+        let send_waker = Arc::new(CountingWaker::default());
+
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Empty));
+        receiver.register_recv_waker(&Waker::noop().clone());
+        receiver.register_recv_waker(&Waker::from(recv_waker.clone()));
+
+        {
+            let mut send = pin!(sender.send(1));
+            assert!(send.as_mut().now_or_never().is_none());
+            assert_eq!(recv_waker.count(), 1);
+
+            assert_eq!(receiver.try_recv(), Ok(1));
+            assert_matches!(send.now_or_never(), Some(Ok(())));
+        }
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Empty));
+
+        // Registering repeatedly replaces the waker
+        sender.register_send_waker(&Waker::noop().clone());
+        sender.register_send_waker(&Waker::from(send_waker.clone()));
+        drop(receiver);
+        assert_eq!(send_waker.count(), 1);
+        assert!(!sender.is_connected());
+    }
+
+    #[test]
+    fn cancelled_recv_then_disconnect_wakes_sender() {
+        use std::{pin::pin, sync::Arc, task::Waker};
+
+        let (mut sender, mut receiver) = channel();
+        let recv_waker = Arc::new(CountingWaker::default());
+        let send_waker = Arc::new(CountingWaker::default());
+
+        // The receiver starts waiting, then its task is cancelled, which
+        // takes its waker
+        {
+            let mut recv = receiver.recv();
+            let waker = Waker::from(recv_waker.clone());
+            let mut cx = std::task::Context::from_waker(&waker);
+            assert!(recv.poll_unpin(&mut cx).is_pending());
+        }
+
+        let mut send = pin!(sender.send(1));
+        let waker = Waker::from(send_waker.clone());
+        let mut cx = std::task::Context::from_waker(&waker);
+        assert!(send.as_mut().poll(&mut cx).is_pending());
+        assert_eq!(recv_waker.count(), 0);
+
+        // Dropping the receiver (and with it, the shared state) must still
+        // wake the sender exactly once, and return its item intact
+        drop(receiver);
+        assert_eq!(send_waker.count(), 1);
+        assert_matches!(send.poll(&mut cx), std::task::Poll::Ready(Err(SendError(1))));
+        assert_eq!(recv_waker.count(), 0);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn concurrent_recv_cancellation_and_disconnect() {
+        for _ in 0..1000 {
+            let (mut sender, mut receiver) = channel();
+
+            let sender_task = tokio::task::spawn(async move { sender.send(1).await });
+
+            let receiver_task = tokio::task::spawn(async move {
+                // Give up on the receive after a single poll, then disconnect
+                let received = receiver.recv().now_or_never();
+                drop(receiver);
+                received
+            });
+
+            let sent = tokio::time::timeout(std::time::Duration::from_secs(5), sender_task)
+                .await
+                .expect("the sender was never woken")
+                .unwrap();
+
+            match receiver_task.await.unwrap() {
+                Some(Some(item)) => {
+                    assert_eq!(item, 1);
+                    assert_matches!(sent, Ok(()));
+                }
+                _ => assert_matches!(sent, Err(SendError(1))),
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "audit failed")]
+    fn audit_detects_early_release() {
+        let (mut sender, mut receiver) = channel();
+        let mut send = Box::pin(sender.send(1));
+        assert!(send.as_mut().now_or_never().is_none());
+        assert_eq!(receiver.peek().now_or_never(), Some(Some(&1)));
+
+        // Corrupt the state by releasing the sender behind the receiver's back
+        receiver
+            .inner
+            .lock()
+            .unwrap()
+            .item_held
+            .store(false, std::sync::atomic::Ordering::Relaxed);
+
+        let _ = receiver.try_recv();
+    }
+
+    #[test]
+    fn try_recv_disconnected() {
+        let (sender, mut receiver) = channel::<i32>();
+        drop(sender);
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Disconnected));
+    }
+
+    #[test]
+    fn try_recv_mapped() {
+        use std::{
+            pin::pin,
+            task::{Context, Poll, Waker},
+        };
+
+        #[derive(Debug, PartialEq)]
+        enum Error {
+            Idle,
+            Closed,
+        }
+
+        let (mut sender, mut receiver) = channel();
+        let mut cx = Context::from_waker(Waker::noop());
+
+        assert_eq!(
+            receiver.try_recv_mapped(|| Error::Idle, || Error::Closed),
+            Err(Error::Idle)
+        );
+
+        {
+            let mut send = pin!(sender.send(1));
+            assert!(send.as_mut().poll(&mut cx).is_pending());
+            assert_eq!(
+                receiver.try_recv_mapped(|| Error::Idle, || Error::Closed),
+                Ok(1)
+            );
+            assert_matches!(send.poll(&mut cx), Poll::Ready(Ok(())));
+        }
+
+        drop(sender);
+        assert_eq!(
+            receiver.try_recv_mapped(|| Error::Idle, || Error::Closed),
+            Err(Error::Closed)
+        );
+    }
+
+    #[test]
+    fn len_reflects_staged_item() {
+        use std::{
+            pin::pin,
+            task::{Context, Poll, Waker},
+        };
+
+        let (mut sender, mut receiver) = channel();
+        let mut cx = Context::from_waker(Waker::noop());
+
+        assert_eq!(sender.capacity(), 0);
+        assert_eq!(receiver.capacity(), 0);
+        assert!(receiver.is_empty());
+
+        let mut send = pin!(sender.send(1));
+        assert!(send.as_mut().poll(&mut cx).is_pending());
+        assert_eq!(receiver.len(), 1);
+
+        // A peeked item is still ready to be received
+        assert_eq!(receiver.poll_peek(&mut cx), Poll::Ready(Some(&1)));
+        assert_eq!(receiver.len(), 1);
+
+        assert_eq!(receiver.try_recv(), Ok(1));
+        assert_eq!(receiver.len(), 0);
+        assert_matches!(send.poll(&mut cx), Poll::Ready(Ok(())));
+        assert!(receiver.is_empty());
+    }
+
+    #[test]
+    fn spurious_polls_keep_waker_registered() {
+        use std::{
+            pin::pin,
+            sync::Arc,
+            task::{Context, Poll, Waker},
+        };
+
+        let (mut sender, mut receiver) = channel();
+
+        let first = Arc::new(CountingWaker::default());
+        let second = Arc::new(CountingWaker::default());
+        let first_waker = Waker::from(first.clone());
+        let second_waker = Waker::from(second.clone());
+
+        assert_eq!(
+            receiver.poll_next_unpin(&mut Context::from_waker(&first_waker)),
+            Poll::Pending
+        );
+
+        // Spurious polls, including with a different waker, before the sender
+        // does anything
+        for _ in 0..3 {
+            assert_eq!(
+                receiver.poll_next_unpin(&mut Context::from_waker(&first_waker)),
+                Poll::Pending
+            );
+        }
+
+        assert_eq!(
+            receiver.poll_next_unpin(&mut Context::from_waker(&second_waker)),
+            Poll::Pending
+        );
+
+        let mut send = pin!(sender.send(1));
+        assert!(send
+            .as_mut()
+            .poll(&mut Context::from_waker(Waker::noop()))
+            .is_pending());
+
+        // Only the most recently registered waker should have been woken
+        assert_eq!(first.count(), 0);
+        assert_eq!(second.count(), 1);
+
+        assert_eq!(
+            receiver.poll_next_unpin(&mut Context::from_waker(&second_waker)),
+            Poll::Ready(Some(1))
+        );
+
+        assert_matches!(
+            send.poll(&mut Context::from_waker(Waker::noop())),
+            Poll::Ready(Ok(()))
+        );
+    }
+
+    #[test]
+    fn spurious_polls_between_sender_actions() {
+        use std::{
+            pin::pin,
+            sync::Arc,
+            task::{Context, Poll, Waker},
+        };
+
+        let (mut sender, mut receiver) = channel();
+
+        let recv_waker = Arc::new(CountingWaker::default());
+        let recv_waker_handle = Waker::from(recv_waker.clone());
+        let mut recv_cx = Context::from_waker(&recv_waker_handle);
+        let mut send_cx = Context::from_waker(Waker::noop());
+
+        for i in 0..10 {
+            let woken_before = recv_waker.count();
+            assert_eq!(receiver.poll_next_unpin(&mut recv_cx), Poll::Pending);
+
+            let mut send = pin!(sender.send(i));
+            assert!(send.as_mut().poll(&mut send_cx).is_pending());
+
+            // Spuriously poll the sender again before the receiver acts
+            assert!(send.as_mut().poll(&mut send_cx).is_pending());
+
+            assert!(recv_waker.count() > woken_before);
+            assert_eq!(receiver.poll_next_unpin(&mut recv_cx), Poll::Ready(Some(i)));
+            assert_matches!(send.poll(&mut send_cx), Poll::Ready(Ok(())));
+        }
+    }
+
+    #[tokio::test]
+    async fn select_loop_receives_every_item() {
+        let (mut sender1, mut receiver1) = channel();
+        let (mut sender2, mut receiver2) = channel();
+
+        let send_task1 = tokio::task::spawn(async move {
+            for i in 0..100 {
+                sender1.send(i).await.unwrap();
+            }
+        });
+
+        let send_task2 = tokio::task::spawn(async move {
+            for i in 0..100 {
+                sender2.send(i).await.unwrap();
+            }
+        });
+
+        let mut received1 = Vec::new();
+        let mut received2 = Vec::new();
+        let mut yields = 0;
+
+        // Every branch is re-created and re-polled each time the task wakes,
+        // including when it was woken by one of the other branches.
+        loop {
+            tokio::select! {
+                biased;
+
+                () = tokio::task::yield_now() => yields += 1,
+                item = receiver1.recv() => match item {
+                    Some(item) => received1.push(item),
+                    None => break,
+                },
+                item = receiver2.recv() => match item {
+                    Some(item) => received2.push(item),
+                    None => break,
+                },
+                () = std::future::pending() => unreachable!(),
+            }
+
+            if received1.len() == 100 && received2.len() == 100 {
+                break;
+            }
+        }
+
+        assert!(yields > 0);
+        assert_eq!(received1, (0..100).collect::<Vec<_>>());
+        assert_eq!(received2, (0..100).collect::<Vec<_>>());
+
+        send_task1.await.unwrap();
+        send_task2.await.unwrap();
+    }
+
+    #[test]
+    fn cancelled_recv_wakes_sender_once() {
+        use std::{
+            pin::pin,
+            sync::Arc,
+            task::{Context, Poll, Waker},
+        };
+
+        let (mut sender, mut receiver) = channel();
+
+        let send_waker = Arc::new(CountingWaker::default());
+        let send_waker_handle = Waker::from(send_waker.clone());
+        let mut send_cx = Context::from_waker(&send_waker_handle);
+        let mut recv_cx = Context::from_waker(Waker::noop());
+
+        let mut send = pin!(sender.send(1));
+
+        // The receive is woken by the staged item, but dropped before it's
+        // polled again.
+        let mut recv = receiver.recv();
+        assert_eq!(recv.poll_unpin(&mut recv_cx), Poll::Pending);
+        assert!(send.as_mut().poll(&mut send_cx).is_pending());
+        drop(recv);
+
+        // Nothing was taken, so the sender wasn't woken, and is still
+        // waiting.
+        assert_eq!(send_waker.count(), 0);
+        assert!(send.as_mut().poll(&mut send_cx).is_pending());
+        assert_eq!(send_waker.count(), 0);
+
+        // The cancelled receive was committed in a single poll, so a
+        // panic while the item is swapped out is the only way to interrupt
+        // it. This still wakes the sender exactly once, without losing the
+        // item.
+        let panicked = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let recv = receiver.recv_with(|_: &i32| panic!("cancelled"));
+            pin!(recv).poll(&mut recv_cx)
+        }));
+        assert!(panicked.is_err());
+        assert_eq!(send_waker.count(), 1);
+        assert!(send.as_mut().poll(&mut send_cx).is_pending());
+
+        // The item is delivered exactly once, with exactly one more wake
+        let mut recv = receiver.recv();
+        assert_eq!(recv.poll_unpin(&mut recv_cx), Poll::Ready(Some(1)));
+        drop(recv);
+        assert_eq!(send_waker.count(), 2);
+        assert_matches!(send.poll(&mut send_cx), Poll::Ready(Ok(())));
+        assert_eq!(send_waker.count(), 2);
+    }
+
+    // `SendError` is `repr(transparent)`, so it must have the same layout as
+    // its payload.
+    const _: () = {
+        use std::mem::{align_of, size_of};
+
+        assert!(size_of::<SendError<u64>>() == size_of::<u64>());
+        assert!(align_of::<SendError<u64>>() == align_of::<u64>());
+        assert!(size_of::<SendError<[u8; 3]>>() == size_of::<[u8; 3]>());
+        assert!(size_of::<SendError<()>>() == 0);
+    };
+
+    #[test]
+    fn rendezvous_channel_allocations() {
+        use std::{
+            pin::pin,
+            task::{Context, Poll, Waker},
+        };
+
+        use crate::testing::allocations;
+
+        let mut cx = Context::from_waker(Waker::noop());
+
+        let before = allocations();
+        let (mut sender, mut receiver) = super::rendezvous_channel();
+        assert_eq!(allocations() - before, 1);
+
+        let before = allocations();
+        for i in 0..100 {
+            // Alternate between the sender and the receiver waiting first
+            if i % 2 == 1 {
+                assert!(receiver.poll_next_unpin(&mut cx).is_pending());
+            }
+
+            let mut send = pin!(sender.send(i));
+            assert!(send.as_mut().poll(&mut cx).is_pending());
+            assert_eq!(receiver.poll_next_unpin(&mut cx), Poll::Ready(Some(i)));
+            assert_matches!(send.poll(&mut cx), Poll::Ready(Ok(())));
+        }
+        assert_eq!(allocations() - before, 0);
+    }
+
+    #[test]
+    fn send_error_formatting() {
+        let err = SendError(5);
+        assert_eq!(err.to_string(), "tried to send on a disconnected channel");
+        assert_eq!(format!("{err:?}"), "SendError(5)");
+    }
+
+    #[test]
+    fn send_error_with_item_formatting() {
+        let err = SendError("hello");
+        assert_eq!(
+            err.with_item().to_string(),
+            "tried to send on a disconnected channel: hello"
+        );
+
+        // The error's own text is unaffected
+        assert_eq!(err.to_string(), "tried to send on a disconnected channel");
+        assert_eq!(err.into_inner(), "hello");
+    }
+
+    #[test]
+    fn send_after_leaked_send() {
+        let (mut sender, mut receiver) = channel();
+
+        // The leaked send's item is pinned in its (leaked) allocation, so its
+        // pointer stays in the slot until the next send clobbers it.
+        let mut send = Box::pin(sender.send(1));
+        assert!(send.as_mut().now_or_never().is_none());
+        std::mem::forget(send);
+
+        let mut send = Box::pin(sender.send(2));
+        assert!(send.as_mut().now_or_never().is_none());
+        assert_eq!(receiver.next().now_or_never(), Some(Some(2)));
+        assert_matches!(send.now_or_never(), Some(Ok(())));
+
+        drop(sender);
+        assert_eq!(receiver.next().now_or_never(), Some(None));
+    }
+
+    #[test]
+    fn send_after_leaked_completed_send() {
+        let (mut sender, mut receiver) = channel();
+
+        // The item is received, but the send is leaked before it can reclaim
+        // its pointer, leaving it behind in `taken_item`.
+        let mut send = Box::pin(sender.send(1));
+        assert!(send.as_mut().now_or_never().is_none());
+        assert_eq!(receiver.next().now_or_never(), Some(Some(1)));
+        std::mem::forget(send);
+
+        // The leaked send doesn't look like an item to the receiver
+        assert!(receiver.next().now_or_never().is_none());
+
+        let mut send = Box::pin(sender.send(2));
+        assert!(send.as_mut().now_or_never().is_none());
+        assert_eq!(receiver.next().now_or_never(), Some(Some(2)));
+        assert_matches!(send.now_or_never(), Some(Ok(())));
+    }
+
+    #[test]
+    fn send_after_leaked_peeked_send() {
+        let (mut sender, mut receiver) = channel();
+
+        // The receiver already owns a peeked item, so leaking its send
+        // doesn't lose it.
+        let mut send = Box::pin(sender.send(1));
+        assert!(send.as_mut().now_or_never().is_none());
+        assert_eq!(receiver.peek().now_or_never(), Some(Some(&1)));
+        std::mem::forget(send);
+
+        let mut send = Box::pin(sender.send(2));
+        assert!(send.as_mut().now_or_never().is_none());
+        assert_eq!(receiver.next().now_or_never(), Some(Some(1)));
+        assert!(send.as_mut().now_or_never().is_none());
+        assert_eq!(receiver.next().now_or_never(), Some(Some(2)));
+        assert_matches!(send.now_or_never(), Some(Ok(())));
+    }
+
+    #[test]
+    fn two_phase_receive() {
+        use std::{
+            sync::Arc,
+            task::{Context, Poll, Waker},
+        };
+
+        let (mut sender, mut receiver) = channel();
+        let send_waker = Arc::new(CountingWaker::default());
+        let send_waker_ref = Waker::from(send_waker.clone());
+        let mut send_cx = Context::from_waker(&send_waker_ref);
+        let mut recv_cx = Context::from_waker(Waker::noop());
+
+        assert!(receiver.poll_peek_ready(&mut recv_cx).is_pending());
+
+        let mut send = Box::pin(sender.send(1));
+        assert!(send.poll_unpin(&mut send_cx).is_pending());
+        assert_eq!(receiver.poll_peek_ready(&mut recv_cx), Poll::Ready(Some(())));
+
+        // The sender stays blocked until the item is taken
+        assert!(send.poll_unpin(&mut send_cx).is_pending());
+        let wakes = send_waker.count();
+        assert_eq!(receiver.take_staged(), Some(1));
+        assert_eq!(send_waker.count(), wakes + 1);
+        assert_matches!(send.poll_unpin(&mut send_cx), Poll::Ready(Ok(())));
+        drop(send);
+
+        assert_eq!(receiver.take_staged(), None);
+        drop(sender);
+        assert_eq!(receiver.poll_peek_ready(&mut recv_cx), Poll::Ready(None));
+    }
+
+    #[test]
+    fn sender_cannot_be_duplicated() {
+        let (sender, _receiver) = channel::<i32>();
+        assert_matches!(sender.duplicate(), Err(super::DuplicateError));
+    }
+
+    #[test]
+    fn flush_after_send() {
+        let (mut sender, mut receiver) = channel();
+
+        let mut send = Box::pin(sender.send(1));
+        assert!(send.as_mut().now_or_never().is_none());
+        assert_eq!(receiver.next().now_or_never(), Some(Some(1)));
+        assert_matches!(send.now_or_never(), Some(Ok(())));
+
+        assert_matches!(sender.flush().now_or_never(), Some(Ok(())));
+    }
+
+    #[tokio::test]
+    async fn flush_waits_for_slow_receiver() {
+        let (mut sender, mut receiver) = channel();
+
+        // Leak a staged send, so that its item is still in flight
+        let mut send = Box::pin(sender.send(1));
+        assert!(send.as_mut().now_or_never().is_none());
+        std::mem::forget(send);
+
+        let receiver_task = tokio::task::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            let item = receiver.next().await;
+            (item, receiver)
+        });
+
+        let start = std::time::Instant::now();
+        sender.flush().await.unwrap();
+        assert!(start.elapsed() >= std::time::Duration::from_millis(20));
+
+        let (item, receiver) = receiver_task.await.unwrap();
+        assert_eq!(item, Some(1));
+
+        drop(receiver);
+        assert_matches!(sender.flush().await, Err(SendError(())));
+    }
+
+    #[tokio::test]
+    async fn close_after_flush_delivers_staged_item() {
+        let (mut sender, mut receiver) = channel();
+
+        // Leak a staged send, so that its item is still in flight at shutdown
+        let mut send = Box::pin(sender.send(1));
+        assert!(send.as_mut().now_or_never().is_none());
+        std::mem::forget(send);
+
+        let mut close = sender.close_after_flush();
+        assert!((&mut close).now_or_never().is_none());
+        assert!(receiver.is_connected());
+
+        let close_task = tokio::task::spawn(close);
+        assert_eq!(receiver.recv().await, Some(1));
+        close_task.await.unwrap();
+
+        assert!(!receiver.is_connected());
+        assert_eq!(receiver.recv().await, None);
+    }
+
+    #[test]
+    fn flush_waits_for_peeked_item() {
+        let (mut sender, mut receiver) = channel();
+
+        let mut send = Box::pin(sender.send(1));
+        assert!(send.as_mut().now_or_never().is_none());
+        assert_eq!(receiver.peek().now_or_never(), Some(Some(&1)));
+        std::mem::forget(send);
+
+        let mut flush = sender.flush();
+        let mut cx = std::task::Context::from_waker(std::task::Waker::noop());
+        assert!(flush.poll_unpin(&mut cx).is_pending());
+
+        assert_eq!(receiver.next().now_or_never(), Some(Some(1)));
+        assert_matches!(flush.now_or_never(), Some(Ok(())));
+    }
+
+    #[test]
+    fn send_in_flight_toggles() {
+        let (mut sender, mut receiver) = channel();
+        assert!(!sender.is_send_in_flight());
+
+        // Leak staged sends, so that the sender can be queried while their
+        // items are in flight
+        let mut send = Box::pin(sender.send(1));
+        assert!(send.as_mut().now_or_never().is_none());
+        std::mem::forget(send);
+        assert!(sender.is_send_in_flight());
+
+        assert_eq!(receiver.next().now_or_never(), Some(Some(1)));
+        assert!(!sender.is_send_in_flight());
+
+        // A peeked item is still in flight until it's received
+        let mut send = Box::pin(sender.send(2));
+        assert!(send.as_mut().now_or_never().is_none());
+        std::mem::forget(send);
+        assert_eq!(receiver.peek().now_or_never(), Some(Some(&2)));
+        assert!(sender.is_send_in_flight());
+
+        assert_eq!(receiver.next().now_or_never(), Some(Some(2)));
+        assert!(!sender.is_send_in_flight());
+    }
+
+    #[test]
+    fn non_send_payload_on_one_thread() {
+        use std::{cell::RefCell, rc::Rc};
+
+        // Nothing about sending or receiving requires `T: Send`; only moving
+        // a half to another thread does.
+        let (mut sender, mut receiver) = channel();
+        let shared = Rc::new(RefCell::new(Vec::new()));
+
+        block_on(async {
+            let send_task = async {
+                for i in 0..3 {
+                    let item = Rc::clone(&shared);
+                    item.borrow_mut().push(i);
+                    sender.send(item).await.unwrap();
+                }
+            };
+
+            let recv_task = async {
+                let mut received = 0;
+                while let Some(item) = receiver.next().await {
+                    assert!(Rc::ptr_eq(&item, &shared));
+                    received += 1;
+
+                    if received == 3 {
+                        break;
+                    }
+                }
+            };
+
+            futures::future::join(send_task, recv_task).await;
+        });
+
+        assert_eq!(*shared.borrow(), [0, 1, 2]);
+        assert_eq!(Rc::strong_count(&shared), 1);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn generators_compose_with_spawned_tasks() {
+        use futures::{stream, Stream};
+
+        fn assert_spawnable_stream<S: Stream + Unpin + Send + 'static>(_: &S) {}
+
+        let (mut sender, receiver) = channel();
+        let (mut doubled_sender, doubled_receiver) = channel();
+        assert_spawnable_stream(&receiver);
+
+        // A generator feeding a sender, on its own task
+        let producer = tokio::task::spawn(async move {
+            for i in 0..10 {
+                sender.send(i).await.unwrap();
+            }
+        });
+
+        // A generator deriving a stream from a receiver, feeding another
+        // channel, on another task
+        let relay = tokio::task::spawn(async move {
+            let doubled = stream::unfold(receiver, |mut receiver| async move {
+                let item = receiver.next().await?;
+                Some((item * 2, receiver))
+            });
+
+            let mut doubled = std::pin::pin!(doubled);
+            while let Some(item) = doubled.next().await {
+                doubled_sender.send(item).await.unwrap();
+            }
+        });
+
+        let items: Vec<i32> = doubled_receiver.collect().await;
+        assert_eq!(items, Vec::from_iter((0..10).map(|i| i * 2)));
+
+        producer.await.unwrap();
+        relay.await.unwrap();
+    }
+
+    #[test]
+    fn prefetch_then_recv() {
+        let (mut sender, mut receiver) = channel();
+
+        let mut send = Box::pin(sender.send(1));
+        assert!(send.as_mut().now_or_never().is_none());
+
+        // Prefetching releases the sender right away
+        assert_eq!(receiver.prefetch().now_or_never(), Some(()));
+        assert_eq!(receiver.len(), 1);
+        assert_matches!(send.now_or_never(), Some(Ok(())));
+
+        // The sender moves on to its next item, which waits behind the
+        // buffered one
+        let mut send = Box::pin(sender.send(2));
+        assert!(send.as_mut().now_or_never().is_none());
+
+        assert_eq!(receiver.peek().now_or_never(), Some(Some(&1)));
+        assert_eq!(receiver.next().now_or_never(), Some(Some(1)));
+        assert!(send.as_mut().now_or_never().is_none());
+        assert_eq!(receiver.next().now_or_never(), Some(Some(2)));
+        assert_matches!(send.now_or_never(), Some(Ok(())));
+    }
+
+    #[test]
+    fn prefetch_releases_peeked_item() {
+        let (mut sender, mut receiver) = channel();
+
+        let mut send = Box::pin(sender.send(1));
+        assert!(send.as_mut().now_or_never().is_none());
+        assert_eq!(receiver.peek().now_or_never(), Some(Some(&1)));
+        assert!(send.as_mut().now_or_never().is_none());
+
+        assert_eq!(receiver.prefetch().now_or_never(), Some(()));
+        assert_matches!(send.now_or_never(), Some(Ok(())));
+        drop(sender);
+
+        assert_eq!(receiver.try_recv(), Ok(1));
+        assert_eq!(receiver.prefetch().now_or_never(), Some(()));
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Disconnected));
+    }
+
+    /// A small xorshift generator, so that the soak test's pauses and
+    /// cancellations vary without needing a dependency on `rand`.
+    struct XorShift(u64);
+
+    impl XorShift {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        /// True with a probability of 1 in `n`, which must be a power of 2
+        fn one_in(&mut self, n: u64) -> bool {
+            self.next() & (n - 1) == 0
+        }
+
+        async fn maybe_pause(&mut self) {
+            if self.one_in(4096) {
+                tokio::time::sleep(std::time::Duration::from_micros(100)).await;
+            } else if self.one_in(16) {
+                tokio::task::yield_now().await;
+            }
+        }
+    }
+
+    /// Mix `item` into a running checksum, such that both the values and
+    /// their order affect the result.
+    fn checksum(sum: u64, item: u64) -> u64 {
+        (sum ^ item).wrapping_mul(0x100_0000_01b3).rotate_left(5)
+    }
+
+    /// Run millions of handoffs across threads, with random pauses and
+    /// cancellations on both sides, to catch rare races in the protocol. The
+    /// number of items can be set with `HANDOFF_SOAK_ITEMS`.
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    #[ignore = "soak test; run explicitly with --ignored"]
+    async fn soak() {
+        use crate::SendOrCancel;
+
+        let count: u64 = std::env::var("HANDOFF_SOAK_ITEMS")
+            .ok()
+            .and_then(|count| count.parse().ok())
+            .unwrap_or(5_000_000);
+
+        let (mut sender, mut receiver) = channel();
+
+        let sender_task = tokio::task::spawn(async move {
+            let mut rng = XorShift(0x9e37_79b9_7f4a_7c15);
+            let mut sum = 0;
+            let mut cancelled = 0_u64;
+
+            for i in 0..count {
+                rng.maybe_pause().await;
+
+                if rng.one_in(64) {
+                    // Retry cancelled sends until the item is delivered, so
+                    // that the receiver still sees every item in order.
+                    loop {
+                        match sender.send_or(i, tokio::task::yield_now()).await {
+                            SendOrCancel::Sent => break,
+                            SendOrCancel::Cancelled(_) => cancelled += 1,
+                            SendOrCancel::Disconnected(_) => panic!("receiver disconnected"),
+                        }
+                    }
+                } else {
+                    sender.send(i).await.unwrap();
+                }
+
+                sum = checksum(sum, i);
+            }
+
+            (sum, cancelled)
+        });
+
+        let receiver_task = tokio::task::spawn(async move {
+            let mut rng = XorShift(0x2545_f491_4f6c_dd1d);
+            let mut sum = 0;
+            let mut received = 0_u64;
+
+            loop {
+                rng.maybe_pause().await;
+
+                let item = if rng.one_in(64) {
+                    match receiver.recv_or_future(tokio::task::yield_now()).await {
+                        futures::future::Either::Left(item) => item,
+                        futures::future::Either::Right(()) => continue,
+                    }
+                } else {
+                    receiver.recv().await
+                };
+
+                let Some(item) = item else { break };
+                assert_eq!(item, received, "items arrived out of order");
+
+                sum = checksum(sum, item);
+                received += 1;
+            }
+
+            (sum, received)
+        });
+
+        let (sent, received) = tokio::time::timeout(
+            std::time::Duration::from_secs(600),
+            futures::future::join(sender_task, receiver_task),
+        )
+        .await
+        .expect("the soak test deadlocked");
+
+        let (sent_sum, cancelled) = sent.unwrap();
+        let (received_sum, received) = received.unwrap();
+
+        assert_eq!(received, count);
+        assert_eq!(sent_sum, received_sum);
+        assert!(count < 1000 || cancelled > 0, "no sends were cancelled");
+    }
 
     // TODO: bench compare various channels
 }
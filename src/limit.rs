@@ -0,0 +1,178 @@
+/*!
+Sends gated by an external rate limiter.
+*/
+
+use std::{
+    fmt::{self, Debug, Formatter},
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_util::ready;
+use pin_project::pin_project;
+
+use crate::{SendError, SendFut, Sender};
+
+/// A rate limiter that can gate sends, for use with
+/// [`Sender::send_limited`].
+///
+/// This keeps the crate independent of any particular rate limiting
+/// implementation; a thin wrapper is enough to adapt one. Each call to
+/// [`until_ready`][RateLimiter::until_ready] should wait for, and consume,
+/// one unit of capacity.
+pub trait RateLimiter {
+    /// The future returned by [`until_ready`][RateLimiter::until_ready].
+    type Ready<'a>: Future<Output = ()>
+    where
+        Self: 'a;
+
+    /// Wait until the limiter permits another send.
+    fn until_ready(&self) -> Self::Ready<'_>;
+}
+
+impl<L: RateLimiter + ?Sized> RateLimiter for &L {
+    type Ready<'a>
+        = L::Ready<'a>
+    where
+        Self: 'a;
+
+    #[inline]
+    fn until_ready(&self) -> Self::Ready<'_> {
+        L::until_ready(self)
+    }
+}
+
+impl<T> Sender<T> {
+    /// Asynchronously send an item to the receiver, once `limiter` permits
+    /// it.
+    ///
+    /// This first waits for [`limiter.until_ready()`][RateLimiter::until_ready],
+    /// and only then stages the item, so the receiver never sees the item
+    /// before the limiter allows it. After that, this behaves exactly like
+    /// [`send`][Sender::send]: it waits for the receiver to take the item,
+    /// so a send is held back by whichever is slower, the limiter or the
+    /// consumer.
+    #[inline]
+    pub fn send_limited<'a, L>(&'a mut self, item: T, limiter: &'a L) -> SendLimitedFut<'a, T, L>
+    where
+        L: RateLimiter + ?Sized,
+    {
+        SendLimitedFut {
+            ready: Some(limiter.until_ready()),
+            send: self.send(item),
+        }
+    }
+}
+
+/// Future for the [`send_limited`][Sender::send_limited] method. See its
+/// documentation for details.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+#[pin_project]
+pub struct SendLimitedFut<'a, T, L: RateLimiter + ?Sized + 'a> {
+    // The limiter's readiness, until it resolves
+    #[pin]
+    ready: Option<L::Ready<'a>>,
+
+    #[pin]
+    send: SendFut<'a, T>,
+}
+
+impl<T, L: RateLimiter + ?Sized> Debug for SendLimitedFut<'_, T, L> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SendLimitedFut")
+            .field("limited", &self.ready.is_some())
+            .field("send", &self.send)
+            .finish()
+    }
+}
+
+impl<T, L: RateLimiter + ?Sized> Future for SendLimitedFut<'_, T, L> {
+    type Output = Result<(), SendError<T>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut this = self.project();
+
+        if let Some(ready) = this.ready.as_mut().as_pin_mut() {
+            ready!(ready.poll(cx));
+            this.ready.set(None);
+        }
+
+        this.send.poll(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, time::Duration};
+
+    use futures::{future::join, FutureExt, StreamExt};
+    use tokio::time::{sleep_until, Instant, Sleep};
+
+    use super::RateLimiter;
+    use crate::{channel, TryRecvError};
+
+    /// Permits one send per `period`.
+    struct MockLimiter {
+        period: Duration,
+        next: Cell<Instant>,
+    }
+
+    impl MockLimiter {
+        fn new(period: Duration) -> Self {
+            Self {
+                period,
+                next: Cell::new(Instant::now()),
+            }
+        }
+    }
+
+    impl RateLimiter for MockLimiter {
+        type Ready<'a> = Sleep;
+
+        fn until_ready(&self) -> Sleep {
+            let deadline = self.next.get().max(Instant::now());
+            self.next.set(deadline + self.period);
+            sleep_until(deadline)
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn sends_are_spaced_by_the_limiter() {
+        let (mut sender, receiver) = channel();
+        let limiter = MockLimiter::new(Duration::from_millis(100));
+        let start = Instant::now();
+
+        let send_task = async {
+            for i in 0..5 {
+                sender.send_limited(i, &limiter).await.unwrap();
+            }
+
+            // Disconnect, so that the receiver's collect can finish
+            drop(sender);
+        };
+
+        let recv_task = receiver.map(|i| (i, start.elapsed())).collect::<Vec<_>>();
+        let ((), received) = join(send_task, recv_task).await;
+
+        let expected: Vec<_> = (0..5)
+            .map(|i| (i, Duration::from_millis(100 * i as u64)))
+            .collect();
+        assert_eq!(received, expected);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn item_not_staged_until_ready() {
+        let (mut sender, mut receiver) = channel();
+        let limiter = MockLimiter::new(Duration::from_secs(1));
+        limiter.until_ready().await;
+
+        let mut send = Box::pin(sender.send_limited(1, &limiter));
+        assert!(send.as_mut().now_or_never().is_none());
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Empty));
+
+        let (sent, received) = join(send, receiver.recv()).await;
+        sent.unwrap();
+        assert_eq!(received, Some(1));
+    }
+}
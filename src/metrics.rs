@@ -0,0 +1,129 @@
+/*!
+Consumer throughput estimates for adaptive producers.
+*/
+
+use std::sync::atomic::{AtomicU64, Ordering::Relaxed};
+
+use crate::{timer, Sender};
+
+/// How much weight each new interval gets in the moving average. Higher
+/// values track changes in the consumer's pace faster, but are noisier.
+const SMOOTHING: f64 = 0.2;
+
+/// An exponential moving average of the intervals between completed sends.
+///
+/// This is only ever updated and read by the sender, so relaxed atomics are
+/// sufficient; they're only atomic because `Inner` is shared.
+#[derive(Debug)]
+pub(crate) struct RateEstimate {
+    // When the most recent send completed, as a timestamp from
+    // `timer::now_nanos`, or `u64::MAX` if no send has completed yet
+    last_taken: AtomicU64,
+
+    // The average interval in seconds, as `f64` bits, or NaN if fewer than
+    // two sends have completed
+    interval: AtomicU64,
+}
+
+impl RateEstimate {
+    pub(crate) fn new() -> Self {
+        Self {
+            last_taken: AtomicU64::new(u64::MAX),
+            interval: AtomicU64::new(f64::NAN.to_bits()),
+        }
+    }
+
+    /// Record that the receiver took an item.
+    pub(crate) fn record(&self) {
+        let now = timer::now_nanos();
+        let last = self.last_taken.swap(now, Relaxed);
+
+        if last == u64::MAX {
+            return;
+        }
+
+        let interval = now.saturating_sub(last) as f64 / 1e9;
+        let average = f64::from_bits(self.interval.load(Relaxed));
+
+        let average = match average.is_nan() {
+            true => interval,
+            false => SMOOTHING * interval + (1.0 - SMOOTHING) * average,
+        };
+
+        self.interval.store(average.to_bits(), Relaxed);
+    }
+
+    /// The estimated rate, in items per second.
+    fn rate(&self) -> Option<f64> {
+        let average = f64::from_bits(self.interval.load(Relaxed));
+
+        match average > 0.0 {
+            true => Some(average.recip()),
+            false => None,
+        }
+    }
+}
+
+impl<T> Sender<T> {
+    /// The estimated rate at which the receiver is taking items, in items
+    /// per second.
+    ///
+    /// This is an exponential moving average of the intervals between
+    /// completed sends, updated each time a send completes, so it reflects
+    /// how fast the consumer drains the channel while the producer keeps up
+    /// with it. A producer can use it to throttle itself or adjust its batch
+    /// sizes. It returns `None` until at least two sends have completed, or
+    /// once the receiver disconnects.
+    #[must_use]
+    pub fn estimated_rate(&self) -> Option<f64> {
+        self.inner.lock()?.rate.rate()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use futures::future::join;
+
+    use crate::channel;
+
+    #[tokio::test]
+    async fn estimate_converges_on_consumer_rate() {
+        let (mut sender, mut receiver) = channel();
+        assert_eq!(sender.estimated_rate(), None);
+
+        // The consumer takes an item every 10ms, so at most 100 per second
+        let consumer = tokio::task::spawn(async move {
+            while receiver.recv().await.is_some() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        });
+
+        for i in 0..40 {
+            sender.send(i).await.unwrap();
+        }
+
+        // Timer granularity only ever makes the consumer slower
+        let rate = sender.estimated_rate().unwrap();
+        assert!((50.0..=110.0).contains(&rate), "estimated {rate} items/sec");
+
+        drop(sender);
+        consumer.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn no_estimate_after_disconnect() {
+        let (mut sender, mut receiver) = channel();
+
+        for i in 0..2 {
+            let (sent, received) = join(sender.send(i), receiver.recv()).await;
+            sent.unwrap();
+            assert_eq!(received, Some(i));
+        }
+
+        assert!(sender.estimated_rate().is_some());
+        drop(receiver);
+        assert_eq!(sender.estimated_rate(), None);
+    }
+}
@@ -0,0 +1,1107 @@
+/*!
+A multi-producer, multi-consumer variant of the handoff channel.
+
+Both halves of this channel can be cloned. Each sent item is delivered to
+exactly one receiver, and each send still blocks until some receiver takes
+the item, so this is useful for distributing work among a pool of competing
+consumers with rendezvous backpressure.
+
+Unlike the single-producer channel, which uses a single lock-free slot, this
+channel pairs up parked senders and parked receivers through a pair of
+intrusive wait queues, both behind a single `Mutex` shared by every handle.
+Senders are served in the order they arrive; waiting receivers are notified
+in the order they started waiting.
+
+Each send and receive future owns its own queue node, pinned inside the
+future, which it links into the queue while it waits. A parked sender's item
+stays in its future until a receiver takes it, waiting never allocates, and a
+future that's cancelled unlinks itself in constant time. A [`Receiver`] that's
+polled directly as a [`Stream`] uses a node it allocates once, when it's
+created. Every operation still takes the same lock, so heavily contended
+channels serialize on it.
+
+The channel is disconnected once *all* senders or *all* receivers have been
+dropped.
+
+```
+# futures::executor::block_on(async move {
+use handoff::mpmc;
+use futures::future::join3;
+
+let (sender, receiver) = mpmc::channel();
+let sender2 = sender.clone();
+let receiver2 = receiver.clone();
+
+let send_task = async move {
+    sender.send(1).await.unwrap();
+    sender2.send(2).await.unwrap();
+};
+
+let recv_task = |mut receiver: mpmc::Receiver<i32>| async move {
+    receiver.recv().await
+};
+
+let ((), a, b) = join3(send_task, recv_task(receiver), recv_task(receiver2)).await;
+let mut received = [a.unwrap(), b.unwrap()];
+received.sort();
+assert_eq!(received, [1, 2]);
+# });
+```
+*/
+
+use std::{
+    cell::UnsafeCell,
+    fmt::{self, Debug, Formatter},
+    future::Future,
+    pin::Pin,
+    ptr::{self, NonNull},
+    sync::{
+        atomic::{
+            AtomicUsize,
+            Ordering::{AcqRel, Relaxed},
+        },
+        Arc, Mutex, MutexGuard,
+    },
+    task::{Context, Poll, Waker},
+};
+
+use futures_util::stream::Stream;
+use pin_project::{pin_project, pinned_drop};
+use pinned_aliasable::Aliasable;
+
+use crate::{SendError, UnsafeCellExt};
+
+/// Create an unbuffered, multi-producer, multi-consumer channel. See the
+/// [module documentation][self] for details.
+#[must_use]
+pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
+    let shared = Arc::new(Shared {
+        state: Mutex::new(State {
+            senders: WaitList::new(),
+            receivers: WaitList::new(),
+        }),
+        sender_count: AtomicUsize::new(1),
+        receiver_count: AtomicUsize::new(1),
+    });
+
+    let receiver = Receiver::new(shared.clone());
+
+    (Sender { shared }, receiver)
+}
+
+struct Shared<T> {
+    state: Mutex<State<T>>,
+
+    // The number of live senders and receivers. These are only ever
+    // decremented while the state lock is held, so that a waiter that checks
+    // them under the lock can't miss a disconnect.
+    sender_count: AtomicUsize,
+    receiver_count: AtomicUsize,
+}
+
+impl<T> Shared<T> {
+    #[inline]
+    fn lock(&self) -> MutexGuard<'_, State<T>> {
+        // None of the critical sections can panic while the state is
+        // inconsistent, so it's fine to ignore poisoning.
+        self.state.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Create a new sender, unless all the senders have disconnected.
+    fn try_new_sender(shared: &Arc<Self>) -> Option<Sender<T>> {
+        // Hold the lock so that we can't race with the last sender's drop
+        let _state = shared.lock();
+
+        if shared.sender_count.load(Relaxed) == 0 {
+            return None;
+        }
+
+        shared.sender_count.fetch_add(1, Relaxed);
+
+        Some(Sender {
+            shared: shared.clone(),
+        })
+    }
+
+    /// Create a new receiver, unless all the receivers have disconnected.
+    fn try_new_receiver(shared: &Arc<Self>) -> Option<Receiver<T>> {
+        // Hold the lock so that we can't race with the last receiver's drop
+        let _state = shared.lock();
+
+        if shared.receiver_count.load(Relaxed) == 0 {
+            return None;
+        }
+
+        shared.receiver_count.fetch_add(1, Relaxed);
+        Some(Receiver::new(shared.clone()))
+    }
+}
+
+struct State<T> {
+    // Senders blocked waiting for a receiver, each with its staged item. A
+    // sender's node is unlinked by a receiver when it takes the item; the
+    // sender detects this when it's next polled.
+    senders: WaitList<ParkedSender<T>>,
+
+    // Receivers blocked waiting for a sender. A receiver's node is unlinked
+    // when a sender notifies it.
+    receivers: WaitList<ParkedReceiver>,
+}
+
+impl<T> State<T> {
+    /// Wake the longest-waiting receiver, if any.
+    fn notify_receiver(&mut self) {
+        if let Some(receiver) = self.receivers.pop_front() {
+            if let Some(waker) = receiver.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+/// A node in one of the wait queues, owned by the operation that's waiting.
+///
+/// Everything in a node is only accessed while the state lock is held. Once
+/// a node has been linked, its owner must keep it pinned in place, and must
+/// take the state lock before dropping it, so that it's never freed while
+/// it's still in a queue, or while another handle is looking at it.
+struct Node<V> {
+    inner: UnsafeCell<NodeInner<V>>,
+}
+
+struct NodeInner<V> {
+    prev: Option<NonNull<Node<V>>>,
+    next: Option<NonNull<Node<V>>>,
+    linked: bool,
+    value: V,
+}
+
+// Safety: the links and the value are only accessed while the state lock is
+// held, so nodes can be shared and sent like the value itself.
+unsafe impl<V: Send> Send for Node<V> {}
+unsafe impl<V: Send> Sync for Node<V> {}
+
+impl<V> Node<V> {
+    #[inline]
+    fn new(value: V) -> Self {
+        Self {
+            inner: UnsafeCell::new(NodeInner {
+                prev: None,
+                next: None,
+                linked: false,
+                value,
+            }),
+        }
+    }
+
+    #[inline]
+    fn inner(&self) -> NonNull<NodeInner<V>> {
+        self.inner.get_non_null()
+    }
+}
+
+struct ParkedSender<T> {
+    // This is only `None` once a receiver took the item, or the send
+    // failed and returned it.
+    item: Option<T>,
+    waker: Option<Waker>,
+}
+
+type ParkedReceiver = Option<Waker>;
+
+/// An intrusive, doubly linked FIFO queue of [`Node`]s.
+struct WaitList<V> {
+    head: Option<NonNull<Node<V>>>,
+    tail: Option<NonNull<Node<V>>>,
+}
+
+// Safety: the list is only accessed through the state lock
+unsafe impl<V: Send> Send for WaitList<V> {}
+
+impl<V> WaitList<V> {
+    #[inline]
+    const fn new() -> Self {
+        Self {
+            head: None,
+            tail: None,
+        }
+    }
+
+    #[inline]
+    fn is_empty(&self) -> bool {
+        self.head.is_none()
+    }
+
+    /// Link `node` at the back of the queue. Does nothing if it's already
+    /// linked.
+    ///
+    /// # Safety
+    ///
+    /// `node` must follow the rules in the [`Node`] documentation, and must
+    /// never be linked into any other queue.
+    unsafe fn push_back(&mut self, node: &Node<V>) {
+        let ptr = NonNull::from(node);
+        let inner = unsafe { node.inner().as_mut() };
+
+        if inner.linked {
+            return;
+        }
+
+        inner.prev = self.tail;
+        inner.next = None;
+        inner.linked = true;
+
+        match self.tail {
+            Some(tail) => unsafe { tail.as_ref().inner().as_mut() }.next = Some(ptr),
+            None => self.head = Some(ptr),
+        }
+
+        self.tail = Some(ptr);
+    }
+
+    /// Unlink `node`, if it's linked. Returns true if it was.
+    ///
+    /// # Safety
+    ///
+    /// If `node` is linked, it must be linked into this queue.
+    unsafe fn remove(&mut self, node: &Node<V>) -> bool {
+        let inner = unsafe { node.inner().as_mut() };
+
+        if !inner.linked {
+            return false;
+        }
+
+        let prev = inner.prev.take();
+        let next = inner.next.take();
+        inner.linked = false;
+
+        match prev {
+            Some(prev) => unsafe { prev.as_ref().inner().as_mut() }.next = next,
+            None => self.head = next,
+        }
+
+        match next {
+            Some(next) => unsafe { next.as_ref().inner().as_mut() }.prev = prev,
+            None => self.tail = prev,
+        }
+
+        true
+    }
+
+    /// Unlink the node at the front of the queue, returning its value. The
+    /// node can't be freed while the lock is held, so the value can be used
+    /// until then.
+    fn pop_front(&mut self) -> Option<&mut V> {
+        let head = self.head?;
+
+        // Safety: linked nodes are valid until their owner unlinks them,
+        // which requires the lock we're holding
+        unsafe {
+            self.remove(head.as_ref());
+            Some(&mut head.as_ref().inner().as_mut().value)
+        }
+    }
+
+    /// Call `f` on the value of each linked node, front to back, leaving them
+    /// linked.
+    fn for_each(&mut self, mut f: impl FnMut(&mut V)) {
+        let mut cursor = self.head;
+
+        while let Some(node) = cursor {
+            // Safety: as in `pop_front`
+            let inner = unsafe { node.as_ref().inner().as_mut() };
+            f(&mut inner.value);
+            cursor = inner.next;
+        }
+    }
+}
+
+/// The sending half of a multi-producer, multi-consumer handoff channel.
+///
+/// This object is created by the [`channel`] function, and can be cloned to
+/// create additional producers. See the [module documentation][self] for
+/// details.
+pub struct Sender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Sender<T> {
+    /// Asynchronously send an item to one of the receivers.
+    ///
+    /// This method will asynchronously block until some receiver has received
+    /// the item. If all receivers disconnect, this will instead return a
+    /// [`SendError`] containing the item that failed to send.
+    #[inline]
+    pub fn send(&self, item: T) -> SendFut<'_, T> {
+        SendFut {
+            node: Aliasable::new(Node::new(ParkedSender {
+                item: Some(item),
+                waker: None,
+            })),
+            sender: self,
+            state: SendState::Unstaged,
+        }
+    }
+
+    /// Check if any receivers are still connected.
+    ///
+    /// Note that the receivers may disconnect at any time, so a `true` result
+    /// is only a hint. Once this returns `false`, though, it will never again
+    /// return `true`.
+    #[inline]
+    #[must_use]
+    pub fn is_connected(&self) -> bool {
+        self.shared.receiver_count.load(Relaxed) > 0
+    }
+}
+
+impl<T> Sender<T> {
+    /// Create a [`WeakSender`] for this channel, which doesn't count as a
+    /// sender for the purposes of disconnection.
+    #[inline]
+    #[must_use]
+    pub fn downgrade(&self) -> WeakSender<T> {
+        WeakSender {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+/// A weak handle to the sending half of a multi-producer, multi-consumer
+/// channel, created by [`Sender::downgrade`].
+///
+/// A weak sender doesn't keep the channel connected: once every (strong)
+/// [`Sender`] has been dropped, the receivers see a disconnect, even if weak
+/// senders still exist. A weak sender can be upgraded to a strong one as long
+/// as the channel is still connected, but never after the last strong sender
+/// is dropped, so a disconnected channel is never resurrected. This is useful
+/// for registries and caches that need to track a channel without affecting
+/// its lifetime.
+///
+/// The single-producer [`Sender`][crate::Sender] has no weak equivalent,
+/// since each half of that channel is unique.
+pub struct WeakSender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> WeakSender<T> {
+    /// Try to upgrade this into a strong [`Sender`]. Returns `None` if all
+    /// the strong senders have been dropped.
+    #[inline]
+    #[must_use]
+    pub fn upgrade(&self) -> Option<Sender<T>> {
+        Shared::try_new_sender(&self.shared)
+    }
+}
+
+impl<T> Clone for WeakSender<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T> Debug for WeakSender<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WeakSender")
+            .field("senders", &self.shared.sender_count.load(Relaxed))
+            .finish()
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        self.shared.sender_count.fetch_add(1, Relaxed);
+
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let mut state = self.shared.lock();
+
+        // If this was the last sender, all the waiting receivers need to be
+        // informed of the disconnect.
+        if self.shared.sender_count.fetch_sub(1, AcqRel) == 1 {
+            while !state.receivers.is_empty() {
+                state.notify_receiver();
+            }
+        }
+    }
+}
+
+impl<T> Debug for Sender<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sender")
+            .field("connected", &self.is_connected())
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SendState {
+    // We haven't been polled yet, so our node isn't linked
+    Unstaged,
+
+    // Our node is in the senders queue, unless a receiver took our item
+    Staged,
+
+    // We completed
+    Done,
+}
+
+/// Future for sending a single item through an mpmc [`Sender`], created by
+/// the [`send`][Sender::send] method. See its documentation for details.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+#[pin_project(PinnedDrop)]
+pub struct SendFut<'a, T> {
+    // Our node in the senders queue, which holds our item. Receivers access
+    // it through the queue while it's linked, so it can be aliased even when
+    // we have a reference to it; see the `Aliasable` docs for details.
+    #[pin]
+    node: Aliasable<Node<ParkedSender<T>>>,
+    sender: &'a Sender<T>,
+    state: SendState,
+}
+
+impl<T> Debug for SendFut<'_, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SendFut")
+            .field("sender", &self.sender)
+            .field(
+                "state",
+                &match self.state {
+                    SendState::Unstaged => "unstaged",
+                    SendState::Staged => "staged",
+                    SendState::Done => "done",
+                },
+            )
+            .finish()
+    }
+}
+
+impl<T> Future for SendFut<'_, T> {
+    type Output = Result<(), SendError<T>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let shared = &this.sender.shared;
+        let node = this.node.as_ref().get();
+        let mut state = shared.lock();
+        let connected = shared.receiver_count.load(Relaxed) > 0;
+
+        // Safety: we hold the lock, so nothing else is accessing our node.
+        // This reference isn't used across any queue operation.
+        let inner = unsafe { node.inner().as_mut() };
+
+        match *this.state {
+            SendState::Unstaged if !connected => {
+                *this.state = SendState::Done;
+                let item = inner.value.item.take().expect("an unstaged send has its item");
+                Poll::Ready(Err(SendError(item)))
+            }
+            SendState::Unstaged => {
+                inner.value.waker = Some(cx.waker().clone());
+
+                // Safety: our node is pinned, is only ever linked into this
+                // queue, and our drop takes the lock.
+                unsafe { state.senders.push_back(node) };
+                state.notify_receiver();
+
+                *this.state = SendState::Staged;
+                Poll::Pending
+            }
+            // A receiver unlinked our node, which means it took our item
+            SendState::Staged if !inner.linked => {
+                *this.state = SendState::Done;
+                Poll::Ready(Ok(()))
+            }
+            SendState::Staged if !connected => {
+                *this.state = SendState::Done;
+
+                // Safety: our node is only ever linked into this queue
+                unsafe { state.senders.remove(node) };
+                let item = unsafe { node.inner().as_mut() }.value.item.take();
+                Poll::Ready(Err(SendError(item.expect("a parked send has its item"))))
+            }
+            SendState::Staged => {
+                match &mut inner.value.waker {
+                    Some(waker) => waker.clone_from(cx.waker()),
+                    waker @ None => *waker = Some(cx.waker().clone()),
+                }
+
+                Poll::Pending
+            }
+            SendState::Done => panic!("Don't poll futures after they completed"),
+        }
+    }
+}
+
+#[pinned_drop]
+impl<T> PinnedDrop for SendFut<'_, T> {
+    fn drop(self: Pin<&mut Self>) {
+        let this = self.project();
+
+        if *this.state == SendState::Staged {
+            let mut state = this.sender.shared.lock();
+
+            // Unlink our node, if a receiver hasn't taken our item yet. The
+            // item is dropped with the node, after the lock is released.
+            //
+            // Safety: our node is only ever linked into this queue
+            unsafe { state.senders.remove(this.node.as_ref().get()) };
+        }
+    }
+}
+
+/// The receiving half of a multi-producer, multi-consumer handoff channel.
+///
+/// This object is created by the [`channel`] function, and can be cloned to
+/// create additional consumers; each item is delivered to exactly one
+/// receiver. Like the single-consumer [`Receiver`][crate::Receiver], it
+/// implements [`Stream`]. See the [module documentation][self] for details.
+pub struct Receiver<T> {
+    shared: Arc<Shared<T>>,
+
+    // Our node in the receivers queue while we're polled as a stream. A
+    // receiver can be moved between polls, so this is boxed, rather than
+    // pinned in place like the node in a `RecvFut`.
+    node: Pin<Box<Aliasable<Node<ParkedReceiver>>>>,
+}
+
+impl<T> Receiver<T> {
+    fn new(shared: Arc<Shared<T>>) -> Self {
+        Self {
+            shared,
+            node: Box::pin(Aliasable::new(Node::new(None))),
+        }
+    }
+
+    /// Attempt to receive the next item from any sender.
+    ///
+    /// This method will asynchronously block until a sender sends an item,
+    /// then return that item. Alternatively, if all senders disconnect, this
+    /// will return `None`.
+    #[inline]
+    pub fn recv(&mut self) -> RecvFut<'_, T> {
+        RecvFut {
+            node: Aliasable::new(Node::new(None)),
+            receiver: self,
+        }
+    }
+
+    /// Check if any senders are still connected.
+    ///
+    /// Note that the senders may disconnect at any time, so a `true` result
+    /// is only a hint. Once this returns `false`, though, it will never again
+    /// return `true`.
+    #[inline]
+    #[must_use]
+    pub fn is_connected(&self) -> bool {
+        self.shared.sender_count.load(Relaxed) > 0
+    }
+
+    /// The number of (strong) [`Sender`]s still connected to this channel.
+    ///
+    /// A consumer can use this to keep draining until every producer is
+    /// gone, even if there's briefly nothing to receive. Like
+    /// [`is_connected`][Receiver::is_connected], the result is only a hint,
+    /// since senders may be created or dropped at any time, but once it
+    /// reaches 0, it stays there.
+    #[inline]
+    #[must_use]
+    pub fn sender_count(&self) -> usize {
+        self.shared.sender_count.load(Relaxed)
+    }
+
+    /// Create a new sender that feeds this channel, for attaching producers
+    /// at runtime without needing access to an existing [`Sender`].
+    ///
+    /// Returns `None` if all the senders have already disconnected; a
+    /// disconnected channel can't be reconnected. This is only available for
+    /// multi-producer channels; the single-producer
+    /// [`Receiver`][crate::Receiver] has no equivalent.
+    #[inline]
+    #[must_use]
+    pub fn new_sender(&self) -> Option<Sender<T>> {
+        Shared::try_new_sender(&self.shared)
+    }
+
+    /// Create a [`WeakReceiver`] for this channel, which doesn't count as a
+    /// receiver for the purposes of disconnection.
+    #[inline]
+    #[must_use]
+    pub fn downgrade(&self) -> WeakReceiver<T> {
+        WeakReceiver {
+            shared: self.shared.clone(),
+        }
+    }
+
+    /// Receive an item, or else wait for one with `node`, which is either
+    /// our own node or the node of a `RecvFut` borrowing us.
+    fn poll_recv(
+        &self,
+        node: Pin<&Aliasable<Node<ParkedReceiver>>>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<T>> {
+        let node = node.get();
+        let mut state = self.shared.lock();
+
+        // A stream poll may have left our own node queued, where it would
+        // swallow a notification meant for a `RecvFut`. A receiver only ever
+        // waits with one node at a time, so make sure it's unlinked.
+        let own_node = self.node.as_ref().get();
+
+        if !ptr::eq(node, own_node) {
+            // Safety: receiver nodes are only ever linked into this queue
+            unsafe { state.receivers.remove(own_node) };
+        }
+
+        if let Some(sender) = state.senders.pop_front() {
+            let item = sender.item.take().expect("a parked send has its item");
+            let waker = sender.waker.take();
+
+            // We were the receiver that was notified (if any), so we're
+            // no longer waiting.
+            //
+            // Safety: receiver nodes are only ever linked into this queue
+            unsafe { state.receivers.remove(node) };
+
+            // If there are still more items, make sure someone's around to
+            // take them.
+            if !state.senders.is_empty() {
+                state.notify_receiver();
+            }
+
+            drop(state);
+
+            if let Some(waker) = waker {
+                waker.wake();
+            }
+
+            return Poll::Ready(Some(item));
+        }
+
+        if self.shared.sender_count.load(Relaxed) == 0 {
+            return Poll::Ready(None);
+        }
+
+        // Safety: we hold the lock, so nothing else is accessing the node
+        match &mut unsafe { node.inner().as_mut() }.value {
+            Some(waker) => waker.clone_from(cx.waker()),
+            waker @ None => *waker = Some(cx.waker().clone()),
+        }
+
+        // Safety: the node is pinned, is only ever linked into this queue,
+        // and its owner calls `cancel_wait` before dropping it.
+        unsafe { state.receivers.push_back(node) };
+
+        Poll::Pending
+    }
+
+    /// Stop waiting with `node`. If we were already notified about an item,
+    /// but won't receive it, pass the notification along to the next
+    /// receiver so that the item isn't stranded.
+    fn cancel_wait(&self, node: Pin<&Aliasable<Node<ParkedReceiver>>>) {
+        let mut state = self.shared.lock();
+
+        // Safety: receiver nodes are only ever linked into this queue
+        if !unsafe { state.receivers.remove(node.get()) } && !state.senders.is_empty() {
+            state.notify_receiver();
+        }
+    }
+}
+
+/// A weak handle to the receiving half of a multi-producer, multi-consumer
+/// channel, created by [`Receiver::downgrade`].
+///
+/// Like a [`WeakSender`], a weak receiver doesn't keep the channel connected,
+/// and can only be upgraded while at least one strong [`Receiver`] still
+/// exists.
+pub struct WeakReceiver<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> WeakReceiver<T> {
+    /// Try to upgrade this into a strong [`Receiver`]. Returns `None` if all
+    /// the strong receivers have been dropped.
+    #[inline]
+    #[must_use]
+    pub fn upgrade(&self) -> Option<Receiver<T>> {
+        Shared::try_new_receiver(&self.shared)
+    }
+}
+
+impl<T> Clone for WeakReceiver<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T> Debug for WeakReceiver<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WeakReceiver")
+            .field("receivers", &self.shared.receiver_count.load(Relaxed))
+            .finish()
+    }
+}
+
+impl<T> Clone for Receiver<T> {
+    fn clone(&self) -> Self {
+        self.shared.receiver_count.fetch_add(1, Relaxed);
+        Self::new(self.shared.clone())
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        self.cancel_wait(self.node.as_ref());
+
+        let mut state = self.shared.lock();
+
+        // If this was the last receiver, all the waiting senders need to be
+        // informed of the disconnect, so they can reclaim their items.
+        if self.shared.receiver_count.fetch_sub(1, AcqRel) == 1 {
+            state.senders.for_each(|sender| {
+                if let Some(waker) = &sender.waker {
+                    waker.wake_by_ref();
+                }
+            });
+        }
+    }
+}
+
+impl<T> Debug for Receiver<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Receiver")
+            .field("connected", &self.is_connected())
+            .finish()
+    }
+}
+
+impl<T> Stream for Receiver<T> {
+    type Item = T;
+
+    #[inline]
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        self.poll_recv(self.node.as_ref(), cx)
+    }
+}
+
+/// Future type for receiving a single item from an mpmc [`Receiver`].
+/// Created by the [`recv`][Receiver::recv] method; see its documentation for
+/// details.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+#[pin_project(PinnedDrop)]
+pub struct RecvFut<'a, T> {
+    // Our node in the receivers queue, aliased by senders while it's linked
+    #[pin]
+    node: Aliasable<Node<ParkedReceiver>>,
+    receiver: &'a mut Receiver<T>,
+}
+
+impl<T> Debug for RecvFut<'_, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Recv")
+            .field("receiver", &self.receiver)
+            .finish()
+    }
+}
+
+impl<T> Future for RecvFut<'_, T> {
+    type Output = Option<T>;
+
+    #[inline]
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        this.receiver.poll_recv(this.node.as_ref(), cx)
+    }
+}
+
+#[pinned_drop]
+impl<T> PinnedDrop for RecvFut<'_, T> {
+    #[inline]
+    fn drop(self: Pin<&mut Self>) {
+        let this = self.project();
+        this.receiver.cancel_wait(this.node.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashSet,
+        future::Future,
+        pin::pin,
+        sync::Arc,
+        task::{Context, Poll, Waker},
+    };
+
+    use cool_asserts::assert_matches;
+    use futures::{future::join_all, FutureExt, StreamExt};
+
+    use crate::{
+        testing::{allocations, CountingWaker},
+        SendError,
+    };
+
+    use super::channel;
+
+    #[tokio::test]
+    async fn single_pair() {
+        let (sender, receiver) = channel();
+
+        let sender_task = tokio::task::spawn(async move {
+            for i in 0..10 {
+                sender.send(i).await.unwrap();
+            }
+        });
+
+        let data: Vec<i32> = receiver.collect().await;
+        sender_task.await.unwrap();
+        assert_eq!(data, (0..10).collect::<Vec<_>>());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn exactly_once_delivery() {
+        let (sender, receiver) = channel();
+
+        let senders: Vec<_> = (0..4)
+            .map(|n| {
+                let sender = sender.clone();
+                tokio::task::spawn(async move {
+                    for i in 0..250 {
+                        sender.send(n * 1000 + i).await.unwrap();
+                    }
+                })
+            })
+            .collect();
+        drop(sender);
+
+        let receivers: Vec<_> = (0..4)
+            .map(|_| {
+                let receiver = receiver.clone();
+                tokio::task::spawn(receiver.collect::<Vec<i32>>())
+            })
+            .collect();
+        drop(receiver);
+
+        for sender in senders {
+            sender.await.unwrap();
+        }
+
+        let mut seen = HashSet::new();
+        for items in join_all(receivers).await {
+            for item in items.unwrap() {
+                assert!(seen.insert(item), "{item} was delivered twice");
+            }
+        }
+
+        assert_eq!(seen.len(), 1000);
+    }
+
+    #[tokio::test]
+    async fn disconnect_requires_all_receivers() {
+        let (sender, receiver) = channel();
+        let receiver2 = receiver.clone();
+
+        drop(receiver);
+        assert!(sender.is_connected());
+
+        let receiver_task = tokio::task::spawn(async move {
+            tokio::task::yield_now().await;
+            drop(receiver2);
+        });
+
+        assert_matches!(sender.send(1).await, Err(SendError(1)));
+        assert!(!sender.is_connected());
+        receiver_task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn disconnect_requires_all_senders() {
+        let (sender, mut receiver) = channel::<i32>();
+        let sender2 = sender.clone();
+
+        drop(sender);
+        assert!(receiver.is_connected());
+
+        let sender_task = tokio::task::spawn(async move {
+            tokio::task::yield_now().await;
+            drop(sender2);
+        });
+
+        assert_eq!(receiver.recv().await, None);
+        sender_task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn new_sender_from_receiver() {
+        let (sender, mut receiver) = channel();
+
+        let extra = receiver.new_sender().unwrap();
+        drop(sender);
+        assert!(receiver.is_connected());
+
+        let sender_task = tokio::task::spawn(async move { extra.send(1).await });
+        assert_eq!(receiver.recv().await, Some(1));
+        sender_task.await.unwrap().unwrap();
+
+        // Once every sender is gone, the channel stays disconnected
+        assert_eq!(receiver.recv().await, None);
+        assert!(receiver.new_sender().is_none());
+    }
+
+    #[tokio::test]
+    async fn weak_handles_never_resurrect() {
+        let (sender, mut receiver) = channel();
+        let weak_sender = sender.downgrade();
+        let weak_receiver = receiver.downgrade();
+
+        // Upgrading works while the channel is connected
+        let upgraded = weak_sender.upgrade().unwrap();
+        drop(sender);
+        assert!(receiver.is_connected());
+
+        let sender_task = tokio::task::spawn(async move { upgraded.send(1).await });
+        let mut upgraded_receiver = weak_receiver.upgrade().unwrap();
+        assert_eq!(upgraded_receiver.recv().await, Some(1));
+        sender_task.await.unwrap().unwrap();
+
+        // Weak senders don't keep the channel connected
+        assert_eq!(receiver.recv().await, None);
+        assert!(weak_sender.upgrade().is_none());
+        assert!(weak_sender.clone().upgrade().is_none());
+
+        drop(receiver);
+        drop(upgraded_receiver);
+        assert!(weak_receiver.upgrade().is_none());
+    }
+
+    #[test]
+    fn sender_count_tracks_senders() {
+        let (sender, receiver) = channel::<i32>();
+        assert_eq!(receiver.sender_count(), 1);
+
+        let clone = sender.clone();
+        let attached = receiver.new_sender().unwrap();
+        assert_eq!(receiver.sender_count(), 3);
+
+        // Weak senders don't count until they're upgraded
+        let weak = sender.downgrade();
+        assert_eq!(receiver.sender_count(), 3);
+        let upgraded = weak.upgrade().unwrap();
+        assert_eq!(receiver.clone().sender_count(), 4);
+
+        drop((sender, clone, attached));
+        assert_eq!(receiver.sender_count(), 1);
+        assert!(receiver.is_connected());
+
+        drop(upgraded);
+        assert_eq!(receiver.sender_count(), 0);
+        assert!(weak.upgrade().is_none());
+        assert_eq!(receiver.sender_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn cancelled_receiver_passes_notification_on() {
+        let (sender, mut receiver) = channel();
+        let mut receiver2 = receiver.clone();
+
+        // The first receiver starts waiting, followed by the second
+        let mut first = Box::pin(receiver.recv());
+        assert!(first.as_mut().now_or_never().is_none());
+
+        let second = tokio::task::spawn(async move { receiver2.recv().await });
+        tokio::task::yield_now().await;
+
+        // The sender notifies the first receiver, which gives up without
+        // receiving. The notification must be passed to the second.
+        let sender_task = tokio::task::spawn(async move { sender.send(1).await });
+        tokio::task::yield_now().await;
+        drop(first);
+
+        assert_eq!(second.await.unwrap(), Some(1));
+        sender_task.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn cancelled_send_reclaims_item() {
+        let (sender, mut receiver) = channel();
+
+        let mut send = Box::pin(sender.send(1));
+        assert!(send.as_mut().now_or_never().is_none());
+        drop(send);
+
+        drop(sender);
+        assert_eq!(receiver.recv().await, None);
+    }
+
+    #[test]
+    fn cancelled_send_unlinks_from_middle_of_queue() {
+        let (sender, mut receiver) = channel();
+        let mut cx = Context::from_waker(Waker::noop());
+
+        let mut sends: Vec<_> = (1..=3).map(|i| Box::pin(sender.send(i))).collect();
+
+        // Each send's node lives in the send future, so parking doesn't
+        // allocate
+        let before = allocations();
+
+        for send in &mut sends {
+            assert!(send.as_mut().poll(&mut cx).is_pending());
+        }
+
+        assert_eq!(allocations() - before, 0);
+
+        // Cancelling the second send leaves the others in order
+        drop(sends.remove(1));
+
+        assert_eq!(receiver.recv().now_or_never(), Some(Some(1)));
+        assert_eq!(receiver.recv().now_or_never(), Some(Some(3)));
+        assert!(receiver.recv().now_or_never().is_none());
+
+        for mut send in sends {
+            assert_matches!(send.as_mut().poll(&mut cx), Poll::Ready(Ok(())));
+        }
+    }
+
+    #[test]
+    fn stream_wait_doesnt_swallow_recv_notification() {
+        let (sender, mut receiver) = channel();
+        let waker = Arc::new(CountingWaker::default());
+
+        // Polling as a stream leaves the receiver's own node queued
+        assert!(receiver
+            .poll_next_unpin(&mut Context::from_waker(Waker::noop()))
+            .is_pending());
+
+        let mut recv = pin!(receiver.recv());
+        let recv_waker = Waker::from(waker.clone());
+        assert!(recv
+            .as_mut()
+            .poll(&mut Context::from_waker(&recv_waker))
+            .is_pending());
+
+        // The notification goes to the future that's actually waiting
+        let mut send = pin!(sender.send(1));
+        assert!(send
+            .as_mut()
+            .poll(&mut Context::from_waker(Waker::noop()))
+            .is_pending());
+        assert_eq!(waker.count(), 1);
+
+        assert_eq!(
+            recv.poll(&mut Context::from_waker(&recv_waker)),
+            Poll::Ready(Some(1))
+        );
+    }
+}
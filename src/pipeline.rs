@@ -0,0 +1,342 @@
+/*!
+Pipelined sends, which return once an item is staged instead of once it's
+taken.
+*/
+
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::atomic::Ordering::Relaxed,
+    task::{Context, Poll},
+};
+
+use futures_util::ready;
+use pin_project::pin_project;
+use twinsies::Joint;
+
+use crate::{Inner, SendError, SendFut, Sender};
+
+impl<T> Sender<T> {
+    /// Borrow this sender as a two-stage [`SendPipeline`], which lets a
+    /// producer prepare its next item while the previous one is still
+    /// waiting for the receiver.
+    ///
+    /// A [`send`][SendPipeline::send] through the pipeline resolves as soon
+    /// as its item is staged, returning a [`SendTicket`] for it. The item is
+    /// handed off while the producer goes on to build the next one; the next
+    /// send first waits for the staged item to be taken, since the channel
+    /// only has a single slot. So at most two items are outstanding at a
+    /// time: one staged, and one ready to stage. Items still arrive in the
+    /// order they were sent.
+    ///
+    /// ```
+    /// # futures::executor::block_on(async move {
+    /// use std::pin::pin;
+    /// use futures::{future::join, StreamExt};
+    ///
+    /// let (mut sender, receiver) = handoff::channel();
+    ///
+    /// let send_task = async move {
+    ///     let mut pipeline = pin!(sender.pipeline());
+    ///
+    ///     for i in 0..5 {
+    ///         // While this item is being built, the previous one is staged
+    ///         let item = i * 10;
+    ///         pipeline.as_mut().send(item).await.unwrap();
+    ///     }
+    ///
+    ///     // Wait for the last item to be taken
+    ///     pipeline.flush().await.unwrap();
+    /// };
+    ///
+    /// let ((), received) = join(send_task, receiver.collect::<Vec<_>>()).await;
+    /// assert_eq!(received, [0, 10, 20, 30, 40]);
+    /// # });
+    /// ```
+    #[inline]
+    pub fn pipeline(&mut self) -> SendPipeline<'_, T> {
+        SendPipeline {
+            inner: &self.inner,
+            staged: None,
+            staged_seq: 0,
+            confirmed: None,
+        }
+    }
+}
+
+/// A sender that stages items without waiting for them to be taken, created
+/// by the [`Sender::pipeline`] method. See its documentation for details.
+///
+/// Dropping the pipeline cancels its staged send, if any, exactly like
+/// dropping a [`SendFut`]: if the receiver hasn't taken the item yet, it's
+/// withdrawn and dropped. Use [`flush`][SendPipeline::flush] first to make
+/// sure the last item was delivered.
+#[must_use = "a pipeline does nothing unless items are sent through it"]
+#[pin_project]
+#[derive(Debug)]
+pub struct SendPipeline<'a, T> {
+    inner: &'a Joint<Inner<T>>,
+
+    // The send whose item is staged but not yet known to be taken, if any
+    #[pin]
+    staged: Option<SendFut<'a, T>>,
+
+    // The sequence number of the staged item
+    staged_seq: u64,
+
+    // The sequence number of the most recent item known to be taken
+    confirmed: Option<u64>,
+}
+
+impl<'a, T> SendPipeline<'a, T> {
+    /// Stage an item, once the previously staged item (if any) is taken.
+    ///
+    /// The returned future resolves as soon as the item is staged, rather
+    /// than when the receiver takes it. If the receiver disconnects, this
+    /// returns a [`SendError`] containing the item; the previously staged
+    /// item, if the receiver never took it, is dropped.
+    #[inline]
+    pub fn send(self: Pin<&mut Self>, item: T) -> PipelineSendFut<'_, 'a, T> {
+        PipelineSendFut {
+            pipeline: self,
+            item: Some(item),
+        }
+    }
+
+    /// Wait for the currently staged item, if any, to be taken. If the
+    /// receiver disconnects before taking it, the item is returned in a
+    /// [`SendError`].
+    #[inline]
+    pub fn flush(self: Pin<&mut Self>) -> PipelineFlushFut<'_, 'a, T> {
+        PipelineFlushFut { pipeline: self }
+    }
+
+    /// Check if the receiver has taken the item for `ticket`. This doesn't
+    /// wait, and only reads the channel's state, so it's useful for checking
+    /// on a staged item without waiting for it.
+    ///
+    /// After the receiver disconnects, this only reports items the pipeline
+    /// already confirmed were taken.
+    #[must_use]
+    pub fn is_taken(&self, ticket: &SendTicket) -> bool {
+        match self.inner.lock() {
+            Some(lock) => lock.taken_count.load(Relaxed) > ticket.seq,
+            None => self.confirmed.is_some_and(|seq| seq >= ticket.seq),
+        }
+    }
+
+    /// Check if the pipeline has a staged item that isn't known to be taken
+    /// yet.
+    #[inline]
+    #[must_use]
+    pub fn has_staged(&self) -> bool {
+        self.staged.is_some()
+    }
+
+    /// Drive the staged send, if any, to completion.
+    fn poll_staged(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), SendError<T>>> {
+        let mut this = self.project();
+
+        let Some(send) = this.staged.as_mut().as_pin_mut() else { return Poll::Ready(Ok(())) };
+        let result = ready!(send.poll(cx));
+        this.staged.set(None);
+
+        if result.is_ok() {
+            *this.confirmed = Some(*this.staged_seq);
+        }
+
+        Poll::Ready(result)
+    }
+}
+
+/// Identifies an item sent through a [`SendPipeline`]; see
+/// [`SendPipeline::is_taken`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SendTicket {
+    seq: u64,
+}
+
+impl SendTicket {
+    /// The sequence number of the item among all the items the receiver has
+    /// taken from this channel, starting at 0. See [`Ack::seq`][crate::Ack::seq]
+    /// for details.
+    #[inline]
+    #[must_use]
+    pub fn seq(&self) -> u64 {
+        self.seq
+    }
+}
+
+/// Future for the [`send`][SendPipeline::send] method. See its documentation
+/// for details.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+#[derive(Debug)]
+pub struct PipelineSendFut<'p, 'a, T> {
+    pipeline: Pin<&'p mut SendPipeline<'a, T>>,
+
+    // The item to stage; `None` once the future completes
+    item: Option<T>,
+}
+
+// The item is never pinned; it's moved into the pipeline's send future.
+impl<T> Unpin for PipelineSendFut<'_, '_, T> {}
+
+impl<T> Future for PipelineSendFut<'_, '_, T> {
+    type Output = Result<SendTicket, SendError<T>>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+
+        // Only one item can be staged at a time, so wait for the previous
+        // one to be taken.
+        let previous = ready!(this.pipeline.as_mut().poll_staged(cx));
+
+        let item = this
+            .item
+            .take()
+            .expect("can't poll a PipelineSendFut after it completes");
+
+        if previous.is_err() {
+            return Poll::Ready(Err(SendError(item)));
+        }
+
+        let mut pipeline = this.pipeline.as_mut().project();
+
+        // Since only one item is staged at a time, no other item can be taken
+        // between now and when ours is, so the current count is our item's
+        // sequence number; see `SendAckedFut`.
+        let Some(seq) = pipeline.inner.lock().map(|lock| lock.taken_count.load(Relaxed)) else {
+            return Poll::Ready(Err(SendError(item)));
+        };
+
+        *pipeline.staged_seq = seq;
+        pipeline.staged.set(Some(SendFut::new(pipeline.inner, item)));
+
+        // Poll the new send once, which stages its item. It's only driven
+        // further by the next send or flush.
+        match this.pipeline.as_mut().poll_staged(cx) {
+            Poll::Ready(Err(err)) => Poll::Ready(Err(err)),
+            Poll::Ready(Ok(())) | Poll::Pending => Poll::Ready(Ok(SendTicket { seq })),
+        }
+    }
+}
+
+/// Future for the [`flush`][SendPipeline::flush] method. See its
+/// documentation for details.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+#[derive(Debug)]
+pub struct PipelineFlushFut<'p, 'a, T> {
+    pipeline: Pin<&'p mut SendPipeline<'a, T>>,
+}
+
+impl<T> Future for PipelineFlushFut<'_, '_, T> {
+    type Output = Result<(), SendError<T>>;
+
+    #[inline]
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.pipeline.as_mut().poll_staged(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        pin::pin,
+        task::{Context, Poll, Waker},
+    };
+
+    use cool_asserts::assert_matches;
+    use futures::{future::join, FutureExt, StreamExt};
+
+    use crate::{channel, SendError, TryRecvError};
+
+    #[tokio::test]
+    async fn items_arrive_in_order() {
+        let (mut sender, receiver) = channel();
+
+        let sender_task = tokio::task::spawn(async move {
+            let mut pipeline = pin!(sender.pipeline());
+            let mut tickets = Vec::new();
+
+            for i in 0..100 {
+                tickets.push(pipeline.as_mut().send(i).await.unwrap());
+            }
+
+            pipeline.as_mut().flush().await.unwrap();
+            assert!(tickets.iter().all(|ticket| pipeline.is_taken(ticket)));
+            tickets.iter().map(|ticket| ticket.seq()).collect::<Vec<_>>()
+        });
+
+        let received: Vec<u64> = receiver.collect().await;
+        assert_eq!(received, (0..100).collect::<Vec<_>>());
+        assert_eq!(sender_task.await.unwrap(), received);
+    }
+
+    #[test]
+    fn send_returns_once_staged() {
+        let (mut sender, mut receiver) = channel();
+        let mut cx = Context::from_waker(Waker::noop());
+        let mut pipeline = pin!(sender.pipeline());
+
+        // The first send completes immediately, with its item staged
+        let first = pipeline.as_mut().send(1).now_or_never().unwrap().unwrap();
+        assert!(pipeline.has_staged());
+        assert!(!pipeline.is_taken(&first));
+
+        // The second send can't stage until the first item is taken
+        {
+            let mut second = pipeline.as_mut().send(2);
+            assert!(second.poll_unpin(&mut cx).is_pending());
+
+            assert_eq!(receiver.try_recv(), Ok(1));
+            assert_matches!(second.poll_unpin(&mut cx), Poll::Ready(Ok(ticket)) => {
+                assert_eq!(ticket.seq(), 1);
+            });
+        }
+
+        assert!(pipeline.is_taken(&first));
+        assert_eq!(receiver.try_recv(), Ok(2));
+        assert_matches!(pipeline.as_mut().flush().now_or_never(), Some(Ok(())));
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Empty));
+    }
+
+    #[tokio::test]
+    async fn disconnect_returns_staged_item_from_flush() {
+        let (mut sender, receiver) = channel();
+        let mut pipeline = pin!(sender.pipeline());
+
+        pipeline.as_mut().send(1).await.unwrap();
+        drop(receiver);
+
+        assert_matches!(pipeline.as_mut().flush().await, Err(SendError(1)));
+        assert_matches!(pipeline.as_mut().send(2).await, Err(SendError(2)));
+    }
+
+    #[tokio::test]
+    async fn producer_overlaps_with_handoff() {
+        let (mut sender, mut receiver) = channel();
+        let mut pipeline = pin!(sender.pipeline());
+
+        // The receiver only takes the first item once the producer has
+        // moved on to building the second
+        let (built, received) = join(
+            async {
+                pipeline.as_mut().send(1).await.unwrap();
+                tokio::task::yield_now().await;
+                let built = 2;
+                pipeline.as_mut().send(built).await.unwrap();
+                pipeline.as_mut().flush().await.unwrap();
+                built
+            },
+            async {
+                let first = receiver.recv().await;
+                let second = receiver.recv().await;
+                (first, second)
+            },
+        )
+        .await;
+
+        assert_eq!(built, 2);
+        assert_eq!(received, (Some(1), Some(2)));
+    }
+}
@@ -0,0 +1,339 @@
+/*!
+A handoff channel with two priority lanes.
+
+Each lane is an ordinary handoff channel, so every send still blocks until the
+receiver takes its item. When items are staged in both lanes at once, the
+receiver always delivers the high-priority item first; the low-priority
+sender stays blocked until no high-priority item is waiting. This is useful
+for schedulers, where urgent work should preempt background work without
+either producer needing to know about the other.
+
+A single [`Sender`] sends to both lanes. To have a send in progress in each
+lane at once, borrow both lanes with [`Sender::lanes`].
+
+```
+# futures::executor::block_on(async move {
+use handoff::priority::{self, Priority};
+use futures::future::join;
+
+let (mut sender, mut receiver) = priority::channel();
+
+let send_task = async move {
+    let (high, low) = sender.lanes();
+    let (a, b) = join(low.send("background"), high.send("urgent")).await;
+    a.unwrap();
+    b.unwrap();
+};
+
+let recv_task = async move {
+    let first = receiver.recv().await;
+    let second = receiver.recv().await;
+    (first, second)
+};
+
+let ((), received) = join(send_task, recv_task).await;
+assert_eq!(received, (Some("urgent"), Some("background")));
+# });
+```
+*/
+
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_util::stream::{FusedStream, Stream, StreamExt};
+
+use crate::SendFut;
+
+/// Create a handoff channel with two priority lanes. See the [module
+/// documentation][self] for details.
+#[must_use]
+pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
+    let (high_sender, high_receiver) = crate::channel();
+    let (low_sender, low_receiver) = crate::channel();
+
+    (
+        Sender {
+            high: high_sender,
+            low: low_sender,
+        },
+        Receiver {
+            high: high_receiver,
+            low: low_receiver,
+        },
+    )
+}
+
+/// The priority of an item sent through a [priority channel][self].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    /// The item is only delivered when no high-priority item is waiting.
+    Low,
+
+    /// The item is delivered ahead of any waiting low-priority item.
+    High,
+}
+
+/// The sending half of a priority channel.
+///
+/// This object is created by the [`channel`] function. See the [module
+/// documentation][self] for details.
+#[derive(Debug)]
+pub struct Sender<T> {
+    high: crate::Sender<T>,
+    low: crate::Sender<T>,
+}
+
+impl<T> Sender<T> {
+    /// Asynchronously send an item to the receiver, in the lane for the given
+    /// `priority`.
+    ///
+    /// This method will asynchronously block until the receiver has received
+    /// the item, which, for a low-priority item, only happens once no
+    /// high-priority item is waiting. See [`crate::Sender::send`] for details.
+    #[inline]
+    pub fn send_with(&mut self, priority: Priority, item: T) -> SendFut<'_, T> {
+        self.lane(priority).send(item)
+    }
+
+    /// Get the sender for a single lane.
+    #[inline]
+    #[must_use]
+    pub fn lane(&mut self, priority: Priority) -> &mut crate::Sender<T> {
+        match priority {
+            Priority::High => &mut self.high,
+            Priority::Low => &mut self.low,
+        }
+    }
+
+    /// Get the senders for both lanes, as `(high, low)`, so that a send can
+    /// be in progress in each lane at once.
+    #[inline]
+    #[must_use]
+    pub fn lanes(&mut self) -> (&mut crate::Sender<T>, &mut crate::Sender<T>) {
+        (&mut self.high, &mut self.low)
+    }
+
+    /// Check if the receiver is still connected. See
+    /// [`crate::Sender::is_connected`] for details.
+    #[inline]
+    #[must_use]
+    pub fn is_connected(&self) -> bool {
+        self.high.is_connected()
+    }
+}
+
+/// The receiving half of a priority channel.
+///
+/// This object is created by the [`channel`] function. Like the
+/// single-lane [`Receiver`][crate::Receiver], it implements [`Stream`]. See
+/// the [module documentation][self] for details.
+#[derive(Debug)]
+pub struct Receiver<T> {
+    high: crate::Receiver<T>,
+    low: crate::Receiver<T>,
+}
+
+impl<T> Receiver<T> {
+    /// Attempt to receive the next item, preferring high-priority items.
+    ///
+    /// This method will asynchronously block until the sender sends an item
+    /// in either lane, then return that item. If items are waiting in both
+    /// lanes, the high-priority item is returned. If the sender disconnects,
+    /// this will return `None`.
+    #[inline]
+    pub fn recv(&mut self) -> RecvFut<'_, T> {
+        RecvFut { receiver: self }
+    }
+
+    /// Like [`recv`][Receiver::recv], but also report which lane the item
+    /// was sent in.
+    #[inline]
+    pub fn recv_with_priority(&mut self) -> RecvWithPriorityFut<'_, T> {
+        RecvWithPriorityFut { receiver: self }
+    }
+
+    /// Check if the sender is still connected. See
+    /// [`crate::Receiver::is_connected`] for details.
+    #[inline]
+    #[must_use]
+    pub fn is_connected(&self) -> bool {
+        self.high.is_connected()
+    }
+
+    fn poll_recv_with_priority(&mut self, cx: &mut Context<'_>) -> Poll<Option<(Priority, T)>> {
+        // Both lanes are always polled when nothing is available, so that
+        // both wakers are registered.
+        let high = match self.high.poll_next_unpin(cx) {
+            Poll::Ready(Some(item)) => return Poll::Ready(Some((Priority::High, item))),
+            Poll::Ready(None) => true,
+            Poll::Pending => false,
+        };
+
+        match self.low.poll_next_unpin(cx) {
+            Poll::Ready(Some(item)) => Poll::Ready(Some((Priority::Low, item))),
+            Poll::Ready(None) if high => Poll::Ready(None),
+            Poll::Ready(None) | Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl<T> Stream for Receiver<T> {
+    type Item = T;
+
+    #[inline]
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        self.get_mut()
+            .poll_recv_with_priority(cx)
+            .map(|item| item.map(|(_, item)| item))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (high_lower, high_upper) = self.high.size_hint();
+        let (low_lower, low_upper) = self.low.size_hint();
+
+        (
+            high_lower + low_lower,
+            high_upper.zip(low_upper).map(|(high, low)| high + low),
+        )
+    }
+}
+
+impl<T> FusedStream for Receiver<T> {
+    #[inline]
+    fn is_terminated(&self) -> bool {
+        self.high.is_terminated() && self.low.is_terminated()
+    }
+}
+
+/// Future type for receiving a single item from a priority [`Receiver`].
+/// Created by the [`recv`][Receiver::recv] method; see its documentation for
+/// details.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+#[derive(Debug)]
+pub struct RecvFut<'a, T> {
+    receiver: &'a mut Receiver<T>,
+}
+
+impl<T> Future for RecvFut<'_, T> {
+    type Output = Option<T>;
+
+    #[inline]
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.receiver.poll_next_unpin(cx)
+    }
+}
+
+/// Future type for receiving a single item from a priority [`Receiver`],
+/// along with its priority. Created by the
+/// [`recv_with_priority`][Receiver::recv_with_priority] method; see its
+/// documentation for details.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+#[derive(Debug)]
+pub struct RecvWithPriorityFut<'a, T> {
+    receiver: &'a mut Receiver<T>,
+}
+
+impl<T> Future for RecvWithPriorityFut<'_, T> {
+    type Output = Option<(Priority, T)>;
+
+    #[inline]
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.receiver.poll_recv_with_priority(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        pin::pin,
+        task::{Context, Poll, Waker},
+    };
+
+    use cool_asserts::assert_matches;
+    use futures::{FutureExt, StreamExt};
+
+    use super::{channel, Priority};
+
+    #[test]
+    fn high_priority_preempts_low() {
+        let (mut sender, mut receiver) = channel();
+        let mut cx = Context::from_waker(Waker::noop());
+        let (high, low) = sender.lanes();
+
+        let mut low_send = pin!(low.send(1));
+        assert!(low_send.poll_unpin(&mut cx).is_pending());
+
+        let mut high_send = pin!(high.send(2));
+        assert!(high_send.poll_unpin(&mut cx).is_pending());
+
+        assert_eq!(receiver.poll_next_unpin(&mut cx), Poll::Ready(Some(2)));
+        assert_matches!(high_send.poll_unpin(&mut cx), Poll::Ready(Ok(())));
+
+        // The low-priority sender was held while the high-priority item was
+        // waiting
+        assert!(low_send.poll_unpin(&mut cx).is_pending());
+
+        assert_eq!(
+            receiver.recv_with_priority().now_or_never(),
+            Some(Some((Priority::Low, 1)))
+        );
+        assert_matches!(low_send.poll_unpin(&mut cx), Poll::Ready(Ok(())));
+    }
+
+    #[tokio::test]
+    async fn high_lane_drains_first() {
+        let (mut sender, mut receiver) = channel();
+
+        let sender_task = tokio::task::spawn(async move {
+            let (high, low) = sender.lanes();
+
+            let low_task = async {
+                for i in 0..3 {
+                    low.send(i).await.unwrap();
+                }
+            };
+
+            let high_task = async {
+                for i in 10..13 {
+                    high.send(i).await.unwrap();
+                }
+            };
+
+            futures::future::join(low_task, high_task).await;
+        });
+
+        // Yield before each receive, to give the sender a chance to stage its
+        // next item in each lane
+        let mut items = Vec::new();
+        loop {
+            tokio::task::yield_now().await;
+
+            match receiver.recv().await {
+                Some(item) => items.push(item),
+                None => break,
+            }
+        }
+
+        sender_task.await.unwrap();
+        assert_eq!(items, [10, 11, 12, 0, 1, 2]);
+    }
+
+    #[tokio::test]
+    async fn disconnect_ends_both_lanes() {
+        let (mut sender, mut receiver) = channel();
+
+        let sender_task = tokio::task::spawn(async move {
+            sender.send_with(Priority::Low, 1).await.unwrap();
+        });
+
+        assert_eq!(receiver.recv().await, Some(1));
+        sender_task.await.unwrap();
+
+        assert!(!receiver.is_connected());
+        assert_eq!(receiver.recv().await, None);
+    }
+}
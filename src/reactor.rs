@@ -0,0 +1,263 @@
+/*!
+Readiness notifications through an `eventfd`, so that a [`Receiver`] can be
+driven by an `epoll` or `poll` based event loop.
+*/
+
+use std::{
+    ffi::{c_int, c_uint},
+    fs::File,
+    io::{self, Read, Write},
+    os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd},
+    sync::{atomic::Ordering::Acquire, Arc},
+    task::{Wake, Waker},
+};
+
+use crate::Receiver;
+
+extern "C" {
+    fn eventfd(initval: c_uint, flags: c_int) -> c_int;
+}
+
+// These are the same as `O_NONBLOCK` and `O_CLOEXEC`, which only differ from
+// the generic values on a few architectures.
+#[cfg(any(
+    target_arch = "mips",
+    target_arch = "mips64",
+    target_arch = "mips32r6",
+    target_arch = "mips64r6"
+))]
+const EFD_FLAGS: c_int = 0x80 | 0x80000;
+
+#[cfg(any(target_arch = "sparc", target_arch = "sparc64"))]
+const EFD_FLAGS: c_int = 0x4000 | 0x400000;
+
+#[cfg(not(any(
+    target_arch = "mips",
+    target_arch = "mips64",
+    target_arch = "mips32r6",
+    target_arch = "mips64r6",
+    target_arch = "sparc",
+    target_arch = "sparc64"
+)))]
+const EFD_FLAGS: c_int = 0o4000 | 0o2000000;
+
+/// A non-blocking `eventfd`, which is readable after it's been signalled
+/// until it's drained. It's registered as the receiver's waker, so the
+/// sender signals it when it stages an item, and `Inner` signals it on a
+/// disconnect, without either of them knowing about it.
+#[derive(Debug)]
+pub(crate) struct EventFd(File);
+
+impl EventFd {
+    fn new() -> io::Result<Self> {
+        // Safety: `eventfd` has no preconditions.
+        let fd = unsafe { eventfd(0, EFD_FLAGS) };
+
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        // Safety: we just created this descriptor, and nothing else owns it.
+        Ok(Self(File::from(unsafe { OwnedFd::from_raw_fd(fd) })))
+    }
+
+    /// Make the descriptor readable.
+    pub(crate) fn signal(&self) {
+        // This can only fail if the counter would overflow, in which case the
+        // descriptor is already readable.
+        let _ = (&self.0).write(&1_u64.to_ne_bytes());
+    }
+
+    /// Reset the descriptor, so that it's no longer readable.
+    pub(crate) fn drain(&self) {
+        // This fails with `WouldBlock` if the descriptor wasn't readable.
+        let _ = (&self.0).read(&mut [0; 8]);
+    }
+
+    /// A waker that signals this descriptor.
+    pub(crate) fn waker(self: &Arc<Self>) -> Waker {
+        Waker::from(self.clone())
+    }
+}
+
+impl Wake for EventFd {
+    #[inline]
+    fn wake(self: Arc<Self>) {
+        self.signal()
+    }
+
+    #[inline]
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.signal()
+    }
+}
+
+impl<T> Receiver<T> {
+    /// Get a file descriptor that becomes readable when an item is
+    /// available, or when the sender disconnects, so that this receiver can
+    /// be driven by an event loop based on `epoll`, `poll`, or `select`.
+    ///
+    /// The descriptor is an `eventfd`, created the first time this is called.
+    /// It's owned by the receiver, and stays open until the receiver is
+    /// dropped, so it must be removed from the event loop before then. Once
+    /// it's readable, call [`try_recv`][Receiver::try_recv]: each call resets
+    /// the descriptor, and arranges for it to be signalled again when the
+    /// next item is staged. Don't read from the descriptor directly.
+    ///
+    /// The descriptor may occasionally be readable when there's nothing to
+    /// receive, in which case `try_recv` returns
+    /// [`TryRecvError::Empty`][crate::TryRecvError::Empty]. Polling the
+    /// receiver as a future or a stream replaces the descriptor as the
+    /// receiver's waker, until the next `try_recv`.
+    ///
+    /// Returns `None` if the descriptor couldn't be created. This is only
+    /// available on Linux, with the `reactor` feature enabled.
+    #[must_use]
+    pub fn readiness_fd(&self) -> Option<RawFd> {
+        if let Some(event) = self.readiness.get() {
+            return Some(event.0.as_raw_fd());
+        }
+
+        let event = Arc::new(EventFd::new().ok()?);
+        let event = self.readiness.get_or_init(|| event);
+
+        // Arm the descriptor, and then check if anything arrived before it
+        // was armed.
+        match self.inner.lock() {
+            Some(lock) => {
+                lock.receiver_waker.register(&event.waker());
+
+                if !lock.sent_item.load(Acquire).is_null() || self.peeked.is_some() {
+                    event.signal();
+                }
+            }
+            None => event.signal(),
+        }
+
+        Some(event.0.as_raw_fd())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        ffi::c_int,
+        os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd},
+        pin::pin,
+        task::{Context, Waker},
+        thread,
+    };
+
+    use futures::{executor::block_on, Future};
+
+    use crate::{channel, TryRecvError};
+
+    // `epoll_event` is packed on x86_64, and only there.
+    #[cfg_attr(target_arch = "x86_64", repr(C, packed))]
+    #[cfg_attr(not(target_arch = "x86_64"), repr(C))]
+    struct EpollEvent {
+        events: u32,
+        data: u64,
+    }
+
+    const EPOLLIN: u32 = 0x1;
+    const EPOLL_CTL_ADD: c_int = 1;
+
+    extern "C" {
+        fn epoll_create1(flags: c_int) -> c_int;
+        fn epoll_ctl(epfd: c_int, op: c_int, fd: c_int, event: *mut EpollEvent) -> c_int;
+        fn epoll_wait(epfd: c_int, events: *mut EpollEvent, max: c_int, timeout: c_int) -> c_int;
+    }
+
+    /// A level-triggered epoll instance watching a single descriptor.
+    struct Epoll(OwnedFd);
+
+    impl Epoll {
+        fn new(fd: RawFd) -> Self {
+            let epoll = unsafe { epoll_create1(0) };
+            assert!(epoll >= 0, "epoll_create1 failed");
+            let epoll = unsafe { OwnedFd::from_raw_fd(epoll) };
+
+            let mut event = EpollEvent {
+                events: EPOLLIN,
+                data: 0,
+            };
+
+            let result = unsafe { epoll_ctl(epoll.as_raw_fd(), EPOLL_CTL_ADD, fd, &mut event) };
+            assert_eq!(result, 0, "epoll_ctl failed");
+
+            Self(epoll)
+        }
+
+        /// Wait up to `timeout` milliseconds (or forever, if it's -1) for
+        /// the descriptor to be readable.
+        fn wait(&self, timeout: c_int) -> bool {
+            let mut event = EpollEvent { events: 0, data: 0 };
+            let result = unsafe { epoll_wait(self.0.as_raw_fd(), &mut event, 1, timeout) };
+            assert!(result >= 0, "epoll_wait failed");
+            result == 1
+        }
+    }
+
+    #[test]
+    fn readable_while_an_item_is_staged() {
+        let (mut sender, mut receiver) = channel();
+        let epoll = Epoll::new(receiver.readiness_fd().unwrap());
+        let mut cx = Context::from_waker(Waker::noop());
+        assert!(!epoll.wait(0));
+
+        for i in 0..3 {
+            let mut send = pin!(sender.send(i));
+            assert!(send.as_mut().poll(&mut cx).is_pending());
+            assert!(epoll.wait(0));
+
+            assert_eq!(receiver.try_recv(), Ok(i));
+            assert!(send.poll(&mut cx).is_ready());
+            assert!(!epoll.wait(0));
+        }
+
+        drop(sender);
+        assert!(epoll.wait(0));
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Disconnected));
+    }
+
+    #[test]
+    fn readable_if_staged_before_creation() {
+        let (mut sender, mut receiver) = channel();
+        let mut cx = Context::from_waker(Waker::noop());
+
+        let mut send = pin!(sender.send(1));
+        assert!(send.as_mut().poll(&mut cx).is_pending());
+
+        let epoll = Epoll::new(receiver.readiness_fd().unwrap());
+        assert!(epoll.wait(0));
+        assert_eq!(receiver.try_recv(), Ok(1));
+    }
+
+    #[test]
+    fn event_loop() {
+        let (mut sender, mut receiver) = channel();
+        let epoll = Epoll::new(receiver.readiness_fd().unwrap());
+
+        let sender_thread = thread::spawn(move || {
+            for i in 0..100 {
+                block_on(sender.send(i)).unwrap();
+            }
+        });
+
+        let mut received = Vec::new();
+
+        loop {
+            assert!(epoll.wait(5000), "timed out waiting for readiness");
+
+            match receiver.try_recv() {
+                Ok(item) => received.push(item),
+                Err(TryRecvError::Empty) => {}
+                Err(TryRecvError::Disconnected) => break,
+            }
+        }
+
+        sender_thread.join().unwrap();
+        assert_eq!(received, (0..100).collect::<Vec<_>>());
+    }
+}
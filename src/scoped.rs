@@ -0,0 +1,102 @@
+/*!
+Channels whose producers run on [scoped threads][std::thread::scope].
+
+Neither half of a handoff channel requires its items to be `'static`, so a
+producer running on a scoped thread can send items that borrow from the
+enclosing stack frame. This module packages up that pattern: it spawns the
+producer onto the scope with a [`BlockingSender`], and hands back a
+[`Receiver`] for an async consumer.
+
+# Lifetime contract
+
+Items of type `T` may borrow anything that outlives the scope (the scope's
+`'env` lifetime). The producer runs on a thread owned by the scope, and
+[`thread::scope`][std::thread::scope] joins it before returning, so neither
+the producer nor any item it sends can outlive the data it borrows. The receiver can be used
+anywhere within the scope, including being moved to other scoped threads; if
+it escapes the scope by some other means, it can only be used as long as `T`
+itself is valid, as with any other type.
+*/
+
+use std::thread::{Scope, ScopedJoinHandle};
+
+use crate::{channel as unscoped_channel, BlockingSender, Receiver};
+
+/// Spawn `producer` on a thread in `scope`, sending items through a new
+/// channel, and return the receiving half.
+///
+/// The producer's thread exits when `producer` returns, which disconnects
+/// the channel. The returned join handle can be used to retrieve its result
+/// or panic, though [`thread::scope`][std::thread::scope] will also join it
+/// automatically.
+///
+/// ```
+/// use std::thread;
+/// use futures::{executor::block_on, StreamExt};
+/// use handoff::scoped;
+///
+/// let text = String::from("borrowed words, not static ones");
+///
+/// let words: Vec<&str> = thread::scope(|scope| {
+///     let (receiver, _producer) = scoped::channel(scope, |mut sender| {
+///         for word in text.split_whitespace() {
+///             sender.send_blocking(word).expect("receiver disconnected");
+///         }
+///     });
+///
+///     block_on(receiver.collect())
+/// });
+///
+/// assert_eq!(words, ["borrowed", "words,", "not", "static", "ones"]);
+/// ```
+pub fn channel<'scope, 'env, T, F, R>(
+    scope: &'scope Scope<'scope, 'env>,
+    producer: F,
+) -> (Receiver<T>, ScopedJoinHandle<'scope, R>)
+where
+    T: Send + 'scope,
+    F: FnOnce(BlockingSender<T>) -> R + Send + 'scope,
+    R: Send + 'scope,
+{
+    let (sender, receiver) = unscoped_channel();
+    let sender = sender.into_blocking();
+    let handle = scope.spawn(move || producer(sender));
+
+    (receiver, handle)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use crate::block_on_recv;
+
+    use super::channel;
+
+    #[test]
+    fn borrowed_items_from_scoped_thread() {
+        let mut data = vec![1, 2, 3];
+
+        let total = thread::scope(|scope| {
+            let (mut receiver, producer) = channel(scope, |mut sender| {
+                for item in &mut data {
+                    *item *= 10;
+                    sender.send_blocking(&*item).unwrap();
+                }
+
+                "done"
+            });
+
+            let mut total = 0;
+            while let Some(&item) = block_on_recv(&mut receiver) {
+                total += item;
+            }
+
+            assert_eq!(producer.join().unwrap(), "done");
+            total
+        });
+
+        assert_eq!(total, 60);
+        assert_eq!(data, [10, 20, 30]);
+    }
+}
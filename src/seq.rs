@@ -0,0 +1,131 @@
+/*!
+Channels that tag each item with a sequence number, for detecting dropped or
+reordered items.
+*/
+
+use crate::{channel, Receiver, SendFut, Sender};
+
+/// Create a handoff channel that tags each sent item with a sequence number.
+///
+/// The receiver is an ordinary [`Receiver`], which receives each item as a
+/// `(seq, item)` pair. Sequence numbers start at 0 and increase by 1 with
+/// each call to [`SeqSender::send`]. A send that's cancelled or fails still
+/// uses up its sequence number, so a gap in the received sequence numbers
+/// means that items were never delivered.
+///
+/// ```
+/// # futures::executor::block_on(async move {
+/// use handoff::channel_seq;
+/// use futures::{future::join, StreamExt};
+///
+/// let (mut sender, receiver) = channel_seq();
+///
+/// let send_task = async move {
+///     sender.send("a").await.unwrap();
+///     sender.send("b").await.unwrap();
+/// };
+///
+/// let (_, received) = join(send_task, receiver.collect::<Vec<_>>()).await;
+/// assert_eq!(received, [(0, "a"), (1, "b")]);
+/// # });
+/// ```
+#[inline]
+#[must_use]
+pub fn channel_seq<T>() -> (SeqSender<T>, Receiver<(u64, T)>) {
+    let (sender, receiver) = channel();
+    (SeqSender { sender, next: 0 }, receiver)
+}
+
+/// The sending half of a sequenced channel, created by [`channel_seq`].
+#[derive(Debug)]
+pub struct SeqSender<T> {
+    sender: Sender<(u64, T)>,
+
+    // The sequence number for the next send
+    next: u64,
+}
+
+impl<T> SeqSender<T> {
+    /// Asynchronously send an item to the receiver, tagged with the next
+    /// sequence number. See [`Sender::send`] for details.
+    #[inline]
+    pub fn send(&mut self, item: T) -> SendFut<'_, (u64, T)> {
+        let seq = self.next;
+        self.next += 1;
+        self.sender.send((seq, item))
+    }
+
+    /// The sequence number that the next send will use.
+    #[inline]
+    #[must_use]
+    pub fn next_seq(&self) -> u64 {
+        self.next
+    }
+
+    /// Check if the receiver is still connected. See
+    /// [`Sender::is_connected`] for details.
+    #[inline]
+    #[must_use]
+    pub fn is_connected(&self) -> bool {
+        self.sender.is_connected()
+    }
+
+    /// Get a reference to the underlying sender.
+    #[inline]
+    #[must_use]
+    pub fn get_ref(&self) -> &Sender<(u64, T)> {
+        &self.sender
+    }
+
+    /// Consume this wrapper, returning the underlying sender.
+    #[inline]
+    #[must_use]
+    pub fn into_inner(self) -> Sender<(u64, T)> {
+        self.sender
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        future::Future,
+        pin::pin,
+        task::{Context, Waker},
+    };
+
+    use futures::{FutureExt, StreamExt};
+
+    use super::channel_seq;
+
+    #[tokio::test]
+    async fn sequence_is_contiguous() {
+        let (mut sender, receiver) = channel_seq();
+
+        let sender_task = tokio::task::spawn(async move {
+            for item in ["a", "b", "c", "d", "e"] {
+                sender.send(item).await.unwrap();
+            }
+            sender.next_seq()
+        });
+
+        let received: Vec<(u64, &str)> = receiver.collect().await;
+        assert_eq!(sender_task.await.unwrap(), 5);
+
+        let seqs: Vec<u64> = received.iter().map(|&(seq, _)| seq).collect();
+        assert_eq!(seqs, [0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn cancelled_send_leaves_gap() {
+        let (mut sender, mut receiver) = channel_seq();
+        let mut cx = Context::from_waker(Waker::noop());
+
+        // Dropped without ever being received
+        assert!(sender.send(1).now_or_never().is_none());
+
+        let mut send = pin!(sender.send(2));
+        assert!(send.as_mut().poll(&mut cx).is_pending());
+        assert_eq!(receiver.try_recv(), Ok((1, 2)));
+        assert!(send.poll(&mut cx).is_ready());
+    }
+}
@@ -0,0 +1,194 @@
+/*!
+A [`Sink`] adapter for [`Sender`].
+*/
+
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_util::{ready, sink::Sink};
+use pin_project::pin_project;
+use twinsies::Joint;
+
+use crate::{Inner, SendError, SendFut, Sender};
+
+impl<T> Sender<T> {
+    /// Borrow this sender as a [`Sink`].
+    ///
+    /// Each item passed to [`start_send`][Sink::start_send] is staged the next
+    /// time the sink is polled, and [`poll_flush`][Sink::poll_flush] only
+    /// resolves once the receiver has taken it, so a flushed sink has no item
+    /// in flight. If the receiver disconnects, the pending item is returned
+    /// in a [`SendError`]. Because the sink only borrows the sender, closing
+    /// it just flushes it; the channel is disconnected when the sender is
+    /// dropped.
+    ///
+    /// Combined with the [`Stream`][futures_util::Stream] implementation on
+    /// [`Receiver`][crate::Receiver], this allows one channel to be forwarded
+    /// into another with [`StreamExt::forward`][futures_util::StreamExt::forward]:
+    ///
+    /// ```
+    /// # futures::executor::block_on(async move {
+    /// use futures::{future::join3, StreamExt};
+    ///
+    /// let (mut sender, receiver) = handoff::channel();
+    /// let (mut forward_sender, forward_receiver) = handoff::channel();
+    ///
+    /// let send_task = async move {
+    ///     sender.send(1).await.unwrap();
+    ///     sender.send(2).await.unwrap();
+    /// };
+    ///
+    /// // The forwarding sender is dropped once the forward is done, which
+    /// // ends the collect below
+    /// let forward_task = async move { receiver.map(Ok).forward(forward_sender.sink()).await };
+    /// let recv_task = forward_receiver.collect::<Vec<i32>>();
+    ///
+    /// let ((), forwarded, received) = join3(send_task, forward_task, recv_task).await;
+    /// forwarded.unwrap();
+    /// assert_eq!(received, [1, 2]);
+    /// # });
+    /// ```
+    #[inline]
+    pub fn sink(&mut self) -> SenderSink<'_, T> {
+        SenderSink {
+            inner: &self.inner,
+            send: None,
+        }
+    }
+}
+
+/// Sink for the [`sink`][Sender::sink] method. See its documentation for
+/// details.
+#[must_use = "sinks do nothing unless polled"]
+#[pin_project]
+#[derive(Debug)]
+pub struct SenderSink<'a, T> {
+    inner: &'a Joint<Inner<T>>,
+
+    // The send in progress, if any
+    #[pin]
+    send: Option<SendFut<'a, T>>,
+}
+
+impl<T> SenderSink<'_, T> {
+    /// Drive the send in progress, if any, to completion.
+    fn poll_send(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), SendError<T>>> {
+        let mut this = self.project();
+
+        let Some(send) = this.send.as_mut().as_pin_mut() else { return Poll::Ready(Ok(())) };
+        let result = ready!(send.poll(cx));
+        this.send.set(None);
+
+        Poll::Ready(result)
+    }
+}
+
+impl<T> Sink<T> for SenderSink<'_, T> {
+    type Error = SendError<T>;
+
+    #[inline]
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), SendError<T>>> {
+        self.poll_send(cx)
+    }
+
+    #[inline]
+    fn start_send(self: Pin<&mut Self>, item: T) -> Result<(), SendError<T>> {
+        let mut this = self.project();
+
+        assert!(
+            this.send.is_none(),
+            "start_send called without poll_ready returning Ready(Ok(()))"
+        );
+
+        this.send.set(Some(SendFut::new(this.inner, item)));
+        Ok(())
+    }
+
+    #[inline]
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), SendError<T>>> {
+        self.poll_send(cx)
+    }
+
+    #[inline]
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), SendError<T>>> {
+        self.poll_send(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cool_asserts::assert_matches;
+    use futures::{FutureExt, SinkExt, StreamExt};
+
+    use crate::{channel, SendError};
+
+    #[tokio::test]
+    async fn forward_between_channels() {
+        let (mut sender, receiver) = channel();
+        let (mut forward_sender, forward_receiver) = channel();
+
+        let send_task = tokio::task::spawn(async move {
+            for i in 0..10 {
+                sender.send(i).await.unwrap();
+            }
+        });
+
+        let recv_task = tokio::task::spawn(forward_receiver.collect::<Vec<i32>>());
+
+        // The forward ends when the first sender disconnects
+        receiver.map(Ok).forward(forward_sender.sink()).await.unwrap();
+        drop(forward_sender);
+
+        send_task.await.unwrap();
+        assert_eq!(recv_task.await.unwrap(), (0..10).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn forward_reports_sink_disconnect() {
+        let (mut sender, receiver) = channel();
+        let (mut forward_sender, mut forward_receiver) = channel();
+
+        let send_task = tokio::task::spawn(async move {
+            for i in 0..3 {
+                if sender.send(i).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        let recv_task = tokio::task::spawn(async move {
+            let item = forward_receiver.recv().await;
+            drop(forward_receiver);
+            item
+        });
+
+        let result = receiver.map(Ok).forward(forward_sender.sink()).await;
+        assert_matches!(result, Err(SendError(1)));
+        assert_eq!(recv_task.await.unwrap(), Some(0));
+        send_task.await.unwrap();
+    }
+
+    #[test]
+    fn flush_waits_for_receiver() {
+        let (mut sender, mut receiver) = channel();
+        let mut sink = Box::pin(sender.sink());
+
+        assert_matches!(sink.feed(1).now_or_never(), Some(Ok(())));
+        assert!(sink.flush().now_or_never().is_none());
+
+        assert_eq!(receiver.try_recv(), Ok(1));
+        assert_matches!(sink.flush().now_or_never(), Some(Ok(())));
+    }
+
+    #[test]
+    fn disconnected_receiver_returns_item() {
+        let (mut sender, receiver) = channel();
+        drop(receiver);
+
+        let mut sink = Box::pin(sender.sink());
+        assert_matches!(sink.send(1).now_or_never(), Some(Err(SendError(1))));
+    }
+}
@@ -0,0 +1,297 @@
+/*!
+Receives that report more about their outcome: as a single [`RecvStatus`], or
+along with whether the receiver had to wait, or whether the sender is still
+connected.
+*/
+
+use std::{
+    fmt::Debug,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use crate::{Receiver, RecvFut, TryRecvError};
+
+/// The outcome of a receive, from [`Receiver::recv_status`] or
+/// [`Receiver::try_recv_status`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RecvStatus<T> {
+    /// An item was received.
+    Value(T),
+
+    /// The sender is connected, but isn't currently sending an item. This is
+    /// only returned by [`try_recv_status`][Receiver::try_recv_status].
+    Empty,
+
+    /// The sender disconnected.
+    Disconnected,
+}
+
+impl<T> RecvStatus<T> {
+    /// Get the received item, if any.
+    #[inline]
+    #[must_use]
+    pub fn value(self) -> Option<T> {
+        match self {
+            RecvStatus::Value(item) => Some(item),
+            RecvStatus::Empty | RecvStatus::Disconnected => None,
+        }
+    }
+
+    /// Check if this status indicates that the sender disconnected.
+    #[inline]
+    #[must_use]
+    pub fn is_disconnected(&self) -> bool {
+        matches!(self, RecvStatus::Disconnected)
+    }
+}
+
+impl<T> From<Result<T, TryRecvError>> for RecvStatus<T> {
+    #[inline]
+    fn from(result: Result<T, TryRecvError>) -> Self {
+        match result {
+            Ok(item) => RecvStatus::Value(item),
+            Err(TryRecvError::Empty) => RecvStatus::Empty,
+            Err(TryRecvError::Disconnected) => RecvStatus::Disconnected,
+        }
+    }
+}
+
+impl<T> Receiver<T> {
+    /// Receive the next item from the sender, reporting the outcome as a
+    /// [`RecvStatus`].
+    ///
+    /// This is the same as [`recv`][Receiver::recv], but resolves to
+    /// [`RecvStatus::Value`] or [`RecvStatus::Disconnected`] instead of an
+    /// `Option`; it never resolves to [`RecvStatus::Empty`].
+    #[inline]
+    pub fn recv_status(&mut self) -> RecvStatusFut<'_, T> {
+        RecvStatusFut { recv: self.recv() }
+    }
+
+    /// Attempt to receive an item without blocking, reporting the outcome as
+    /// a [`RecvStatus`].
+    ///
+    /// This is the same as [`try_recv`][Receiver::try_recv], but uses the
+    /// same status type as [`recv_status`][Receiver::recv_status].
+    #[inline]
+    pub fn try_recv_status(&mut self) -> RecvStatus<T> {
+        self.try_recv().into()
+    }
+
+    /// Receive the next item from the sender, along with whether the
+    /// receiver had to wait for it.
+    ///
+    /// This is the same as [`recv`][Receiver::recv], but the item is paired
+    /// with `true` if the future returned `Pending` at least once before the
+    /// item arrived, or `false` if the item was already available. This is
+    /// useful for adaptive batching: a consumer that rarely waits is falling
+    /// behind its producer, and can afford larger batches.
+    #[inline]
+    pub fn recv_timed(&mut self) -> RecvTimedFut<'_, T> {
+        RecvTimedFut {
+            recv: self.recv(),
+            waited: false,
+        }
+    }
+
+    /// Receive the next item from the sender, along with a hint about
+    /// whether more items might follow.
+    ///
+    /// This is the same as [`recv`][Receiver::recv], but the item is paired
+    /// with whether the sender was still connected when the item was
+    /// delivered, as reported by [`is_connected`][Receiver::is_connected].
+    /// A consumer can use this to decide whether to try another receive or
+    /// to wind down.
+    ///
+    /// The hint is only advisory. An item taken straight from a waiting send
+    /// is always paired with `true`, since its sender is blocked until the
+    /// item is taken, even if it disconnects right after; so `false` only
+    /// shows up for an item that was already [peeked][Receiver::peek] or
+    /// [prefetched][Receiver::prefetch] before the sender disconnected. A
+    /// `true` hint is no guarantee that another item will arrive, but a
+    /// `false` one means that the sender is gone.
+    #[inline]
+    pub fn recv_hinted(&mut self) -> RecvHintedFut<'_, T> {
+        RecvHintedFut { recv: self.recv() }
+    }
+}
+
+/// Future type for receiving a single item from a [`Receiver`] as a
+/// [`RecvStatus`]. Created by the [`recv_status`][Receiver::recv_status]
+/// method; see its documentation for details.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct RecvStatusFut<'a, T> {
+    recv: RecvFut<'a, T>,
+}
+
+impl<T> Debug for RecvStatusFut<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RecvStatus")
+            .field("receiver", &self.recv.receiver)
+            .finish()
+    }
+}
+
+impl<T> Future for RecvStatusFut<'_, T> {
+    type Output = RecvStatus<T>;
+
+    #[inline]
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<RecvStatus<T>> {
+        Pin::new(&mut self.recv).poll(cx).map(|item| match item {
+            Some(item) => RecvStatus::Value(item),
+            None => RecvStatus::Disconnected,
+        })
+    }
+}
+
+/// Future for the [`recv_timed`][Receiver::recv_timed] method. See its
+/// documentation for details.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct RecvTimedFut<'a, T> {
+    recv: RecvFut<'a, T>,
+
+    // True once we've returned `Pending`
+    waited: bool,
+}
+
+impl<T> Debug for RecvTimedFut<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RecvTimedFut")
+            .field("receiver", &self.recv.receiver)
+            .field("waited", &self.waited)
+            .finish()
+    }
+}
+
+impl<T> Future for RecvTimedFut<'_, T> {
+    type Output = Option<(T, bool)>;
+
+    #[inline]
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match Pin::new(&mut self.recv).poll(cx) {
+            Poll::Ready(item) => Poll::Ready(item.map(|item| (item, self.waited))),
+            Poll::Pending => {
+                self.waited = true;
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Future for the [`recv_hinted`][Receiver::recv_hinted] method. See its
+/// documentation for details.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct RecvHintedFut<'a, T> {
+    recv: RecvFut<'a, T>,
+}
+
+impl<T> Debug for RecvHintedFut<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RecvHintedFut")
+            .field("receiver", &self.recv.receiver)
+            .finish()
+    }
+}
+
+impl<T> Future for RecvHintedFut<'_, T> {
+    type Output = Option<(T, bool)>;
+
+    #[inline]
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let item = futures_util::ready!(Pin::new(&mut self.recv).poll(cx));
+        let connected = self.recv.receiver.is_connected();
+        Poll::Ready(item.map(|item| (item, connected)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::FutureExt;
+
+    use crate::channel;
+
+    use super::RecvStatus;
+
+    #[tokio::test]
+    async fn recv_status() {
+        let (mut sender, mut receiver) = channel();
+
+        let sender_task = tokio::task::spawn(async move { sender.send(1).await });
+        assert_eq!(receiver.recv_status().await, RecvStatus::Value(1));
+        sender_task.await.unwrap().unwrap();
+
+        assert_eq!(receiver.recv_status().await, RecvStatus::Disconnected);
+    }
+
+    #[test]
+    fn recv_timed_ready_immediately() {
+        let (mut sender, mut receiver) = channel();
+
+        let mut send = Box::pin(sender.send(1));
+        assert!(send.as_mut().now_or_never().is_none());
+        assert_eq!(receiver.recv_timed().now_or_never(), Some(Some((1, false))));
+        assert!(send.now_or_never().unwrap().is_ok());
+    }
+
+    #[tokio::test]
+    async fn recv_timed_had_to_wait() {
+        let (mut sender, mut receiver) = channel();
+
+        let sender_task = tokio::task::spawn(async move {
+            tokio::task::yield_now().await;
+            sender.send(1).await
+        });
+
+        assert_eq!(receiver.recv_timed().await, Some((1, true)));
+        sender_task.await.unwrap().unwrap();
+
+        assert_eq!(receiver.recv_timed().await, None);
+    }
+
+    #[tokio::test]
+    async fn recv_hinted_last_item() {
+        let (mut sender, mut receiver) = channel();
+
+        let sender_task = tokio::task::spawn(async move {
+            sender.send(1).await.unwrap();
+            sender.send(2).await.unwrap();
+        });
+
+        // The sender drops right after its last item is taken, but was
+        // necessarily still connected while waiting for it to be taken
+        assert_eq!(receiver.recv_hinted().await, Some((1, true)));
+        assert_eq!(receiver.recv_hinted().await, Some((2, true)));
+        sender_task.await.unwrap();
+
+        assert_eq!(receiver.recv_hinted().await, None);
+    }
+
+    #[tokio::test]
+    async fn recv_hinted_after_disconnect() {
+        let (mut sender, mut receiver) = channel();
+
+        let sender_task = tokio::task::spawn(async move { sender.send(1).await });
+        receiver.prefetch().await;
+        sender_task.await.unwrap().unwrap();
+
+        // The prefetched item outlived its sender, so nothing else can follow
+        assert_eq!(receiver.recv_hinted().await, Some((1, false)));
+        assert_eq!(receiver.recv_hinted().await, None);
+    }
+
+    #[test]
+    fn try_recv_status() {
+        let (mut sender, mut receiver) = channel();
+        assert_eq!(receiver.try_recv_status(), RecvStatus::Empty);
+
+        let mut send = Box::pin(sender.send(1));
+        assert!(send.as_mut().now_or_never().is_none());
+        assert_eq!(receiver.try_recv_status(), RecvStatus::Value(1));
+        assert!(send.now_or_never().unwrap().is_ok());
+
+        drop(sender);
+        assert_eq!(receiver.try_recv_status(), RecvStatus::Disconnected);
+    }
+}
@@ -0,0 +1,48 @@
+/*!
+Channel-aware [`Stream`][futures_util::Stream] adapters for
+[`Receiver`][crate::Receiver].
+
+Most of these are created by a method on [`Receiver`][crate::Receiver]. Unlike the general
+purpose adapters in [`futures::StreamExt`][futures_util::StreamExt], they
+exploit the rendezvous semantics of the channel: because a sender is blocked
+until its item is taken, an adapter that declines to take an item applies
+backpressure directly to the sender.
+*/
+
+mod adaptive;
+#[cfg(feature = "time")]
+mod chunks_timeout;
+mod filter_map;
+#[cfg(feature = "time")]
+mod heartbeat;
+mod map_while;
+mod owned;
+mod peekable;
+mod ready_chunks;
+mod reconnecting;
+mod scan;
+#[cfg(feature = "time")]
+mod staged_duration;
+mod terminator;
+#[cfg(feature = "time")]
+mod throttled;
+mod zip;
+
+pub use adaptive::{Adaptive, AdaptiveMode};
+#[cfg(feature = "time")]
+pub use chunks_timeout::ChunksTimeout;
+pub use filter_map::FilterMap;
+#[cfg(feature = "time")]
+pub use heartbeat::Heartbeat;
+pub use map_while::MapWhile;
+pub use owned::OwnedReceiverStream;
+pub use peekable::PeekableReceiver;
+pub use ready_chunks::ReadyChunks;
+pub use reconnecting::ReconnectingReceiver;
+pub use scan::Scan;
+#[cfg(feature = "time")]
+pub use staged_duration::WithStagedDuration;
+pub use terminator::WithTerminator;
+#[cfg(feature = "time")]
+pub use throttled::Throttled;
+pub use zip::{zip, Zip};
@@ -0,0 +1,282 @@
+/*!
+Implementation of [`Receiver::adaptive`].
+*/
+
+use std::{
+    collections::VecDeque,
+    mem,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_util::stream::{FusedStream, Stream, StreamExt};
+
+use crate::Receiver;
+
+/// How many items in a row have to be ready without waiting before an
+/// [`Adaptive`] stream switches to [`AdaptiveMode::Batch`].
+const BATCH_AFTER: u32 = 4;
+
+/// The most items an [`Adaptive`] stream receives in a single poll.
+const MAX_BATCH: usize = 32;
+
+impl<T> Receiver<T> {
+    /// Convert this receiver into a stream that adapts to how far the
+    /// consumer is behind the producer.
+    ///
+    /// The stream starts out in [`AdaptiveMode::Single`], where it behaves
+    /// exactly like the receiver itself, receiving one item per poll. It
+    /// watches the same "had to wait" signal as
+    /// [`recv_timed`][Receiver::recv_timed]: once several items in a row
+    /// were already staged when it went to receive them, the consumer is
+    /// falling behind, and the stream switches to [`AdaptiveMode::Batch`].
+    /// In that mode, each time it receives an item, it also drains every
+    /// item that's ready without waiting (like
+    /// [`ready_chunks`][Receiver::ready_chunks]) into a buffer, and then
+    /// yields the buffered items one at a time, which saves a wakeup per
+    /// item. As soon as it has to wait for an item again, the consumer has
+    /// caught up, and it switches back to `Single`, so that no items are
+    /// taken from the sender before the consumer asks for them.
+    ///
+    /// Items are yielded in the order they were sent, in either mode.
+    pub fn adaptive(self) -> Adaptive<T> {
+        Adaptive {
+            receiver: self,
+            buffer: VecDeque::new(),
+            mode: AdaptiveMode::Single,
+            ready_streak: 0,
+            waited: false,
+        }
+    }
+}
+
+/// The receiving strategy an [`Adaptive`] stream is currently using.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AdaptiveMode {
+    /// Receive one item per poll, to minimize latency.
+    Single,
+
+    /// Receive every ready item per poll, to maximize throughput.
+    Batch,
+}
+
+/// Stream for the [`Receiver::adaptive`] method.
+#[must_use = "streams do nothing unless polled"]
+#[derive(Debug)]
+pub struct Adaptive<T> {
+    receiver: Receiver<T>,
+
+    // Items drained from the receiver in batch mode, not yet yielded
+    buffer: VecDeque<T>,
+
+    mode: AdaptiveMode,
+
+    // How many items in a row were ready without waiting
+    ready_streak: u32,
+
+    // True if the receiver returned `Pending` since the last item
+    waited: bool,
+}
+
+impl<T> Adaptive<T> {
+    /// The receiving strategy this stream is currently using.
+    #[inline]
+    #[must_use]
+    pub fn mode(&self) -> AdaptiveMode {
+        self.mode
+    }
+
+    /// Check if the sender is still connected. See
+    /// [`Receiver::is_connected`] for details. Items that were already
+    /// drained can still be yielded after it disconnects.
+    #[inline]
+    #[must_use]
+    pub fn is_connected(&self) -> bool {
+        self.receiver.is_connected()
+    }
+
+    /// Get a reference to the underlying receiver.
+    #[inline]
+    #[must_use]
+    pub fn get_ref(&self) -> &Receiver<T> {
+        &self.receiver
+    }
+
+    /// Consume this stream, returning the underlying receiver, along with
+    /// any items that were drained from it but not yet yielded.
+    #[inline]
+    #[must_use]
+    pub fn into_inner(self) -> (Receiver<T>, Vec<T>) {
+        (self.receiver, self.buffer.into())
+    }
+}
+
+// The buffered items are never pinned, so the stream is `Unpin` regardless
+// of `T`.
+impl<T> Unpin for Adaptive<T> {}
+
+impl<T> Stream for Adaptive<T> {
+    type Item = T;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let this = &mut *self;
+
+        if let Some(item) = this.buffer.pop_front() {
+            return Poll::Ready(Some(item));
+        }
+
+        let item = match this.receiver.poll_next_unpin(cx) {
+            Poll::Ready(Some(item)) => item,
+            Poll::Ready(None) => return Poll::Ready(None),
+            Poll::Pending => {
+                this.waited = true;
+                return Poll::Pending;
+            }
+        };
+
+        match mem::take(&mut this.waited) {
+            true => {
+                this.ready_streak = 0;
+                this.mode = AdaptiveMode::Single;
+            }
+            false => {
+                this.ready_streak = this.ready_streak.saturating_add(1);
+
+                if this.ready_streak >= BATCH_AFTER {
+                    this.mode = AdaptiveMode::Batch;
+                }
+            }
+        }
+
+        if this.mode == AdaptiveMode::Batch {
+            // A disconnect is reported once the buffer is empty
+            while this.buffer.len() < MAX_BATCH - 1 {
+                match this.receiver.poll_next_unpin(cx) {
+                    Poll::Ready(Some(item)) => this.buffer.push_back(item),
+                    Poll::Ready(None) | Poll::Pending => break,
+                }
+            }
+        }
+
+        Poll::Ready(Some(item))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (lower, upper) = self.receiver.size_hint();
+        let buffered = self.buffer.len();
+
+        (
+            lower.saturating_add(buffered),
+            upper.and_then(|upper| upper.checked_add(buffered)),
+        )
+    }
+}
+
+impl<T> FusedStream for Adaptive<T> {
+    #[inline]
+    fn is_terminated(&self) -> bool {
+        self.buffer.is_empty() && self.receiver.is_terminated()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        future::Future,
+        pin::pin,
+        task::{Context, Poll, Waker},
+    };
+
+    use futures::{Stream, StreamExt};
+
+    use crate::{channel, testing::InlineWaker};
+
+    use super::{AdaptiveMode, BATCH_AFTER};
+
+    #[test]
+    fn switches_to_batch_when_behind_and_back_when_caught_up() {
+        let (mut sender, receiver) = channel();
+        let mut stream = receiver.adaptive();
+        let mut cx = Context::from_waker(Waker::noop());
+
+        // Every item is already staged when the stream goes to receive it
+        for i in 0..BATCH_AFTER {
+            assert_eq!(stream.mode(), AdaptiveMode::Single);
+
+            let mut send = pin!(sender.send(i));
+            assert!(send.as_mut().poll(&mut cx).is_pending());
+            assert_eq!(stream.poll_next_unpin(&mut cx), Poll::Ready(Some(i)));
+            assert!(send.poll(&mut cx).is_ready());
+        }
+
+        assert_eq!(stream.mode(), AdaptiveMode::Batch);
+
+        // Having to wait means the consumer caught up
+        assert!(stream.poll_next_unpin(&mut cx).is_pending());
+
+        let mut send = pin!(sender.send(BATCH_AFTER));
+        assert!(send.as_mut().poll(&mut cx).is_pending());
+        assert_eq!(
+            stream.poll_next_unpin(&mut cx),
+            Poll::Ready(Some(BATCH_AFTER))
+        );
+        assert_eq!(stream.mode(), AdaptiveMode::Single);
+    }
+
+    #[test]
+    fn bursty_producer_is_drained_in_batches() {
+        let (mut sender, receiver) = channel();
+
+        // The sender stages its next item as soon as the previous one is
+        // taken, so the stream never has to wait
+        let sender_task = InlineWaker::spawn(async move {
+            for i in 0..20 {
+                sender.send(i).await.unwrap();
+            }
+        });
+
+        let mut stream = receiver.adaptive();
+        let mut cx = Context::from_waker(Waker::noop());
+
+        for i in 0..BATCH_AFTER {
+            assert_eq!(stream.poll_next_unpin(&mut cx), Poll::Ready(Some(i)));
+        }
+
+        // The switch drained the rest of the burst in the same poll
+        assert_eq!(stream.mode(), AdaptiveMode::Batch);
+        assert!(sender_task.is_finished());
+
+        let (_, rest) = stream.size_hint();
+        assert_eq!(rest, Some(20 - BATCH_AFTER as usize));
+
+        for i in BATCH_AFTER..20 {
+            assert_eq!(stream.poll_next_unpin(&mut cx), Poll::Ready(Some(i)));
+        }
+
+        assert_eq!(stream.poll_next_unpin(&mut cx), Poll::Ready(None));
+    }
+
+    #[tokio::test]
+    async fn steady_producer_stays_single() {
+        let (mut sender, receiver) = channel();
+
+        let sender_task = tokio::task::spawn(async move {
+            for i in 0..10 {
+                sender.send(i).await.unwrap();
+            }
+        });
+
+        // The sender is only polled after the stream waits for it, so the
+        // stream never falls behind
+        let mut stream = receiver.adaptive();
+
+        for i in 0..10 {
+            assert_eq!(stream.next().await, Some(i));
+            assert_eq!(stream.mode(), AdaptiveMode::Single);
+        }
+
+        assert_eq!(stream.next().await, None);
+        sender_task.await.unwrap();
+    }
+}
@@ -0,0 +1,197 @@
+/*!
+Implementation of [`Receiver::chunks_timeout`].
+*/
+
+use std::{
+    mem,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use futures_util::{
+    stream::{FusedStream, Stream, StreamExt},
+    FutureExt,
+};
+
+use crate::{timer::Sleep, Receiver};
+
+impl<T> Receiver<T> {
+    /// Convert this receiver into a stream of batches, each containing up to
+    /// `max` items.
+    ///
+    /// A batch is yielded as soon as it's full, or once `timeout` has elapsed
+    /// since its first item was received, whichever comes first. When the
+    /// sender disconnects, any partial batch is yielded, and then the stream
+    /// ends. Empty batches are never yielded. This is useful for coalescing
+    /// writes downstream of a channel.
+    ///
+    /// While a batch is being filled, every available item is taken
+    /// immediately, so the sender is never held blocked waiting for the
+    /// timeout. Once a batch is full, no more items are taken until it has
+    /// been yielded and the stream is polled again, which applies backpressure
+    /// to the sender.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max` is zero.
+    pub fn chunks_timeout(self, max: usize, timeout: Duration) -> ChunksTimeout<T> {
+        assert!(max > 0, "chunk size must be nonzero");
+
+        ChunksTimeout {
+            receiver: self,
+            max,
+            timeout,
+            batch: Vec::new(),
+            sleep: None,
+        }
+    }
+}
+
+/// Stream for the [`Receiver::chunks_timeout`] method.
+#[must_use = "streams do nothing unless polled"]
+#[derive(Debug)]
+pub struct ChunksTimeout<T> {
+    receiver: Receiver<T>,
+    max: usize,
+    timeout: Duration,
+
+    // The batch currently being filled
+    batch: Vec<T>,
+
+    // The deadline for the current batch. This is `Some` whenever `batch` is
+    // non-empty.
+    sleep: Option<Sleep>,
+}
+
+impl<T> ChunksTimeout<T> {
+    /// Get a reference to the underlying receiver.
+    #[inline]
+    #[must_use]
+    pub fn get_ref(&self) -> &Receiver<T> {
+        &self.receiver
+    }
+
+    /// Consume this stream, returning the underlying receiver, along with any
+    /// items in the current partial batch.
+    #[inline]
+    #[must_use]
+    pub fn into_inner(self) -> (Receiver<T>, Vec<T>) {
+        (self.receiver, self.batch)
+    }
+
+    fn take_batch(&mut self) -> Vec<T> {
+        self.sleep = None;
+        mem::replace(&mut self.batch, Vec::with_capacity(self.max))
+    }
+}
+
+// The batched items are never pinned.
+impl<T> Unpin for ChunksTimeout<T> {}
+
+impl<T> Stream for ChunksTimeout<T> {
+    type Item = Vec<T>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Vec<T>>> {
+        let this = &mut *self;
+
+        loop {
+            match this.receiver.poll_next_unpin(cx) {
+                Poll::Ready(Some(item)) => {
+                    if this.batch.is_empty() {
+                        this.batch.reserve_exact(this.max);
+                        this.sleep = Some(Sleep::after(this.timeout));
+                    }
+
+                    this.batch.push(item);
+
+                    if this.batch.len() >= this.max {
+                        return Poll::Ready(Some(this.take_batch()));
+                    }
+                }
+                Poll::Ready(None) => {
+                    return Poll::Ready(match this.batch.is_empty() {
+                        true => None,
+                        false => Some(this.take_batch()),
+                    })
+                }
+                Poll::Pending => {
+                    let Some(sleep) = &mut this.sleep else { return Poll::Pending };
+                    futures_util::ready!(sleep.poll_unpin(cx));
+                    return Poll::Ready(Some(this.take_batch()));
+                }
+            }
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // Each remaining item could end up in its own batch, but any items at
+        // all will make at least one batch.
+        let pending = !self.batch.is_empty() as usize;
+        let (lower, upper) = self.receiver.size_hint();
+
+        (pending.max(lower.min(1)), upper.map(|upper| upper + pending))
+    }
+}
+
+impl<T> FusedStream for ChunksTimeout<T> {
+    #[inline]
+    fn is_terminated(&self) -> bool {
+        self.batch.is_empty() && self.receiver.is_terminated()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use futures::StreamExt;
+
+    use crate::channel;
+
+    #[tokio::test]
+    async fn full_batches_and_partial_flush() {
+        let (mut sender, receiver) = channel();
+
+        let sender_task = tokio::task::spawn(async move {
+            for i in 0..7 {
+                sender.send(i).await.unwrap();
+            }
+        });
+
+        let batches: Vec<Vec<i32>> = receiver
+            .chunks_timeout(3, Duration::from_secs(5))
+            .collect()
+            .await;
+
+        sender_task.await.unwrap();
+        assert_eq!(batches, [vec![0, 1, 2], vec![3, 4, 5], vec![6]]);
+    }
+
+    #[tokio::test]
+    async fn timeout_flushes_partial_batch() {
+        let (mut sender, receiver) = channel();
+        let timeout = Duration::from_millis(30);
+
+        let sender_task = tokio::task::spawn(async move {
+            sender.send(1).await.unwrap();
+            sender.send(2).await.unwrap();
+
+            // Neither send was held up waiting for the batch to fill
+            let sent = Instant::now();
+            tokio::time::sleep(timeout * 3).await;
+            sender.send(3).await.unwrap();
+            sent
+        });
+
+        let mut chunks = receiver.chunks_timeout(10, timeout);
+        assert_eq!(chunks.next().await, Some(vec![1, 2]));
+        let flushed = Instant::now();
+        assert_eq!(chunks.next().await, Some(vec![3]));
+        assert_eq!(chunks.next().await, None);
+
+        let sent = sender_task.await.unwrap();
+        assert!(sent < flushed, "the sender was blocked by the batch timeout");
+    }
+}
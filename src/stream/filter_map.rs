@@ -0,0 +1,173 @@
+/*!
+Implementation of [`Receiver::filter_map`].
+*/
+
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_util::{
+    ready,
+    stream::{FusedStream, Stream, StreamExt},
+};
+
+use crate::Receiver;
+
+impl<T> Receiver<T> {
+    /// Convert this receiver into a stream that yields `f(item)` for each
+    /// received item, skipping items for which `f` returns `None`.
+    ///
+    /// This is like [`StreamExt::filter_map`], but with a synchronous
+    /// closure, and the returned [`FilterMap`] keeps the receiver's channel
+    /// methods, like [`is_connected`][FilterMap::is_connected]. Each skipped
+    /// item is still received, so its send succeeds.
+    ///
+    /// To avoid starving other tasks when the sender is always ready and
+    /// every item is skipped, the stream yields to the executor after
+    /// skipping a number of items in a single poll; see
+    /// [`Builder::coop_budget`][crate::Builder::coop_budget].
+    pub fn filter_map<U, F>(self, f: F) -> FilterMap<T, F>
+    where
+        F: FnMut(T) -> Option<U>,
+    {
+        FilterMap { receiver: self, f }
+    }
+}
+
+/// Stream for the [`Receiver::filter_map`] method.
+#[must_use = "streams do nothing unless polled"]
+#[derive(Debug)]
+pub struct FilterMap<T, F> {
+    receiver: Receiver<T>,
+    f: F,
+}
+
+impl<T, F> FilterMap<T, F> {
+    /// Check if the sender is still connected. See
+    /// [`Receiver::is_connected`] for details.
+    #[inline]
+    #[must_use]
+    pub fn is_connected(&self) -> bool {
+        self.receiver.is_connected()
+    }
+
+    /// Get a reference to the underlying receiver.
+    #[inline]
+    #[must_use]
+    pub fn get_ref(&self) -> &Receiver<T> {
+        &self.receiver
+    }
+
+    /// Consume this stream, returning the underlying receiver.
+    #[inline]
+    #[must_use]
+    pub fn into_inner(self) -> Receiver<T> {
+        self.receiver
+    }
+}
+
+// The closure is never pinned.
+impl<T, F> Unpin for FilterMap<T, F> {}
+
+impl<T, U, F> Stream for FilterMap<T, F>
+where
+    F: FnMut(T) -> Option<U>,
+{
+    type Item = U;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<U>> {
+        let this = self.get_mut();
+        let mut budget = this.receiver.coop_budget;
+
+        loop {
+            let Some(item) = ready!(this.receiver.poll_next_unpin(cx)) else {
+                return Poll::Ready(None);
+            };
+
+            if let Some(mapped) = (this.f)(item) {
+                return Poll::Ready(Some(mapped));
+            }
+
+            budget = budget.saturating_sub(1);
+
+            if budget == 0 {
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.receiver.size_hint().1)
+    }
+}
+
+impl<T, U, F> FusedStream for FilterMap<T, F>
+where
+    F: FnMut(T) -> Option<U>,
+{
+    #[inline]
+    fn is_terminated(&self) -> bool {
+        self.receiver.is_terminated()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{stream::FusedStream, FutureExt, StreamExt};
+
+    use crate::{channel, Builder};
+
+    #[tokio::test]
+    async fn skips_filtered_items() {
+        let (mut sender, receiver) = channel();
+
+        let sender_task = tokio::task::spawn(async move {
+            for i in 1..=6 {
+                sender.send(i).await.unwrap();
+            }
+        });
+
+        let mut stream = receiver.filter_map(|item| (item % 2 == 0).then_some(item * 10));
+        assert!(stream.is_connected());
+
+        let items: Vec<i32> = (&mut stream).collect().await;
+        assert_eq!(items, [20, 40, 60]);
+        sender_task.await.unwrap();
+
+        assert!(!stream.is_connected());
+        assert!(stream.is_terminated());
+    }
+
+    #[test]
+    fn yields_after_budget() {
+        let (sender, receiver) = Builder::new().coop_budget(3).channel();
+
+        let producer = std::thread::spawn(move || {
+            let mut sender = sender;
+            futures::executor::block_on(async {
+                for i in 0..10 {
+                    sender.send(i).await.unwrap();
+                }
+            })
+        });
+
+        let mut stream = receiver.filter_map(|item| (item == 9).then_some(item));
+
+        // Every poll either finds the final item or yields, having skipped
+        // at most the budget's worth of items.
+        let mut polls = 0;
+        let item = loop {
+            polls += 1;
+            if let Some(item) = stream.next().now_or_never() {
+                break item;
+            }
+        };
+
+        assert_eq!(item, Some(9));
+        assert!(polls >= 4);
+        producer.join().unwrap();
+    }
+}
@@ -0,0 +1,146 @@
+/*!
+Implementation of [`Receiver::with_heartbeat`].
+*/
+
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use futures_util::{
+    stream::{FusedStream, Stream, StreamExt},
+    FutureExt,
+};
+
+use crate::{timer::Sleep, Receiver};
+
+impl<T> Receiver<T> {
+    /// Convert this receiver into a stream that interleaves received items
+    /// with periodic heartbeats.
+    ///
+    /// The stream yields `Some(item)` whenever an item is received, and
+    /// `None` whenever `interval` elapses without an item being received. It
+    /// ends only when the sender disconnects. This is useful for liveness
+    /// monitoring over channels that may go quiet for long periods.
+    ///
+    /// The receiver is always checked before the heartbeat timer, so an item
+    /// that arrives at the same time as a heartbeat is never lost; it's
+    /// delivered instead of the heartbeat.
+    pub fn with_heartbeat(self, interval: Duration) -> Heartbeat<T> {
+        Heartbeat {
+            receiver: self,
+            interval,
+            sleep: None,
+        }
+    }
+}
+
+/// Stream for the [`Receiver::with_heartbeat`] method.
+#[must_use = "streams do nothing unless polled"]
+#[derive(Debug)]
+pub struct Heartbeat<T> {
+    receiver: Receiver<T>,
+    interval: Duration,
+
+    // The timer for the next heartbeat. This is created lazily, so that the
+    // first interval starts when the stream is first polled.
+    sleep: Option<Sleep>,
+}
+
+impl<T> Heartbeat<T> {
+    /// Get a reference to the underlying receiver.
+    #[inline]
+    #[must_use]
+    pub fn get_ref(&self) -> &Receiver<T> {
+        &self.receiver
+    }
+
+    /// Consume this stream, returning the underlying receiver.
+    #[inline]
+    #[must_use]
+    pub fn into_inner(self) -> Receiver<T> {
+        self.receiver
+    }
+}
+
+impl<T> Stream for Heartbeat<T> {
+    type Item = Option<T>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Option<T>>> {
+        let this = &mut *self;
+
+        if let Poll::Ready(item) = this.receiver.poll_next_unpin(cx) {
+            this.sleep = None;
+            return Poll::Ready(item.map(Some));
+        }
+
+        let interval = this.interval;
+        let sleep = this.sleep.get_or_insert_with(|| Sleep::after(interval));
+        futures_util::ready!(sleep.poll_unpin(cx));
+
+        this.sleep = None;
+        Poll::Ready(Some(None))
+    }
+}
+
+impl<T> FusedStream for Heartbeat<T> {
+    #[inline]
+    fn is_terminated(&self) -> bool {
+        self.receiver.is_terminated()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use futures::{FutureExt, StreamExt};
+
+    use crate::channel;
+
+    #[tokio::test]
+    async fn heartbeats_between_items() {
+        let (mut sender, receiver) = channel();
+        let mut stream = receiver.with_heartbeat(Duration::from_millis(20));
+
+        let sender_task = tokio::task::spawn(async move {
+            sender.send(1).await.unwrap();
+            tokio::time::sleep(Duration::from_millis(70)).await;
+            sender.send(2).await.unwrap();
+        });
+
+        assert_eq!(stream.next().await, Some(Some(1)));
+
+        let mut heartbeats = 0;
+        let item = loop {
+            match stream.next().await {
+                Some(None) => heartbeats += 1,
+                Some(Some(item)) => break item,
+                None => panic!("stream ended early"),
+            }
+        };
+
+        assert_eq!(item, 2);
+        assert!(heartbeats >= 2, "only got {heartbeats} heartbeats");
+
+        sender_task.await.unwrap();
+        assert_eq!(stream.next().await, None);
+    }
+
+    #[tokio::test]
+    async fn item_wins_over_heartbeat() {
+        let (mut sender, receiver) = channel();
+        let mut stream = receiver.with_heartbeat(Duration::from_millis(10));
+
+        // Start the heartbeat timer, then let it expire while an item is
+        // already waiting
+        assert_eq!(stream.next().now_or_never(), None);
+        let sender_task = tokio::task::spawn(async move { sender.send(1).await });
+        tokio::task::yield_now().await;
+        std::thread::sleep(Duration::from_millis(30));
+
+        assert_eq!(stream.next().await, Some(Some(1)));
+        sender_task.await.unwrap().unwrap();
+    }
+}
@@ -0,0 +1,157 @@
+/*!
+Implementation of [`Receiver::map_while`].
+*/
+
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_util::stream::{FusedStream, Stream, StreamExt};
+
+use crate::Receiver;
+
+impl<T> Receiver<T> {
+    /// Convert this receiver into a stream that yields `f(item)` for each
+    /// received item, ending as soon as `f` returns `None`.
+    ///
+    /// This is like [`Iterator::map_while`], but it also closes the channel
+    /// when it ends: the item for which `f` returned `None` (the sentinel) is
+    /// received normally, so the send that delivered it succeeds, but the
+    /// receiver is then dropped, so any subsequent send fails with a
+    /// [`SendError`][crate::SendError]. This lets a sentinel value stop the
+    /// producer as well as the consumer. To keep the channel open instead,
+    /// use [`scan`][Receiver::scan].
+    ///
+    /// If the sender disconnects first, the stream simply ends.
+    pub fn map_while<U, F>(self, f: F) -> MapWhile<T, F>
+    where
+        F: FnMut(T) -> Option<U>,
+    {
+        MapWhile {
+            receiver: Some(self),
+            f,
+        }
+    }
+}
+
+/// Stream for the [`Receiver::map_while`] method.
+#[must_use = "streams do nothing unless polled"]
+#[derive(Debug)]
+pub struct MapWhile<T, F> {
+    // This is `None` after `f` returned `None`, which closed the channel.
+    receiver: Option<Receiver<T>>,
+    f: F,
+}
+
+impl<T, F> MapWhile<T, F> {
+    /// Get a reference to the underlying receiver, or `None` if the stream
+    /// has already ended on a sentinel and closed the channel.
+    #[inline]
+    #[must_use]
+    pub fn get_ref(&self) -> Option<&Receiver<T>> {
+        self.receiver.as_ref()
+    }
+
+    /// Consume this stream, returning the underlying receiver, or `None` if
+    /// the stream has already ended on a sentinel and closed the channel.
+    #[inline]
+    #[must_use]
+    pub fn into_inner(self) -> Option<Receiver<T>> {
+        self.receiver
+    }
+}
+
+// The closure is never pinned.
+impl<T, F> Unpin for MapWhile<T, F> {}
+
+impl<T, U, F> Stream for MapWhile<T, F>
+where
+    F: FnMut(T) -> Option<U>,
+{
+    type Item = U;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<U>> {
+        let Some(receiver) = &mut self.receiver else { return Poll::Ready(None) };
+        let Some(item) = futures_util::ready!(receiver.poll_next_unpin(cx)) else {
+            return Poll::Ready(None);
+        };
+
+        let mapped = (self.f)(item);
+
+        if mapped.is_none() {
+            self.receiver = None;
+        }
+
+        Poll::Ready(mapped)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match &self.receiver {
+            Some(receiver) => (0, receiver.size_hint().1),
+            None => (0, Some(0)),
+        }
+    }
+}
+
+impl<T, U, F> FusedStream for MapWhile<T, F>
+where
+    F: FnMut(T) -> Option<U>,
+{
+    #[inline]
+    fn is_terminated(&self) -> bool {
+        self.receiver
+            .as_ref()
+            .is_none_or(|receiver| receiver.is_terminated())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cool_asserts::assert_matches;
+    use futures::{stream::FusedStream, StreamExt};
+
+    use crate::{channel, SendError};
+
+    #[tokio::test]
+    async fn sentinel_closes_channel() {
+        let (mut sender, receiver) = channel();
+
+        let sender_task = tokio::task::spawn(async move {
+            for i in [1, 2, 3, 0] {
+                sender.send(i).await.unwrap();
+            }
+
+            sender.send(4).await
+        });
+
+        let mut stream = receiver.map_while(|item| (item != 0).then_some(item * 10));
+        let items: Vec<i32> = (&mut stream).collect().await;
+        assert_eq!(items, [10, 20, 30]);
+        assert!(stream.is_terminated());
+        assert!(stream.into_inner().is_none());
+
+        assert_matches!(sender_task.await.unwrap(), Err(SendError(4)));
+    }
+
+    #[tokio::test]
+    async fn sender_disconnect_ends_stream() {
+        let (mut sender, receiver) = channel();
+
+        let sender_task = tokio::task::spawn(async move {
+            for i in [1, 2, 3] {
+                sender.send(i).await.unwrap();
+            }
+        });
+
+        let mut stream = receiver.map_while(|item| (item != 0).then_some(item * 10));
+        let items: Vec<i32> = (&mut stream).collect().await;
+        assert_eq!(items, [10, 20, 30]);
+        sender_task.await.unwrap();
+
+        // The channel wasn't closed by a sentinel, so the receiver survives
+        assert!(stream.is_terminated());
+        assert!(stream.into_inner().is_some());
+    }
+}
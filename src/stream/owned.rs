@@ -0,0 +1,186 @@
+/*!
+Implementation of [`Receiver::into_stream_owning`].
+*/
+
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_util::stream::{FusedStream, Stream, StreamExt};
+
+use crate::Receiver;
+
+impl<T> Receiver<T> {
+    /// Convert this receiver into an [`OwnedReceiverStream`], which counts
+    /// the items it yields and can still report on the channel while it's
+    /// driven by stream combinators.
+    #[inline]
+    pub fn into_stream_owning(self) -> OwnedReceiverStream<T> {
+        OwnedReceiverStream {
+            receiver: self,
+            received: 0,
+            terminated: false,
+        }
+    }
+}
+
+/// A stream of received items that tracks how many were received, created by
+/// the [`Receiver::into_stream_owning`] method.
+///
+/// Most [`StreamExt`] combinators consume the stream they adapt, which makes
+/// it impossible to check on the channel from outside a pipeline. To keep
+/// access to the channel, build the pipeline on
+/// [`by_ref`][StreamExt::by_ref], or reach the wrapper through the
+/// combinators' own `get_ref` methods; either way,
+/// [`is_connected`][OwnedReceiverStream::is_connected] and
+/// [`received_count`][OwnedReceiverStream::received_count] are available
+/// before, during, and after the pipeline runs.
+///
+/// ```
+/// # futures::executor::block_on(async move {
+/// use futures::{future::join, StreamExt};
+///
+/// let (mut sender, receiver) = handoff::channel();
+/// let mut stream = receiver.into_stream_owning();
+///
+/// let send_task = async move {
+///     for i in 0..3 {
+///         sender.send(i).await.unwrap();
+///     }
+/// };
+///
+/// let recv_task = stream.by_ref().map(|i| i * 10).take(3).collect::<Vec<_>>();
+/// let ((), items) = join(send_task, recv_task).await;
+///
+/// assert_eq!(items, [0, 10, 20]);
+/// assert_eq!(stream.received_count(), 3);
+/// # });
+/// ```
+#[must_use = "streams do nothing unless polled"]
+#[derive(Debug)]
+pub struct OwnedReceiverStream<T> {
+    receiver: Receiver<T>,
+
+    // The number of items this stream has yielded
+    received: u64,
+
+    // True once the stream has returned `None`
+    terminated: bool,
+}
+
+impl<T> OwnedReceiverStream<T> {
+    /// Check if the sender is still connected. See
+    /// [`Receiver::is_connected`] for details.
+    #[inline]
+    #[must_use = "checking the connection has no effect on its own"]
+    pub fn is_connected(&self) -> bool {
+        self.receiver.is_connected()
+    }
+
+    /// The number of items this stream has yielded so far.
+    #[inline]
+    #[must_use]
+    pub fn received_count(&self) -> u64 {
+        self.received
+    }
+
+    /// Get a reference to the underlying receiver.
+    #[inline]
+    #[must_use]
+    pub fn get_ref(&self) -> &Receiver<T> {
+        &self.receiver
+    }
+
+    /// Get a mutable reference to the underlying receiver. Items received
+    /// directly through it aren't counted by
+    /// [`received_count`][OwnedReceiverStream::received_count].
+    #[inline]
+    #[must_use]
+    pub fn get_mut(&mut self) -> &mut Receiver<T> {
+        &mut self.receiver
+    }
+
+    /// Unwrap this stream, returning the underlying receiver.
+    #[inline]
+    #[must_use]
+    pub fn into_inner(self) -> Receiver<T> {
+        self.receiver
+    }
+}
+
+impl<T> Stream for OwnedReceiverStream<T> {
+    type Item = T;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let this = &mut *self;
+        let item = futures_util::ready!(this.receiver.poll_next_unpin(cx));
+
+        match item {
+            Some(_) => this.received += 1,
+            None => this.terminated = true,
+        }
+
+        Poll::Ready(item)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.receiver.size_hint()
+    }
+}
+
+impl<T> FusedStream for OwnedReceiverStream<T> {
+    #[inline]
+    fn is_terminated(&self) -> bool {
+        self.terminated
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{future::join, StreamExt};
+
+    use crate::channel;
+
+    #[tokio::test]
+    async fn introspection_through_a_pipeline() {
+        let (mut sender, receiver) = channel();
+        let mut stream = receiver.into_stream_owning();
+
+        let send_task = async move {
+            for i in 0..10 {
+                sender.send(i).await.unwrap();
+            }
+        };
+
+        let recv_task = stream
+            .by_ref()
+            .filter(|i| std::future::ready(i % 2 == 0))
+            .map(|i| i * i)
+            .collect::<Vec<_>>();
+
+        let ((), squares) = join(send_task, recv_task).await;
+
+        assert_eq!(squares, [0, 4, 16, 36, 64]);
+        assert_eq!(stream.received_count(), 10);
+        assert!(!stream.is_connected());
+        assert!(futures::stream::FusedStream::is_terminated(&stream));
+    }
+
+    #[tokio::test]
+    async fn reached_through_combinators() {
+        let (mut sender, receiver) = channel();
+        let mut pipeline = receiver.into_stream_owning().map(|i: i32| i + 1);
+
+        let (sent, item) = join(sender.send(1), pipeline.next()).await;
+        sent.unwrap();
+        assert_eq!(item, Some(2));
+
+        assert_eq!(pipeline.get_ref().received_count(), 1);
+        assert!(pipeline.get_ref().is_connected());
+
+        drop(sender);
+        assert!(!pipeline.get_ref().is_connected());
+    }
+}
@@ -0,0 +1,181 @@
+/*!
+Implementation of [`Receiver::into_peekable`].
+*/
+
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_util::stream::{FusedStream, Stream, StreamExt};
+
+use crate::{PeekFut, Receiver, RecvFut};
+
+impl<T> Receiver<T> {
+    /// Convert this receiver into a [`PeekableReceiver`], which combines
+    /// [`peek`][Receiver::peek] with stream iteration.
+    ///
+    /// Unlike [`StreamExt::peekable`], this doesn't need to be pinned, and
+    /// peeking keeps the sender blocked until the peeked item is received.
+    #[inline]
+    pub fn into_peekable(self) -> PeekableReceiver<T> {
+        PeekableReceiver { receiver: self }
+    }
+}
+
+/// A receiver with one item of lookahead, created by the
+/// [`Receiver::into_peekable`] method.
+///
+/// This is useful for parsers that need to inspect the next item before
+/// deciding whether to consume it. While an item is being peeked, its sender
+/// remains blocked.
+#[must_use = "streams do nothing unless polled"]
+#[derive(Debug)]
+pub struct PeekableReceiver<T> {
+    receiver: Receiver<T>,
+}
+
+impl<T> PeekableReceiver<T> {
+    /// Wait for the next item and return a reference to it, without receiving
+    /// it. Returns `None` if the sender disconnected. See
+    /// [`Receiver::peek`] for details.
+    #[inline]
+    pub fn peek(&mut self) -> PeekFut<'_, T> {
+        self.receiver.peek()
+    }
+
+    /// Receive the next item, which is the previously peeked item, if any.
+    /// Returns `None` if the sender disconnected.
+    ///
+    /// This shadows [`StreamExt::next`], which behaves identically.
+    #[inline]
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> RecvFut<'_, T> {
+        self.receiver.recv()
+    }
+
+    /// Check if the sender is still connected. See
+    /// [`Receiver::is_connected`] for details.
+    #[inline]
+    #[must_use]
+    pub fn is_connected(&self) -> bool {
+        self.receiver.is_connected()
+    }
+
+    /// Get a reference to the underlying receiver.
+    #[inline]
+    #[must_use]
+    pub fn get_ref(&self) -> &Receiver<T> {
+        &self.receiver
+    }
+
+    /// Consume this wrapper, returning the underlying receiver. A peeked item
+    /// is preserved, and will be returned by the receiver's next receive.
+    #[inline]
+    #[must_use]
+    pub fn into_inner(self) -> Receiver<T> {
+        self.receiver
+    }
+}
+
+impl<T> Stream for PeekableReceiver<T> {
+    type Item = T;
+
+    #[inline]
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        self.receiver.poll_next_unpin(cx)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.receiver.size_hint()
+    }
+}
+
+impl<T> FusedStream for PeekableReceiver<T> {
+    #[inline]
+    fn is_terminated(&self) -> bool {
+        self.receiver.is_terminated()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        pin::pin,
+        sync::Arc,
+        task::{Context, Poll, Waker},
+    };
+
+    use futures::{stream::FusedStream, FutureExt, StreamExt};
+
+    use crate::{channel, testing::CountingWaker};
+
+    #[tokio::test]
+    async fn peek_then_next() {
+        let (mut sender, receiver) = channel();
+        let mut receiver = receiver.into_peekable();
+
+        let sender_task = tokio::task::spawn(async move {
+            sender.send(1).await.unwrap();
+            sender.send(2).await.unwrap();
+        });
+
+        assert_eq!(receiver.peek().await, Some(&1));
+        assert_eq!(receiver.peek().await, Some(&1));
+        assert_eq!(receiver.next().await, Some(1));
+        assert_eq!(receiver.peek().await, Some(&2));
+
+        let rest: Vec<i32> = (&mut receiver).collect().await;
+        assert_eq!(rest, [2]);
+        assert_eq!(receiver.peek().await, None);
+        assert!(receiver.is_terminated());
+
+        sender_task.await.unwrap();
+    }
+
+    #[test]
+    fn peek_holds_sender_without_extra_wakes() {
+        let (mut sender, mut receiver) = channel();
+
+        let sender_waker = Arc::new(CountingWaker::default());
+        let sender_waker_handle = Waker::from(sender_waker.clone());
+        let mut sender_cx = Context::from_waker(&sender_waker_handle);
+        let mut recv_cx = Context::from_waker(Waker::noop());
+
+        let mut send = pin!(sender.send(10));
+        assert!(send.poll_unpin(&mut sender_cx).is_pending());
+
+        assert_eq!(receiver.poll_peek(&mut recv_cx), Poll::Ready(Some(&10)));
+
+        // The peek took the item, but the sender shouldn't have been woken,
+        // and should remain blocked even if spuriously polled.
+        assert_eq!(sender_waker.count(), 0);
+        assert!(send.poll_unpin(&mut sender_cx).is_pending());
+
+        assert_eq!(
+            receiver.poll_next_unpin(&mut recv_cx),
+            Poll::Ready(Some(10))
+        );
+        assert_eq!(sender_waker.count(), 1);
+        assert!(matches!(
+            send.poll_unpin(&mut sender_cx),
+            Poll::Ready(Ok(()))
+        ));
+    }
+
+    #[tokio::test]
+    async fn peeked_item_survives_disconnect() {
+        let (mut sender, mut receiver) = channel();
+
+        let sender_task = tokio::task::spawn(async move { sender.send(5).await });
+
+        assert_eq!(receiver.peek().await, Some(&5));
+        sender_task.abort();
+        let _ = sender_task.await;
+
+        assert!(!receiver.is_connected());
+        assert_eq!(receiver.recv().await, Some(5));
+        assert_eq!(receiver.recv().await, None);
+    }
+}
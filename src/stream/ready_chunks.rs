@@ -0,0 +1,164 @@
+/*!
+Implementation of [`Receiver::ready_chunks`].
+*/
+
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_util::{
+    ready,
+    stream::{FusedStream, Stream, StreamExt},
+};
+
+use crate::Receiver;
+
+impl<T> Receiver<T> {
+    /// Convert this receiver into a stream of batches, each containing every
+    /// item that's ready to be received without waiting, up to `max` items.
+    ///
+    /// This is like [`StreamExt::ready_chunks`]. Each batch waits for its
+    /// first item, then keeps receiving for as long as the sender has another
+    /// item staged by the time the previous one was taken. As soon as the
+    /// sender isn't ready, the batch is yielded, so unlike `chunks_timeout`,
+    /// this never delays an item. Empty batches are never yielded.
+    ///
+    /// Because the sender has to be woken and polled again between items,
+    /// batches larger than 1 mostly occur when the sender runs on another
+    /// thread, or when it's polled from within its waker.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max` is zero.
+    pub fn ready_chunks(self, max: usize) -> ReadyChunks<T> {
+        assert!(max > 0, "chunk size must be nonzero");
+
+        ReadyChunks {
+            receiver: self,
+            max,
+        }
+    }
+}
+
+/// Stream for the [`Receiver::ready_chunks`] method.
+#[must_use = "streams do nothing unless polled"]
+#[derive(Debug)]
+pub struct ReadyChunks<T> {
+    receiver: Receiver<T>,
+    max: usize,
+}
+
+impl<T> ReadyChunks<T> {
+    /// Check if the sender is still connected. See
+    /// [`Receiver::is_connected`] for details.
+    #[inline]
+    #[must_use]
+    pub fn is_connected(&self) -> bool {
+        self.receiver.is_connected()
+    }
+
+    /// Get a reference to the underlying receiver.
+    #[inline]
+    #[must_use]
+    pub fn get_ref(&self) -> &Receiver<T> {
+        &self.receiver
+    }
+
+    /// Consume this stream, returning the underlying receiver.
+    #[inline]
+    #[must_use]
+    pub fn into_inner(self) -> Receiver<T> {
+        self.receiver
+    }
+}
+
+impl<T> Stream for ReadyChunks<T> {
+    type Item = Vec<T>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Vec<T>>> {
+        let this = &mut *self;
+
+        let Some(first) = ready!(this.receiver.poll_next_unpin(cx)) else {
+            return Poll::Ready(None);
+        };
+
+        let mut batch = Vec::with_capacity(this.max);
+        batch.push(first);
+
+        // A disconnect after the first item is reported by the next poll
+        while batch.len() < this.max {
+            match this.receiver.poll_next_unpin(cx) {
+                Poll::Ready(Some(item)) => batch.push(item),
+                Poll::Ready(None) | Poll::Pending => break,
+            }
+        }
+
+        Poll::Ready(Some(batch))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (lower, upper) = self.receiver.size_hint();
+        (lower.min(1), upper)
+    }
+}
+
+impl<T> FusedStream for ReadyChunks<T> {
+    #[inline]
+    fn is_terminated(&self) -> bool {
+        self.receiver.is_terminated()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::task::{Context, Poll, Waker};
+
+    use futures::StreamExt;
+
+    use crate::{channel, testing::InlineWaker};
+
+    #[test]
+    fn burst_is_collected_into_one_chunk() {
+        let (mut sender, receiver) = channel();
+
+        // This also stages the first item
+        let sender_task = InlineWaker::spawn(async move {
+            for i in 0..7 {
+                sender.send(i).await.unwrap();
+            }
+        });
+
+        let mut chunks = receiver.ready_chunks(5);
+        let mut cx = Context::from_waker(Waker::noop());
+
+        assert_eq!(
+            chunks.poll_next_unpin(&mut cx),
+            Poll::Ready(Some(vec![0, 1, 2, 3, 4]))
+        );
+        assert_eq!(
+            chunks.poll_next_unpin(&mut cx),
+            Poll::Ready(Some(vec![5, 6]))
+        );
+        assert_eq!(chunks.poll_next_unpin(&mut cx), Poll::Ready(None));
+        assert!(sender_task.is_finished());
+    }
+
+    #[tokio::test]
+    async fn yields_without_waiting_for_more() {
+        let (mut sender, receiver) = channel();
+
+        let sender_task = tokio::task::spawn(async move {
+            for i in 0..3 {
+                sender.send(i).await.unwrap();
+            }
+        });
+
+        // The sender is only polled between batches, so each one contains a
+        // single item.
+        let chunks: Vec<Vec<i32>> = receiver.ready_chunks(10).collect().await;
+        assert_eq!(chunks, [vec![0], vec![1], vec![2]]);
+        sender_task.await.unwrap();
+    }
+}
@@ -0,0 +1,186 @@
+/*!
+Implementation of [`Receiver::into_reconnecting`].
+*/
+
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_util::stream::{Stream, StreamExt};
+
+use crate::Receiver;
+
+impl<T> Receiver<T> {
+    /// Convert this receiver into a [`ReconnectingReceiver`], which can be
+    /// given a replacement receiver to switch to when the sender disconnects.
+    #[inline]
+    pub fn into_reconnecting(self) -> ReconnectingReceiver<T> {
+        ReconnectingReceiver {
+            receiver: self,
+            replacement: None,
+            reconnections: 0,
+        }
+    }
+}
+
+/// A receiver that continues from a replacement receiver when its sender
+/// disconnects, created by the [`Receiver::into_reconnecting`] method.
+///
+/// This is useful for supervised producers that may be restarted: give the
+/// stream the receiving half of the restarted producer's channel with
+/// [`rebind`][ReconnectingReceiver::rebind], and the consumer sees a single
+/// uninterrupted stream. The switch only happens once the current receiver
+/// is exhausted, including any [peeked][Receiver::peek] item, so no items are
+/// lost or duplicated. The stream only ends if the sender disconnects while
+/// no replacement is available; after that, it can still be rebound and
+/// polled again.
+#[must_use = "streams do nothing unless polled"]
+#[derive(Debug)]
+pub struct ReconnectingReceiver<T> {
+    receiver: Receiver<T>,
+
+    // The receiver to switch to when `receiver` is exhausted
+    replacement: Option<Receiver<T>>,
+
+    reconnections: u64,
+}
+
+impl<T> ReconnectingReceiver<T> {
+    /// Supply a receiver to switch to once the current one's sender
+    /// disconnects. If there's already a pending replacement, it's replaced
+    /// and returned.
+    #[inline]
+    pub fn rebind(&mut self, receiver: Receiver<T>) -> Option<Receiver<T>> {
+        self.replacement.replace(receiver)
+    }
+
+    /// Check if there's a pending replacement receiver.
+    #[inline]
+    #[must_use]
+    pub fn has_replacement(&self) -> bool {
+        self.replacement.is_some()
+    }
+
+    /// The number of times this stream has switched to a replacement
+    /// receiver.
+    #[inline]
+    #[must_use]
+    pub fn reconnections(&self) -> u64 {
+        self.reconnections
+    }
+
+    /// Check if the current sender is still connected. See
+    /// [`Receiver::is_connected`] for details.
+    #[inline]
+    #[must_use]
+    pub fn is_connected(&self) -> bool {
+        self.receiver.is_connected()
+    }
+
+    /// Get a reference to the current receiver.
+    #[inline]
+    #[must_use]
+    pub fn get_ref(&self) -> &Receiver<T> {
+        &self.receiver
+    }
+
+    /// Consume this wrapper, returning the current receiver, along with the
+    /// pending replacement, if any.
+    #[inline]
+    #[must_use]
+    pub fn into_inner(self) -> (Receiver<T>, Option<Receiver<T>>) {
+        (self.receiver, self.replacement)
+    }
+}
+
+impl<T> Stream for ReconnectingReceiver<T> {
+    type Item = T;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let this = &mut *self;
+
+        loop {
+            if let Some(item) = futures_util::ready!(this.receiver.poll_next_unpin(cx)) {
+                return Poll::Ready(Some(item));
+            }
+
+            let Some(replacement) = this.replacement.take() else { return Poll::Ready(None) };
+            this.receiver = replacement;
+            this.reconnections += 1;
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.replacement {
+            Some(_) => (self.receiver.size_hint().0, None),
+            None => self.receiver.size_hint(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+
+    use crate::channel;
+
+    #[tokio::test]
+    async fn producer_restart() {
+        let (mut sender1, receiver1) = channel();
+        let (mut sender2, receiver2) = channel();
+
+        let first_producer = tokio::task::spawn(async move {
+            for i in 0..3 {
+                sender1.send(i).await.unwrap();
+            }
+        });
+
+        // The restarted producer is already blocked sending its first item
+        // when the first producer exits.
+        let second_producer = tokio::task::spawn(async move {
+            for i in 3..6 {
+                sender2.send(i).await.unwrap();
+            }
+        });
+
+        let mut stream = receiver1.into_reconnecting();
+        assert!(stream.rebind(receiver2).is_none());
+
+        let items: Vec<i32> = (&mut stream).collect().await;
+        assert_eq!(items, [0, 1, 2, 3, 4, 5]);
+        assert_eq!(stream.reconnections(), 1);
+        assert!(!stream.has_replacement());
+
+        first_producer.await.unwrap();
+        second_producer.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn peeked_item_survives_rebind() {
+        let (mut sender1, mut receiver1) = channel();
+        let (sender2, receiver2) = channel::<i32>();
+
+        let producer = tokio::task::spawn(async move { sender1.send(1).await });
+        assert_eq!(receiver1.peek().await, Some(&1));
+        producer.abort();
+        let _ = producer.await;
+
+        let mut stream = receiver1.into_reconnecting();
+        stream.rebind(receiver2);
+        drop(sender2);
+
+        assert_eq!(stream.next().await, Some(1));
+        assert_eq!(stream.next().await, None);
+        assert_eq!(stream.reconnections(), 1);
+
+        // After ending, the stream can be rebound and resumed
+        let (mut sender3, receiver3) = channel();
+        stream.rebind(receiver3);
+
+        let producer = tokio::task::spawn(async move { sender3.send(7).await });
+        assert_eq!(stream.next().await, Some(7));
+        producer.await.unwrap().unwrap();
+    }
+}
@@ -0,0 +1,190 @@
+/*!
+Implementation of [`Receiver::scan`].
+*/
+
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_util::{
+    ready,
+    stream::{FusedStream, Stream, StreamExt},
+};
+
+use crate::Receiver;
+
+impl<T> Receiver<T> {
+    /// Convert this receiver into a stream that threads a running state
+    /// through the received items. For each item, `f` is called with a
+    /// mutable reference to the state, which starts as `init`; the stream
+    /// yields each value `f` returns, and ends as soon as `f` returns `None`.
+    ///
+    /// This is like [`StreamExt::scan`], but with a synchronous closure, and
+    /// the returned [`Scan`] keeps the receiver's channel methods, like
+    /// [`is_connected`][Scan::is_connected]. Unlike
+    /// [`map_while`][Receiver::map_while], ending the stream doesn't close
+    /// the channel; the receiver and the final state can be recovered with
+    /// [`into_inner`][Scan::into_inner].
+    ///
+    /// If the sender disconnects first, the stream simply ends.
+    pub fn scan<St, U, F>(self, init: St, f: F) -> Scan<T, St, F>
+    where
+        F: FnMut(&mut St, T) -> Option<U>,
+    {
+        Scan {
+            receiver: self,
+            state: init,
+            f,
+            done: false,
+        }
+    }
+}
+
+/// Stream for the [`Receiver::scan`] method.
+#[must_use = "streams do nothing unless polled"]
+#[derive(Debug)]
+pub struct Scan<T, St, F> {
+    receiver: Receiver<T>,
+    state: St,
+    f: F,
+
+    // True once `f` returned `None`.
+    done: bool,
+}
+
+impl<T, St, F> Scan<T, St, F> {
+    /// Check if the sender is still connected. See
+    /// [`Receiver::is_connected`] for details.
+    #[inline]
+    #[must_use]
+    pub fn is_connected(&self) -> bool {
+        self.receiver.is_connected()
+    }
+
+    /// Get a reference to the current state.
+    #[inline]
+    #[must_use]
+    pub fn state(&self) -> &St {
+        &self.state
+    }
+
+    /// Get a reference to the underlying receiver.
+    #[inline]
+    #[must_use]
+    pub fn get_ref(&self) -> &Receiver<T> {
+        &self.receiver
+    }
+
+    /// Consume this stream, returning the underlying receiver and the
+    /// current state.
+    #[inline]
+    #[must_use]
+    pub fn into_inner(self) -> (Receiver<T>, St) {
+        (self.receiver, self.state)
+    }
+}
+
+// Neither the state nor the closure is ever pinned.
+impl<T, St, F> Unpin for Scan<T, St, F> {}
+
+impl<T, St, U, F> Stream for Scan<T, St, F>
+where
+    F: FnMut(&mut St, T) -> Option<U>,
+{
+    type Item = U;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<U>> {
+        let this = self.get_mut();
+
+        if this.done {
+            return Poll::Ready(None);
+        }
+
+        let Some(item) = ready!(this.receiver.poll_next_unpin(cx)) else {
+            return Poll::Ready(None);
+        };
+
+        let output = (this.f)(&mut this.state, item);
+        this.done = output.is_none();
+        Poll::Ready(output)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.done {
+            true => (0, Some(0)),
+            false => (0, self.receiver.size_hint().1),
+        }
+    }
+}
+
+impl<T, St, U, F> FusedStream for Scan<T, St, F>
+where
+    F: FnMut(&mut St, T) -> Option<U>,
+{
+    #[inline]
+    fn is_terminated(&self) -> bool {
+        self.done || self.receiver.is_terminated()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{stream::FusedStream, StreamExt};
+
+    use crate::channel;
+
+    #[tokio::test]
+    async fn running_total() {
+        let (mut sender, receiver) = channel();
+
+        let sender_task = tokio::task::spawn(async move {
+            for i in 1..=4 {
+                sender.send(i).await.unwrap();
+            }
+        });
+
+        let mut stream = receiver.scan(0, |total, item| {
+            *total += item;
+            Some(*total)
+        });
+
+        let totals: Vec<i32> = (&mut stream).collect().await;
+        assert_eq!(totals, [1, 3, 6, 10]);
+        assert_eq!(*stream.state(), 10);
+        assert!(!stream.is_connected());
+        assert!(stream.is_terminated());
+
+        sender_task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn early_termination_keeps_channel_open() {
+        let (mut sender, receiver) = channel();
+
+        let sender_task = tokio::task::spawn(async move {
+            for i in 1..=5 {
+                sender.send(i).await.unwrap();
+            }
+        });
+
+        // Accumulate a frame of three items, ending once it is full
+        let mut stream = receiver.scan(Vec::new(), |frame, item| {
+            frame.push(item);
+            (frame.len() < 3).then_some(frame.len())
+        });
+
+        let lengths: Vec<usize> = (&mut stream).collect().await;
+        assert_eq!(lengths, [1, 2]);
+        assert!(stream.is_terminated());
+        assert!(stream.is_connected());
+
+        let (mut receiver, frame) = stream.into_inner();
+        assert_eq!(frame, [1, 2, 3]);
+
+        let rest: Vec<i32> = (&mut receiver).collect().await;
+        assert_eq!(rest, [4, 5]);
+        sender_task.await.unwrap();
+    }
+}
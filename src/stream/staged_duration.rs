@@ -0,0 +1,147 @@
+/*!
+Implementation of [`Receiver::with_staged_duration`].
+*/
+
+use std::{
+    pin::Pin,
+    sync::atomic::Ordering::Relaxed,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use futures_util::stream::{FusedStream, Stream};
+
+use crate::{timer, Receiver};
+
+impl<T> Receiver<T> {
+    /// Convert this receiver into a stream that pairs each item with how long
+    /// it was staged by the sender before the receiver took it.
+    ///
+    /// The duration runs from when the sender made the item available to
+    /// when the receiver took it, so it directly measures how far the
+    /// consumer is lagging behind the producer. An item that was
+    /// [peeked][Receiver::peek] counts as taken when it was peeked.
+    #[inline]
+    pub fn with_staged_duration(self) -> WithStagedDuration<T> {
+        WithStagedDuration { receiver: self }
+    }
+}
+
+/// Stream for the [`Receiver::with_staged_duration`] method.
+#[must_use = "streams do nothing unless polled"]
+#[derive(Debug)]
+pub struct WithStagedDuration<T> {
+    receiver: Receiver<T>,
+}
+
+impl<T> WithStagedDuration<T> {
+    /// Check if the sender is still connected. See
+    /// [`Receiver::is_connected`] for details.
+    #[inline]
+    #[must_use]
+    pub fn is_connected(&self) -> bool {
+        self.receiver.is_connected()
+    }
+
+    /// Get a reference to the underlying receiver.
+    #[inline]
+    #[must_use]
+    pub fn get_ref(&self) -> &Receiver<T> {
+        &self.receiver
+    }
+
+    /// Consume this stream, returning the underlying receiver.
+    #[inline]
+    #[must_use]
+    pub fn into_inner(self) -> Receiver<T> {
+        self.receiver
+    }
+}
+
+impl<T> Stream for WithStagedDuration<T> {
+    type Item = (Duration, T);
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<(Duration, T)>> {
+        let receiver = &mut self.receiver;
+
+        if let Some(item) = receiver.peeked.take() {
+            receiver.release_held_item();
+            return Poll::Ready(Some((receiver.peeked_wait, item)));
+        }
+
+        let Some(lock) = receiver.inner.lock() else { return Poll::Ready(None) };
+
+        // The timestamp must be read while we have the item, since the sender
+        // overwrites it as soon as it stages its next item.
+        lock.poll_recv_with(cx, |slot| {
+            let waited = timer::elapsed_since(lock.staged_at.load(Relaxed));
+            slot.take().map(|item| (waited, item))
+        })
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.receiver.size_hint()
+    }
+}
+
+impl<T> FusedStream for WithStagedDuration<T> {
+    #[inline]
+    fn is_terminated(&self) -> bool {
+        self.receiver.is_terminated()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use futures::StreamExt;
+
+    use crate::channel;
+
+    #[tokio::test]
+    async fn reports_consumer_lag() {
+        let (mut sender, receiver) = channel();
+
+        let sender_task = tokio::task::spawn(async move {
+            sender.send(1).await.unwrap();
+            sender.send(2).await.unwrap();
+        });
+
+        let mut stream = receiver.with_staged_duration();
+
+        // Let the first item wait for a while before taking it
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let (waited, item) = stream.next().await.unwrap();
+        assert_eq!(item, 1);
+        assert!(waited >= Duration::from_millis(50), "{waited:?}");
+        assert!(waited < Duration::from_millis(500), "{waited:?}");
+
+        // The second item is taken as soon as it's staged
+        let (waited, item) = stream.next().await.unwrap();
+        assert_eq!(item, 2);
+        assert!(waited < Duration::from_millis(50), "{waited:?}");
+
+        assert_eq!(stream.next().await, None);
+        sender_task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn peeked_item_measured_at_peek() {
+        let (mut sender, mut receiver) = channel();
+
+        let sender_task = tokio::task::spawn(async move { sender.send(1).await });
+
+        assert_eq!(receiver.peek().await, Some(&1));
+        let mut stream = receiver.with_staged_duration();
+
+        // Holding the peeked item doesn't count as staged time
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let (waited, item) = stream.next().await.unwrap();
+        assert_eq!(item, 1);
+        assert!(waited < Duration::from_millis(50), "{waited:?}");
+
+        sender_task.await.unwrap().unwrap();
+    }
+}
@@ -0,0 +1,163 @@
+/*!
+Implementation of [`Receiver::with_terminator`].
+*/
+
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_util::stream::{FusedStream, Stream, StreamExt};
+
+use crate::Receiver;
+
+impl<T> Receiver<T> {
+    /// Convert this receiver into a stream that yields one final item,
+    /// `make_end()`, when the sender disconnects, and then ends.
+    ///
+    /// This is useful for adapting the channel to consumers that expect an
+    /// explicit end-of-stream token in-band, rather than relying on the
+    /// stream ending. `make_end` is called exactly once, after the last item
+    /// sent through the channel, and only if the sender disconnects; it's
+    /// never called if the stream is dropped first.
+    ///
+    /// ```
+    /// # futures::executor::block_on(async move {
+    /// use futures::{future::join, StreamExt};
+    ///
+    /// let (mut sender, receiver) = handoff::channel();
+    ///
+    /// let send_task = async move {
+    ///     sender.send("hello").await.unwrap();
+    ///     sender.send("world").await.unwrap();
+    /// };
+    ///
+    /// let recv_task = receiver.with_terminator(|| "EOF").collect::<Vec<_>>();
+    /// let ((), items) = join(send_task, recv_task).await;
+    /// assert_eq!(items, ["hello", "world", "EOF"]);
+    /// # });
+    /// ```
+    #[inline]
+    pub fn with_terminator<F>(self, make_end: F) -> WithTerminator<T, F>
+    where
+        F: FnOnce() -> T,
+    {
+        WithTerminator {
+            receiver: self,
+            make_end: Some(make_end),
+        }
+    }
+}
+
+/// Stream for the [`Receiver::with_terminator`] method.
+#[must_use = "streams do nothing unless polled"]
+#[derive(Debug)]
+pub struct WithTerminator<T, F> {
+    receiver: Receiver<T>,
+
+    // This is `None` once the terminator has been yielded.
+    make_end: Option<F>,
+}
+
+impl<T, F> WithTerminator<T, F> {
+    /// Get a reference to the underlying receiver.
+    #[inline]
+    #[must_use]
+    pub fn get_ref(&self) -> &Receiver<T> {
+        &self.receiver
+    }
+
+    /// Consume this stream, returning the underlying receiver.
+    #[inline]
+    #[must_use]
+    pub fn into_inner(self) -> Receiver<T> {
+        self.receiver
+    }
+}
+
+// The closure is never pinned.
+impl<T, F> Unpin for WithTerminator<T, F> {}
+
+impl<T, F> Stream for WithTerminator<T, F>
+where
+    F: FnOnce() -> T,
+{
+    type Item = T;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let this = &mut *self;
+
+        if this.make_end.is_none() {
+            return Poll::Ready(None);
+        }
+
+        match futures_util::ready!(this.receiver.poll_next_unpin(cx)) {
+            Some(item) => Poll::Ready(Some(item)),
+            None => Poll::Ready(this.make_end.take().map(|make_end| make_end())),
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (lower, upper) = self.receiver.size_hint();
+        let end = self.make_end.is_some() as usize;
+
+        (
+            lower.saturating_add(end),
+            upper.and_then(|upper| upper.checked_add(end)),
+        )
+    }
+}
+
+impl<T, F> FusedStream for WithTerminator<T, F>
+where
+    F: FnOnce() -> T,
+{
+    #[inline]
+    fn is_terminated(&self) -> bool {
+        self.make_end.is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use futures::{stream::FusedStream, FutureExt, StreamExt};
+
+    use crate::channel;
+
+    #[tokio::test]
+    async fn terminator_follows_last_item() {
+        let (mut sender, receiver) = channel();
+        let calls = Cell::new(0);
+
+        let sender_task = tokio::task::spawn(async move {
+            for i in 1..=3 {
+                sender.send(i).await.unwrap();
+            }
+        });
+
+        let mut stream = receiver.with_terminator(|| {
+            calls.set(calls.get() + 1);
+            -1
+        });
+
+        let items: Vec<i32> = (&mut stream).collect().await;
+        sender_task.await.unwrap();
+
+        assert_eq!(items, [1, 2, 3, -1]);
+        assert!(stream.is_terminated());
+        assert_eq!(stream.next().await, None);
+        assert_eq!(calls.get(), 1);
+    }
+
+    #[test]
+    fn no_terminator_while_connected() {
+        let (_sender, receiver) = channel::<i32>();
+        let mut stream = receiver.with_terminator(|| panic!("the sender is still connected"));
+
+        assert_eq!(stream.next().now_or_never(), None);
+        assert!(!stream.is_terminated());
+    }
+}
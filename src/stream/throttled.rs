@@ -0,0 +1,130 @@
+/*!
+Implementation of [`Receiver::throttled`].
+*/
+
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use futures_util::{
+    stream::{FusedStream, Stream, StreamExt},
+    FutureExt,
+};
+
+use crate::{timer::Sleep, Receiver};
+
+impl<T> Receiver<T> {
+    /// Convert this receiver into a rate-limited stream, which takes at most
+    /// one item per `every` interval.
+    ///
+    /// The stream doesn't take the next item from the sender until the
+    /// interval has elapsed since the previous item was taken. Because the
+    /// channel is unbuffered, this keeps the sender blocked in the meantime,
+    /// naturally rate-limiting it without dropping any data.
+    pub fn throttled(self, every: Duration) -> Throttled<T> {
+        Throttled {
+            receiver: self,
+            every,
+            sleep: None,
+        }
+    }
+}
+
+/// Stream for the [`Receiver::throttled`] method.
+#[must_use = "streams do nothing unless polled"]
+#[derive(Debug)]
+pub struct Throttled<T> {
+    receiver: Receiver<T>,
+    every: Duration,
+
+    // When this is `Some`, we're waiting for the interval to elapse before we
+    // take the next item.
+    sleep: Option<Sleep>,
+}
+
+impl<T> Throttled<T> {
+    /// Get a reference to the underlying receiver.
+    #[inline]
+    #[must_use]
+    pub fn get_ref(&self) -> &Receiver<T> {
+        &self.receiver
+    }
+
+    /// Consume this stream, returning the underlying receiver.
+    #[inline]
+    #[must_use]
+    pub fn into_inner(self) -> Receiver<T> {
+        self.receiver
+    }
+}
+
+impl<T> Stream for Throttled<T> {
+    type Item = T;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        if let Some(sleep) = &mut self.sleep {
+            futures_util::ready!(sleep.poll_unpin(cx));
+            self.sleep = None;
+        }
+
+        let item = futures_util::ready!(self.receiver.poll_next_unpin(cx));
+
+        if item.is_some() {
+            self.sleep = Some(Sleep::after(self.every));
+        }
+
+        Poll::Ready(item)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.receiver.size_hint()
+    }
+}
+
+impl<T> FusedStream for Throttled<T> {
+    #[inline]
+    fn is_terminated(&self) -> bool {
+        self.receiver.is_terminated()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use futures::StreamExt;
+
+    use crate::channel;
+
+    #[tokio::test]
+    async fn throttled_spacing() {
+        let (mut sender, receiver) = channel();
+        let every = Duration::from_millis(30);
+
+        let sender_task = tokio::task::spawn(async move {
+            for i in 0..4 {
+                sender.send(i).await.unwrap();
+            }
+        });
+
+        let mut throttled = receiver.throttled(every);
+        let mut stamps = Vec::new();
+
+        while let Some(item) = throttled.next().await {
+            stamps.push((item, Instant::now()));
+        }
+
+        sender_task.await.unwrap();
+
+        let items: Vec<i32> = stamps.iter().map(|&(item, _)| item).collect();
+        assert_eq!(items, [0, 1, 2, 3]);
+
+        for pair in stamps.windows(2) {
+            let gap = pair[1].1 - pair[0].1;
+            assert!(gap >= every * 9 / 10, "items were only {gap:?} apart");
+        }
+    }
+}
@@ -0,0 +1,197 @@
+/*!
+Implementation of [`zip`].
+*/
+
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_util::stream::{FusedStream, Stream};
+
+use crate::Receiver;
+
+/// Combine two receivers into a stream of pairs, consuming them in
+/// lockstep.
+///
+/// A pair is only yielded once both receivers have an item available. While
+/// waiting for the other side, the item from the side that's ahead is
+/// [peeked][Receiver::peek] rather than received, so its sender stays
+/// blocked: whichever producer is ahead is held back by the same rendezvous
+/// backpressure as an ordinary send. The stream ends when either sender
+/// disconnects; an item that was already peeked from the other receiver isn't
+/// lost, and can still be received after [`into_inner`][Zip::into_inner].
+///
+/// ```
+/// # futures::executor::block_on(async move {
+/// use futures::{future::join3, StreamExt};
+///
+/// let (mut numbers, number_receiver) = handoff::channel();
+/// let (mut letters, letter_receiver) = handoff::channel();
+///
+/// let send_numbers = async move {
+///     numbers.send(1).await.unwrap();
+///     numbers.send(2).await.unwrap();
+/// };
+///
+/// let send_letters = async move {
+///     letters.send('a').await.unwrap();
+///     letters.send('b').await.unwrap();
+///     letters.send('c').await.unwrap();
+/// };
+///
+/// let pairs = handoff::zip(number_receiver, letter_receiver).collect::<Vec<_>>();
+/// let (_, _, pairs) = join3(send_numbers, send_letters, pairs).await;
+/// assert_eq!(pairs, [(1, 'a'), (2, 'b')]);
+/// # });
+/// ```
+#[inline]
+pub fn zip<A, B>(a: Receiver<A>, b: Receiver<B>) -> Zip<A, B> {
+    Zip { a, b }
+}
+
+/// Stream for the [`zip`] function.
+#[must_use = "streams do nothing unless polled"]
+#[derive(Debug)]
+pub struct Zip<A, B> {
+    a: Receiver<A>,
+    b: Receiver<B>,
+}
+
+impl<A, B> Zip<A, B> {
+    /// Check if both senders are still connected.
+    #[inline]
+    #[must_use]
+    pub fn is_connected(&self) -> bool {
+        self.a.is_connected() && self.b.is_connected()
+    }
+
+    /// Get references to the underlying receivers.
+    #[inline]
+    #[must_use]
+    pub fn get_ref(&self) -> (&Receiver<A>, &Receiver<B>) {
+        (&self.a, &self.b)
+    }
+
+    /// Consume this stream, returning the underlying receivers. An item that
+    /// was peeked while waiting for the other receiver can still be received.
+    #[inline]
+    #[must_use]
+    pub fn into_inner(self) -> (Receiver<A>, Receiver<B>) {
+        (self.a, self.b)
+    }
+}
+
+impl<A, B> Stream for Zip<A, B> {
+    type Item = (A, B);
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<(A, B)>> {
+        let this = &mut *self;
+
+        // Both sides are always polled, so that both wakers are registered
+        let a = this.a.poll_peek_ready(cx);
+        let b = this.b.poll_peek_ready(cx);
+
+        match (a, b) {
+            (Poll::Ready(None), _) | (_, Poll::Ready(None)) => Poll::Ready(None),
+            (Poll::Ready(Some(())), Poll::Ready(Some(()))) => Poll::Ready(Some((
+                this.a.take_staged().expect("item was peeked"),
+                this.b.take_staged().expect("item was peeked"),
+            ))),
+            _ => Poll::Pending,
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (a_lower, a_upper) = self.a.size_hint();
+        let (b_lower, b_upper) = self.b.size_hint();
+
+        let upper = match (a_upper, b_upper) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (upper, None) | (None, upper) => upper,
+        };
+
+        (a_lower.min(b_lower), upper)
+    }
+}
+
+impl<A, B> FusedStream for Zip<A, B> {
+    #[inline]
+    fn is_terminated(&self) -> bool {
+        self.a.is_terminated() || self.b.is_terminated()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering::SeqCst},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use futures::StreamExt;
+
+    use crate::channel;
+
+    #[tokio::test]
+    async fn fast_producer_is_backpressured() {
+        let (mut fast_sender, fast_receiver) = channel();
+        let (mut slow_sender, slow_receiver) = channel();
+        let fast_sent = Arc::new(AtomicUsize::new(0));
+
+        let fast_task = tokio::task::spawn({
+            let fast_sent = fast_sent.clone();
+            async move {
+                for i in 0..5 {
+                    fast_sender.send(i).await.unwrap();
+                    fast_sent.fetch_add(1, SeqCst);
+                }
+            }
+        });
+
+        let slow_task = tokio::task::spawn(async move {
+            for i in 0..5 {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+                slow_sender.send(i * 10).await.unwrap();
+            }
+        });
+
+        let mut pairs = super::zip(fast_receiver, slow_receiver);
+        let mut received = 0;
+
+        while let Some((fast, slow)) = pairs.next().await {
+            assert_eq!((fast, slow), (received, received * 10));
+            received += 1;
+
+            // The fast producer can only be one send ahead: the one that was
+            // just released, but hasn't been observed completing yet.
+            assert!(fast_sent.load(SeqCst) <= received as usize);
+        }
+
+        assert_eq!(received, 5);
+        fast_task.await.unwrap();
+        slow_task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn peeked_item_survives_disconnect() {
+        let (mut a_sender, a_receiver) = channel();
+        let (b_sender, b_receiver) = channel::<i32>();
+
+        let a_task = tokio::task::spawn(async move { a_sender.send(1).await });
+
+        let mut pairs = super::zip(a_receiver, b_receiver);
+        tokio::task::yield_now().await;
+        drop(b_sender);
+
+        assert_eq!(pairs.next().await, None);
+
+        let (mut a_receiver, _) = pairs.into_inner();
+        assert_eq!(a_receiver.recv().await, Some(1));
+        a_task.await.unwrap().unwrap();
+    }
+}
@@ -0,0 +1,518 @@
+/*!
+Fault-injection scaffolding for testing the channel protocol.
+
+The protocol's unsafe core has several branches that are only reachable
+through specific interleavings, such as a sender leaking its send future
+while the receiver is holding its item pointer. This module makes those
+interleavings scriptable from a single-threaded test:
+
+- [`on`] installs a one-shot hook that runs at a specific [`Point`] inside
+  the protocol, via the `inject!` macro. Hooks can sleep (to delay the
+  next step) or drive the other half of the channel (to reorder steps).
+- [`TestChannel`] wraps both halves in cloneable handles with counting
+  wakers, so that tests (and hooks) can poll either half on demand, poll
+  spuriously, or simulate a leaked send.
+
+It also installs a global allocator that counts allocations per thread, so
+that tests can verify the crate's allocation guarantees with
+[`allocations`].
+*/
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::{Cell, RefCell},
+    future::Future,
+    mem,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering::SeqCst},
+        Arc, Mutex, MutexGuard,
+    },
+    task::{Context, Poll, Wake, Waker},
+};
+
+use futures_util::{FutureExt, StreamExt};
+
+use crate::{channel, Receiver, SendError, SendFut, Sender};
+
+/// A point in the protocol where a hook can be injected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Point {
+    /// The sender has just published its item pointer.
+    SenderStaged,
+
+    /// The receiver has just swapped a sender's item pointer out of the slot,
+    /// and hasn't yet looked at the item.
+    ReceiverAcquired,
+
+    /// The receiver is about to restore a sender's item pointer.
+    ReceiverRestoring,
+
+    /// The receiver is being dropped, and has marked the channel as closing,
+    /// but hasn't disconnected yet.
+    ReceiverClosing,
+}
+
+type Hook = Box<dyn FnOnce()>;
+
+thread_local! {
+    static HOOKS: RefCell<Vec<(Point, Hook)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Install a hook that runs the next time this thread reaches `point`.
+pub(crate) fn on(point: Point, hook: impl FnOnce() + 'static) {
+    HOOKS.with(|hooks| hooks.borrow_mut().push((point, Box::new(hook))));
+}
+
+/// Run the earliest installed hook for `point`, if any. This is called by
+/// the `inject!` macro.
+pub(crate) fn inject(point: Point) {
+    // The hook is removed before it runs, so that it can reentrantly reach
+    // injection points (and install more hooks) itself.
+    let hook = HOOKS.with(|hooks| {
+        let mut hooks = hooks.borrow_mut();
+        let index = hooks.iter().position(|&(p, _)| p == point)?;
+        Some(hooks.remove(index).1)
+    });
+
+    if let Some(hook) = hook {
+        hook()
+    }
+}
+
+/// Allocator that counts the allocations made by each thread.
+struct CountingAlloc;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAlloc = CountingAlloc;
+
+/// The number of allocations (including reallocations) made so far by the
+/// current thread.
+pub(crate) fn allocations() -> usize {
+    ALLOCATIONS.with(Cell::get)
+}
+
+/// Waker that counts how many times it was woken
+#[derive(Debug, Default)]
+pub(crate) struct CountingWaker(AtomicUsize);
+
+impl Wake for CountingWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.fetch_add(1, SeqCst);
+    }
+}
+
+impl CountingWaker {
+    pub(crate) fn count(&self) -> usize {
+        self.0.load(SeqCst)
+    }
+}
+
+/// A waker that polls its future as soon as it's woken, so that a sender
+/// stages its next item before the receiver's poll returns.
+pub(crate) struct InlineWaker {
+    future: Mutex<Option<Pin<Box<dyn Future<Output = ()> + Send>>>>,
+}
+
+impl InlineWaker {
+    /// Poll `future` for the first time, and then every time it's woken.
+    pub(crate) fn spawn(future: impl Future<Output = ()> + Send + 'static) -> Arc<Self> {
+        let task = Arc::new(Self {
+            future: Mutex::new(Some(Box::pin(future))),
+        });
+
+        task.wake_by_ref();
+        task
+    }
+
+    pub(crate) fn is_finished(&self) -> bool {
+        lock(&self.future).is_none()
+    }
+}
+
+impl Wake for InlineWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        // The future may wake itself while it's being polled
+        let Ok(mut future) = self.future.try_lock() else { return };
+        let waker = Waker::from(self.clone());
+
+        if let Some(fut) = future.as_mut() {
+            if fut.poll_unpin(&mut Context::from_waker(&waker)).is_ready() {
+                *future = None;
+            }
+        }
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+/// A channel whose halves are driven manually by a test. Each half is a
+/// cheaply cloneable handle, so that it can be captured by hooks.
+pub(crate) struct TestChannel<T: 'static> {
+    pub sender: TestSender<T>,
+    pub receiver: TestReceiver<T>,
+}
+
+impl<T: Send + 'static> TestChannel<T> {
+    pub(crate) fn new() -> Self {
+        let (sender, receiver) = channel();
+
+        Self {
+            sender: TestSender {
+                // The sender is leaked so that send futures can borrow it
+                // for `'static`, and themselves be leaked.
+                sender: Box::leak(Box::new(sender)),
+                send: Arc::default(),
+                waker: Arc::default(),
+            },
+            receiver: TestReceiver {
+                receiver: Arc::new(Mutex::new(Some(receiver))),
+                waker: Arc::default(),
+            },
+        }
+    }
+}
+
+impl<T> Drop for TestChannel<T> {
+    fn drop(&mut self) {
+        // Don't let unused hooks leak into other tests
+        HOOKS.with(|hooks| hooks.borrow_mut().clear());
+    }
+}
+
+type PinnedSend<T> = Pin<Box<SendFut<'static, T>>>;
+
+/// The sending half of a [`TestChannel`], with at most one send in progress.
+pub(crate) struct TestSender<T: 'static> {
+    sender: &'static Sender<T>,
+    send: Arc<Mutex<Option<PinnedSend<T>>>>,
+    waker: Arc<CountingWaker>,
+}
+
+impl<T> Clone for TestSender<T> {
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender,
+            send: self.send.clone(),
+            waker: self.waker.clone(),
+        }
+    }
+}
+
+impl<T> TestSender<T> {
+    /// Start sending `item`, replacing (and dropping) any send in progress,
+    /// and poll it once.
+    pub(crate) fn start(&self, item: T) -> Poll<Result<(), SendError<T>>> {
+        let send = Box::pin(SendFut::new(&self.sender.inner, item));
+        drop(lock(&self.send).replace(send));
+        self.poll()
+    }
+
+    /// Poll the send in progress. This is a spurious poll if the sender
+    /// hasn't been woken since it was last polled.
+    pub(crate) fn poll(&self) -> Poll<Result<(), SendError<T>>> {
+        let waker = Waker::from(self.waker.clone());
+        let mut cx = Context::from_waker(&waker);
+
+        let mut send = lock(&self.send);
+        let poll = send
+            .as_mut()
+            .expect("no send in progress")
+            .poll_unpin(&mut cx);
+
+        if poll.is_ready() {
+            *send = None;
+        }
+
+        poll
+    }
+
+    /// Simulate a leaked send: the send in progress is forgotten without
+    /// running its destructor, so its item pointer is never reclaimed.
+    pub(crate) fn leak(&self) {
+        mem::forget(lock(&self.send).take().expect("no send in progress"));
+    }
+
+    /// The number of times the sender has been woken.
+    pub(crate) fn wakes(&self) -> usize {
+        self.waker.count()
+    }
+}
+
+/// The receiving half of a [`TestChannel`].
+pub(crate) struct TestReceiver<T> {
+    receiver: Arc<Mutex<Option<Receiver<T>>>>,
+    waker: Arc<CountingWaker>,
+}
+
+impl<T> Clone for TestReceiver<T> {
+    fn clone(&self) -> Self {
+        Self {
+            receiver: self.receiver.clone(),
+            waker: self.waker.clone(),
+        }
+    }
+}
+
+impl<T> TestReceiver<T> {
+    /// Poll the receiver for the next item.
+    pub(crate) fn poll(&self) -> Poll<Option<T>> {
+        let waker = Waker::from(self.waker.clone());
+        let mut cx = Context::from_waker(&waker);
+
+        lock(&self.receiver)
+            .as_mut()
+            .expect("receiver was dropped")
+            .poll_next_unpin(&mut cx)
+    }
+
+    /// Drop the receiver, disconnecting the channel.
+    pub(crate) fn disconnect(&self) {
+        drop(lock(&self.receiver).take());
+    }
+
+    /// The number of times the receiver has been woken.
+    pub(crate) fn wakes(&self) -> usize {
+        self.waker.count()
+    }
+}
+
+// These tests target the protocol branches that are only reachable through
+// specific interleavings.
+#[cfg(test)]
+mod tests {
+    use std::{
+        cell::Cell,
+        rc::Rc,
+        sync::{atomic::Ordering::Relaxed, mpsc},
+        task::Poll,
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use cool_asserts::assert_matches;
+
+    use crate::{channel, SendError};
+
+    use super::{on, Point, TestChannel, TestSender};
+
+    #[test]
+    fn spurious_sender_polls() {
+        let chan = TestChannel::new();
+
+        assert!(chan.sender.start(1).is_pending());
+        for _ in 0..3 {
+            assert!(chan.sender.poll().is_pending());
+        }
+        assert_eq!(chan.receiver.wakes(), 0);
+        assert_eq!(chan.sender.wakes(), 0);
+
+        assert_eq!(chan.receiver.poll(), Poll::Ready(Some(1)));
+        assert_eq!(chan.sender.wakes(), 1);
+        assert_matches!(chan.sender.poll(), Poll::Ready(Ok(())));
+    }
+
+    #[test]
+    fn receive_while_sender_is_staging() {
+        let chan = TestChannel::new();
+        assert!(chan.receiver.poll().is_pending());
+
+        // The receiver takes the item before the sender's poll has even
+        // finished.
+        let receiver = chan.receiver.clone();
+        on(Point::SenderStaged, move || {
+            assert_eq!(receiver.poll(), Poll::Ready(Some(1)))
+        });
+
+        assert!(chan.sender.start(1).is_pending());
+        assert_matches!(chan.sender.poll(), Poll::Ready(Ok(())));
+    }
+
+    #[test]
+    fn send_fails_while_receiver_is_closing() {
+        let chan = TestChannel::new();
+
+        // The receiver has started dropping, but hasn't disconnected yet, so
+        // the send could still lock the channel; it fails instead of staging.
+        let sender = chan.sender.clone();
+        on(Point::ReceiverClosing, move || {
+            assert_matches!(sender.start(1), Poll::Ready(Err(SendError(1))))
+        });
+
+        chan.receiver.disconnect();
+        assert_eq!(chan.sender.wakes(), 0);
+    }
+
+    #[test]
+    fn staged_send_fails_while_receiver_is_closing() {
+        let chan = TestChannel::new();
+        assert!(chan.sender.start(1).is_pending());
+
+        // A send that was already staged is withdrawn on its next poll
+        let sender = chan.sender.clone();
+        on(Point::ReceiverClosing, move || {
+            assert_matches!(sender.poll(), Poll::Ready(Err(SendError(1))))
+        });
+
+        chan.receiver.disconnect();
+    }
+
+    #[test]
+    fn send_racing_receiver_drop_returns_item() {
+        for i in 0..1000 {
+            let (mut sender, receiver) = channel();
+            let dropper = thread::spawn(move || drop(receiver));
+
+            // However the race goes, the receiver never takes the item, so
+            // it always comes back.
+            let result = futures::executor::block_on(sender.send(i));
+            assert_matches!(result, Err(SendError(item)) => assert_eq!(item, i));
+            dropper.join().unwrap();
+        }
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "audit failed")]
+    fn audit_detects_stale_taken_pointer() {
+        let chan = TestChannel::<i32>::new();
+
+        // Report the staged pointer as taken without taking it out of the
+        // slot, as a stale `taken_item` from an earlier send would
+        let sender = chan.sender.clone();
+        on(Point::SenderStaged, move || {
+            let lock = sender.sender.inner.lock().unwrap();
+            let staged = lock.sent_item.load(Relaxed);
+            lock.taken_item.store(staged, Relaxed);
+        });
+
+        let _ = chan.sender.start(1);
+    }
+
+    #[test]
+    fn leaked_send_replaced_while_receiver_holds_item() {
+        let chan = TestChannel::new();
+        assert!(chan.sender.start(1).is_pending());
+        chan.sender.leak();
+
+        // While the receiver holds the leaked send's pointer, a new send
+        // publishes its own. The receiver still gets the leaked item, but
+        // can't restore the old pointer, so it leaves the new one in place.
+        let sender = chan.sender.clone();
+        on(Point::ReceiverAcquired, move || {
+            assert!(sender.start(2).is_pending())
+        });
+
+        assert_eq!(chan.receiver.poll(), Poll::Ready(Some(1)));
+        assert!(chan.sender.poll().is_pending());
+        assert_eq!(chan.receiver.poll(), Poll::Ready(Some(2)));
+        assert_matches!(chan.sender.poll(), Poll::Ready(Ok(())));
+    }
+
+    /// Install a hook that, each time the receiver acquires an item pointer,
+    /// leaks the send it belongs to and stages a new one, until `remaining`
+    /// runs out.
+    fn leak_storm(sender: TestSender<u32>, next: u32, remaining: u32, acquired: Rc<Cell<u32>>) {
+        if remaining == 0 {
+            return;
+        }
+
+        on(Point::ReceiverAcquired, move || {
+            acquired.set(acquired.get() + 1);
+            sender.leak();
+            assert!(sender.start(next).is_pending());
+            leak_storm(sender, next + 1, remaining - 1, acquired);
+        });
+    }
+
+    #[test]
+    fn leak_storm_is_bounded_per_poll() {
+        let chan = TestChannel::new();
+        let acquired = Rc::new(Cell::new(0));
+
+        assert!(chan.sender.start(0).is_pending());
+        leak_storm(chan.sender.clone(), 1, 100, acquired.clone());
+
+        // However many leaked sends replace each other, each poll acquires a
+        // single item pointer, and returns instead of looping to pick up the
+        // newly staged item.
+        for item in 0..100 {
+            assert_eq!(chan.receiver.poll(), Poll::Ready(Some(item)));
+            assert_eq!(acquired.get(), item + 1);
+        }
+
+        assert_eq!(chan.receiver.poll(), Poll::Ready(Some(100)));
+        assert_matches!(chan.sender.poll(), Poll::Ready(Ok(())));
+    }
+
+    #[test]
+    fn delayed_receiver_restore() {
+        let chan = TestChannel::new();
+
+        // The receiver holds the pointer for a while before handing it back;
+        // meanwhile, the sender is polled on another thread, and must wait
+        // for the pointer to come back before it can see that the item was
+        // taken. The second send likely reuses the first one's address, so
+        // this also checks that the first send's handed back pointer isn't
+        // mistaken for the second's.
+        let delay = Duration::from_millis(30);
+
+        for item in [1, 2] {
+            assert!(chan.sender.start(item).is_pending());
+            let (holding_tx, holding_rx) = mpsc::channel();
+
+            thread::scope(|scope| {
+                let receiver = scope.spawn(|| {
+                    // Hooks are per-thread, so install it here
+                    on(Point::ReceiverRestoring, move || {
+                        holding_tx.send(Instant::now()).unwrap();
+                        thread::sleep(delay);
+                    });
+                    chan.receiver.poll()
+                });
+
+                let holding_since = holding_rx.recv().unwrap();
+                assert_matches!(chan.sender.poll(), Poll::Ready(Ok(())));
+                assert!(holding_since.elapsed() >= delay);
+
+                assert_eq!(receiver.join().unwrap(), Poll::Ready(Some(item)));
+            });
+        }
+    }
+
+    #[test]
+    fn disconnect_with_send_in_progress() {
+        let chan = TestChannel::new();
+        assert!(chan.sender.start(1).is_pending());
+        chan.receiver.disconnect();
+
+        assert_eq!(chan.sender.wakes(), 1);
+        assert_matches!(chan.sender.poll(), Poll::Ready(Err(SendError(1))));
+    }
+}
@@ -0,0 +1,299 @@
+/*!
+Send and receive operations with time limits.
+*/
+
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use futures_util::{FutureExt, StreamExt};
+use pin_project::{pin_project, pinned_drop};
+
+use crate::{timer::Sleep, Receiver, SendFut, SendTimeoutError, Sender};
+
+impl<T> Sender<T> {
+    /// Asynchronously send an item to the receiver, giving up if the receiver
+    /// doesn't take it within `timeout`.
+    ///
+    /// This behaves like [`send`][Sender::send], except that if the timeout
+    /// elapses first, the item is reclaimed and returned in a
+    /// [`SendTimeoutError::Timeout`]. If the receiver takes the item at the
+    /// same moment the timeout elapses, the send is reported as a success;
+    /// the item is never both delivered and returned.
+    #[inline]
+    pub fn send_timeout(&mut self, item: T, timeout: Duration) -> SendTimeoutFut<'_, T> {
+        self.send_deadline(item, Instant::now() + timeout)
+    }
+
+    /// Asynchronously send an item to the receiver, giving up if the receiver
+    /// doesn't take it by `deadline`.
+    ///
+    /// This is the same as [`send_timeout`][Sender::send_timeout], but with
+    /// an absolute deadline, which composes better when a single operation
+    /// spans several awaits.
+    #[inline]
+    pub fn send_deadline(&mut self, item: T, deadline: Instant) -> SendTimeoutFut<'_, T> {
+        SendTimeoutFut {
+            send: self.send(item),
+            sleep: Sleep::until(deadline),
+        }
+    }
+}
+
+/// Future for the [`send_timeout`][Sender::send_timeout] and
+/// [`send_deadline`][Sender::send_deadline] methods. See their documentation
+/// for details.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+#[pin_project]
+#[derive(Debug)]
+pub struct SendTimeoutFut<'a, T> {
+    #[pin]
+    send: SendFut<'a, T>,
+    sleep: Sleep,
+}
+
+impl<T> Future for SendTimeoutFut<'_, T> {
+    type Output = Result<(), SendTimeoutError<T>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut this = self.project();
+
+        if let Poll::Ready(result) = this.send.as_mut().poll(cx) {
+            return Poll::Ready(result.map_err(SendTimeoutError::from));
+        }
+
+        futures_util::ready!(this.sleep.poll_unpin(cx));
+
+        Poll::Ready(match this.send.reclaim() {
+            Some(item) => Err(SendTimeoutError::Timeout(item)),
+            None => Ok(()),
+        })
+    }
+}
+
+impl<T> Receiver<T> {
+    /// Asynchronously receive items into a collection until either the
+    /// sender disconnects or `timeout` elapses, then return everything that
+    /// was received.
+    ///
+    /// Unlike [`chunks_timeout`][Receiver::chunks_timeout], this is a
+    /// one-shot collection with a hard cap on the total time spent: once the
+    /// timeout elapses, the collection is returned even if the sender has
+    /// another item ready. Any such item is left with the sender, and the
+    /// receiver can continue to be used afterwards.
+    #[inline]
+    pub fn collect_timeout<C>(&mut self, timeout: Duration) -> CollectTimeoutFut<'_, T, C>
+    where
+        C: Default + Extend<T>,
+    {
+        self.collect_deadline(Instant::now() + timeout)
+    }
+
+    /// Asynchronously receive items into a collection until either the
+    /// sender disconnects or `deadline` passes, then return everything that
+    /// was received.
+    ///
+    /// This is the same as [`collect_timeout`][Receiver::collect_timeout],
+    /// but with an absolute deadline.
+    #[inline]
+    pub fn collect_deadline<C>(&mut self, deadline: Instant) -> CollectTimeoutFut<'_, T, C>
+    where
+        C: Default + Extend<T>,
+    {
+        CollectTimeoutFut {
+            receiver: self,
+            sleep: Sleep::until(deadline),
+            collection: C::default(),
+        }
+    }
+}
+
+/// Future for the [`collect_timeout`][Receiver::collect_timeout] and
+/// [`collect_deadline`][Receiver::collect_deadline] methods. See their
+/// documentation for details.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+#[pin_project(PinnedDrop)]
+#[derive(Debug)]
+pub struct CollectTimeoutFut<'a, T, C> {
+    receiver: &'a mut Receiver<T>,
+    sleep: Sleep,
+    collection: C,
+}
+
+impl<T, C> Future for CollectTimeoutFut<'_, T, C>
+where
+    C: Default + Extend<T>,
+{
+    type Output = C;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<C> {
+        let this = self.project();
+
+        // The deadline is checked before every item, so that a sender that
+        // always has another item ready can't extend the collection past it.
+        loop {
+            if this.sleep.poll_unpin(cx).is_ready() {
+                return Poll::Ready(std::mem::take(this.collection));
+            }
+
+            match futures_util::ready!(this.receiver.poll_next_unpin(cx)) {
+                Some(item) => this.collection.extend(Some(item)),
+                None => return Poll::Ready(std::mem::take(this.collection)),
+            }
+        }
+    }
+}
+
+#[pinned_drop]
+impl<T, C> PinnedDrop for CollectTimeoutFut<'_, T, C> {
+    #[inline]
+    fn drop(self: Pin<&mut Self>) {
+        let Some(lock) = self.receiver.inner.lock() else { return };
+        lock.stop_waiting()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        task::{Context, Waker},
+        time::{Duration, Instant},
+    };
+
+    use cool_asserts::assert_matches;
+    use futures::{executor::block_on, FutureExt, StreamExt};
+
+    use crate::{channel, send_to_any, SendTimeoutError, TryRecvError};
+
+    #[tokio::test]
+    async fn delivered_before_timeout() {
+        let (mut sender, mut receiver) = channel();
+
+        let receiver_task = tokio::task::spawn(async move { receiver.recv().await });
+
+        sender
+            .send_timeout(1, Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert_eq!(receiver_task.await.unwrap(), Some(1));
+    }
+
+    #[tokio::test]
+    async fn timeout_returns_item() {
+        let (mut sender, mut receiver) = channel();
+
+        let start = Instant::now();
+        assert_matches!(
+            sender.send_timeout(1, Duration::from_millis(20)).await,
+            Err(SendTimeoutError::Timeout(1))
+        );
+        assert!(start.elapsed() >= Duration::from_millis(20));
+
+        // The item was reclaimed, so the channel is still usable
+        let receiver_task = tokio::task::spawn(async move { receiver.recv().await });
+        sender.send(2).await.unwrap();
+        assert_eq!(receiver_task.await.unwrap(), Some(2));
+    }
+
+    #[tokio::test]
+    async fn deadline_spans_multiple_sends() {
+        let (mut sender, mut receiver) = channel();
+        let deadline = Instant::now() + Duration::from_millis(30);
+
+        let receiver_task = tokio::task::spawn(async move {
+            let item = receiver.recv().await;
+            (item, receiver)
+        });
+
+        sender.send_deadline(1, deadline).await.unwrap();
+        let (item, receiver) = receiver_task.await.unwrap();
+        assert_eq!(item, Some(1));
+
+        assert_matches!(
+            sender.send_deadline(2, deadline).await,
+            Err(SendTimeoutError::Timeout(2))
+        );
+        assert!(Instant::now() >= deadline);
+        drop(receiver);
+    }
+
+    #[tokio::test]
+    async fn collect_returns_partial_batch_at_deadline() {
+        let (mut sender, mut receiver) = channel();
+
+        let sender_task = tokio::task::spawn(async move {
+            for i in 0..3 {
+                sender.send(i).await.unwrap();
+            }
+
+            // Stalls past the deadline
+            tokio::time::sleep(Duration::from_millis(500)).await;
+            sender.send(3).await.unwrap();
+        });
+
+        let start = Instant::now();
+        let items: Vec<i32> = receiver.collect_timeout(Duration::from_millis(50)).await;
+        let elapsed = start.elapsed();
+
+        assert_eq!(items, [0, 1, 2]);
+        assert!(elapsed >= Duration::from_millis(50));
+        assert!(elapsed < Duration::from_millis(400));
+
+        // The receiver is still usable afterwards
+        assert_eq!(receiver.recv().await, Some(3));
+        sender_task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn collect_ends_at_disconnect() {
+        let (mut sender, mut receiver) = channel();
+
+        let sender_task = tokio::task::spawn(async move {
+            for i in 0..3 {
+                sender.send(i).await.unwrap();
+            }
+        });
+
+        let start = Instant::now();
+        let items: Vec<i32> = receiver.collect_timeout(Duration::from_secs(5)).await;
+
+        assert_eq!(items, [0, 1, 2]);
+        assert!(start.elapsed() < Duration::from_secs(5));
+        sender_task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn disconnect_before_timeout() {
+        let (mut sender, receiver) = channel();
+        drop(receiver);
+
+        assert_matches!(
+            sender.send_timeout(1, Duration::from_secs(5)).await,
+            Err(SendTimeoutError::Disconnected(1))
+        );
+    }
+
+    #[test]
+    fn timed_out_collect_stops_waiting() {
+        let (sender_a, mut receiver_a) = channel();
+        let (sender_b, mut receiver_b) = channel();
+        let mut senders = [sender_a, sender_b];
+
+        let items: Vec<i32> = block_on(receiver_a.collect_timeout(Duration::from_millis(10)));
+        assert!(items.is_empty());
+
+        // Only the second receiver is still waiting, so it gets the item
+        let mut cx = Context::from_waker(Waker::noop());
+        let mut send = Box::pin(send_to_any(&mut senders, 1));
+        assert!(send.as_mut().now_or_never().is_none());
+        assert!(receiver_b.poll_next_unpin(&mut cx).is_pending());
+        assert!(send.as_mut().now_or_never().is_none());
+
+        assert_eq!(receiver_a.try_recv(), Err(TryRecvError::Empty));
+        assert_eq!(receiver_b.try_recv(), Ok(1));
+        assert_matches!(send.now_or_never(), Some(Ok(1)));
+    }
+}
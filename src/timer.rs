@@ -0,0 +1,234 @@
+/*!
+A minimal, runtime-agnostic timer, used to implement the time-based features
+of this crate without depending on any particular async runtime.
+
+All sleeps share a single background thread, which is spawned lazily the first
+time a sleep is registered. The thread keeps a heap of deadlines and wakes
+each sleep's task once its deadline has passed.
+*/
+
+use std::{
+    cmp::Ordering as CmpOrdering,
+    collections::BinaryHeap,
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{
+            AtomicBool,
+            Ordering::{Acquire, Release},
+        },
+        Arc, Condvar, Mutex, OnceLock, Weak,
+    },
+    task::{Context, Poll},
+    thread,
+    time::{Duration, Instant},
+};
+
+use futures_util::task::AtomicWaker;
+
+/// Shared state between a [`Sleep`] and the timer thread.
+#[derive(Debug, Default)]
+struct Entry {
+    fired: AtomicBool,
+    waker: AtomicWaker,
+}
+
+/// A deadline in the timer's queue. Holds a weak reference so that dropped
+/// sleeps don't keep their entries alive.
+#[derive(Debug)]
+struct Scheduled {
+    deadline: Instant,
+    entry: Weak<Entry>,
+}
+
+// `BinaryHeap` is a max-heap, so these are reversed to put the earliest
+// deadline at the top.
+impl Ord for Scheduled {
+    fn cmp(&self, other: &Self) -> CmpOrdering {
+        other.deadline.cmp(&self.deadline)
+    }
+}
+
+impl PartialOrd for Scheduled {
+    fn partial_cmp(&self, other: &Self) -> Option<CmpOrdering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Scheduled {
+    fn eq(&self, other: &Self) -> bool {
+        self.deadline == other.deadline
+    }
+}
+
+impl Eq for Scheduled {}
+
+#[derive(Debug, Default)]
+struct Timer {
+    queue: Mutex<BinaryHeap<Scheduled>>,
+    condvar: Condvar,
+}
+
+impl Timer {
+    /// Get the global timer, spawning its thread if necessary.
+    fn global() -> &'static Timer {
+        static TIMER: OnceLock<Timer> = OnceLock::new();
+
+        let mut spawn = false;
+        let timer = TIMER.get_or_init(|| {
+            spawn = true;
+            Timer::default()
+        });
+
+        if spawn {
+            thread::Builder::new()
+                .name("handoff-timer".to_owned())
+                .spawn(move || timer.run())
+                .expect("failed to spawn handoff timer thread");
+        }
+
+        timer
+    }
+
+    fn schedule(&self, deadline: Instant, entry: &Arc<Entry>) {
+        let mut queue = self.queue.lock().unwrap_or_else(|err| err.into_inner());
+
+        queue.push(Scheduled {
+            deadline,
+            entry: Arc::downgrade(entry),
+        });
+
+        drop(queue);
+        self.condvar.notify_one();
+    }
+
+    fn run(&self) -> ! {
+        let mut queue = self.queue.lock().unwrap_or_else(|err| err.into_inner());
+
+        loop {
+            let now = Instant::now();
+
+            while let Some(scheduled) = queue.peek() {
+                if scheduled.deadline > now {
+                    break;
+                }
+
+                if let Some(entry) = queue.pop().and_then(|s| s.entry.upgrade()) {
+                    entry.fired.store(true, Release);
+                    entry.waker.wake();
+                }
+            }
+
+            // `wait` and `wait_timeout` can both wake spuriously, but that's
+            // fine, since we check all the deadlines again each time.
+            queue = match queue.peek() {
+                Some(scheduled) => {
+                    let timeout = scheduled.deadline.saturating_duration_since(now);
+                    self.condvar
+                        .wait_timeout(queue, timeout)
+                        .unwrap_or_else(|err| err.into_inner())
+                        .0
+                }
+                None => self
+                    .condvar
+                    .wait(queue)
+                    .unwrap_or_else(|err| err.into_inner()),
+            };
+        }
+    }
+}
+
+/// The current time, as a number of nanoseconds since an arbitrary
+/// process-wide epoch. Unlike an [`Instant`], this fits in an atomic, so it
+/// can be shared between the halves of a channel without a lock.
+#[inline]
+#[must_use]
+pub(crate) fn now_nanos() -> u64 {
+    static EPOCH: OnceLock<Instant> = OnceLock::new();
+
+    let epoch = *EPOCH.get_or_init(Instant::now);
+    epoch.elapsed().as_nanos().try_into().unwrap_or(u64::MAX)
+}
+
+/// The time elapsed since `nanos`, a timestamp from [`now_nanos`].
+#[inline]
+#[must_use]
+pub(crate) fn elapsed_since(nanos: u64) -> Duration {
+    Duration::from_nanos(now_nanos().saturating_sub(nanos))
+}
+
+/// A future that completes once a deadline has passed.
+#[derive(Debug)]
+pub(crate) struct Sleep {
+    deadline: Instant,
+    entry: Option<Arc<Entry>>,
+}
+
+impl Sleep {
+    /// Create a sleep that completes at `deadline`. The timer isn't engaged
+    /// until the first time the sleep is polled.
+    #[inline]
+    #[must_use]
+    pub(crate) fn until(deadline: Instant) -> Self {
+        Self {
+            deadline,
+            entry: None,
+        }
+    }
+
+    /// Create a sleep that completes after `duration` has elapsed.
+    #[inline]
+    #[must_use]
+    pub(crate) fn after(duration: Duration) -> Self {
+        Self::until(Instant::now() + duration)
+    }
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if Instant::now() >= self.deadline {
+            return Poll::Ready(());
+        }
+
+        let deadline = self.deadline;
+        let entry = self.entry.get_or_insert_with(|| {
+            let entry = Arc::new(Entry::default());
+            Timer::global().schedule(deadline, &entry);
+            entry
+        });
+
+        // Register before checking, so that we don't miss a concurrent fire.
+        entry.waker.register(cx.waker());
+
+        match entry.fired.load(Acquire) {
+            true => Poll::Ready(()),
+            false => Poll::Pending,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::Sleep;
+
+    #[tokio::test]
+    async fn sleeps_until_deadline() {
+        let start = Instant::now();
+        Sleep::after(Duration::from_millis(30)).await;
+        assert!(start.elapsed() >= Duration::from_millis(30));
+    }
+
+    #[tokio::test]
+    async fn sleeps_fire_in_order() {
+        let start = Instant::now();
+        let long = Sleep::after(Duration::from_millis(60));
+        Sleep::after(Duration::from_millis(20)).await;
+        assert!(start.elapsed() < Duration::from_millis(60));
+        long.await;
+        assert!(start.elapsed() >= Duration::from_millis(60));
+    }
+}